@@ -0,0 +1,52 @@
+use std::{
+    env, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// The most REPL entries kept between sessions.
+pub const HISTORY_LIMIT: usize = 1000;
+
+const HISTORY_FILE: &str = ".mylang_history";
+
+/// `~/.mylang_history`, or `None` when no home directory is set.
+pub fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(|home| Path::new(&home).join(HISTORY_FILE))
+}
+
+/// Reads up to [`HISTORY_LIMIT`] of the most recent entries, one per line. A missing file is an
+/// empty history.
+pub fn load_history(path: &Path) -> io::Result<Vec<String>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let entries: Vec<String> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok(most_recent(entries))
+}
+
+/// Writes up to [`HISTORY_LIMIT`] of the most recent entries, replacing the file.
+pub fn save_history(path: &Path, entries: &[String]) -> io::Result<()> {
+    let entries: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry.trim_end())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let mut contents = most_recent(entries).join("\n");
+    contents.push('\n');
+    fs::write(path, contents)
+}
+
+fn most_recent<T>(mut entries: Vec<T>) -> Vec<T> {
+    let excess = entries.len().saturating_sub(HISTORY_LIMIT);
+    entries.drain(..excess);
+    entries
+}
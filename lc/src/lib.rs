@@ -0,0 +1,7 @@
+mod history;
+mod repl;
+mod run;
+
+pub use crate::history::*;
+pub use crate::repl::*;
+pub use crate::run::*;
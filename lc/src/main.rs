@@ -1,80 +1,150 @@
 use std::{
     env,
-    fs::File,
-    io::{self, Read, Write},
-    path::Path,
+    io::{self, IsTerminal, Write},
     process::ExitCode,
 };
 
 use anyhow::Result;
+use rustyline::{error::ReadlineError, Config, DefaultEditor};
 
+use lc::*;
 use lc_core::*;
 use lc_interpreter::*;
 
-fn run(input: String, context: &mut Interpreter) -> Result<()> {
-    let mut issues = TranslationErrors::new();
-
-    // Lexing
-    let mut scanner = Scanner::new(input);
-    let (tokens, mut errs) = scanner.scan_tokens();
-    issues.merge(&mut errs);
-
-    // Parsing
-    let mut parser = Parser::new(tokens);
-    let (statements, mut errs) = parser.parse();
-    issues.merge(&mut errs);
-
-    // Resolving and binding
-    let mut resolver = Resolver::new(context);
-    let (_, mut errs) = resolver.resolve(&statements);
-    issues.merge(&mut errs);
-
-    // Execution
-    issues.check()?;
-    context.interpret(statements)?;
-    Ok(())
-}
-
-fn run_file(filename: String) -> Result<()> {
-    let path = Path::new(filename.as_str());
-    let mut file = File::open(path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-
+fn run_file(filename: String, options: Options) -> ExitCode {
+    let contents = match read_file(&filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprint!("{}", renderer().render(&e));
+            return ExitCode::FAILURE;
+        }
+    };
+    let source_map = SourceMap::new(&contents);
     let output = &mut io::stdout();
-    run(contents, &mut Interpreter::new(output))
+    if let Err(e) = run(contents, &mut Interpreter::new(output), options) {
+        eprint!("{}", renderer().with_source(&source_map).render(&e));
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
 }
 
-fn run_prompt() -> Result<()> {
+fn run_prompt(options: Options) -> Result<()> {
     let output = &mut io::stdout();
     let mut context = Interpreter::new(output);
+    if io::stdin().is_terminal() {
+        return run_editor(&mut context, options);
+    }
     loop {
-        let mut buffer = String::new();
-        print!("> ");
-        io::stdout().flush()?;
-        let input_size = io::stdin().read_line(&mut buffer)?;
-        if input_size == 0 {
-            // Windows: Ctrl+Z, Unix: Ctrl+D
+        // Windows: Ctrl+Z, Unix: Ctrl+D
+        let Some(line) = prompt_line("> ")? else {
             return Ok(());
+        };
+        let input = read_complete(line, || prompt_line("... "))?;
+        if run_line(input, &mut context, options)? == Flow::Quit {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads a line from stdin after showing `prompt`, or `None` at the end of the input.
+fn prompt_line(prompt: &str) -> io::Result<Option<String>> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut buffer = String::new();
+    match io::stdin().read_line(&mut buffer)? {
+        0 => Ok(None),
+        _ => Ok(Some(buffer)),
+    }
+}
+
+/// The interactive prompt, with line editing and history kept in `~/.mylang_history`.
+fn run_editor(context: &mut Interpreter, options: Options) -> Result<()> {
+    let config = Config::builder()
+        .max_history_size(HISTORY_LIMIT)?
+        .auto_add_history(true)
+        .build();
+    let mut editor = DefaultEditor::with_config(config)?;
+    let path = history_path();
+    // An unreadable history shouldn't keep the REPL from starting
+    if let Some(entries) = path.as_deref().and_then(|path| load_history(path).ok()) {
+        for entry in entries {
+            editor.add_history_entry(entry)?;
         }
-        if let Err(e) = run(buffer, &mut context) {
-            eprint!("{}", e);
+    }
+    loop {
+        // Unfinished input like `fn f() {` continues on the following lines
+        let input = editor.readline("> ").and_then(|line| {
+            read_complete(line, || match editor.readline("... ") {
+                Err(ReadlineError::Eof) => Ok(None),
+                line => line.map(Some),
+            })
+        });
+        match input {
+            Ok(line) => {
+                if run_line(line, context, options)? == Flow::Quit {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
         }
     }
+    if let Some(path) = path {
+        let entries: Vec<String> = editor.history().iter().cloned().collect();
+        save_history(&path, &entries)?;
+    }
+    Ok(())
+}
+
+fn run_line(line: String, context: &mut Interpreter, options: Options) -> Result<Flow> {
+    if let Some(command) = Command::parse(&line) {
+        let flow = dispatch(
+            &command,
+            context,
+            renderer(),
+            &mut io::stdout(),
+            &mut io::stderr(),
+        )?;
+        return Ok(flow);
+    }
+    let source_map = SourceMap::new(&line);
+    match run_echo(line, context, options) {
+        // Like Python's REPL, a null result isn't echoed
+        Ok(Some(Value::Literal(Literal::Null)) | None) => {}
+        Ok(Some(value)) => println!("{}", value.as_str()),
+        Err(e) => eprint!("{}", renderer().with_source(&source_map).render(&e)),
+    }
+    Ok(Flow::Continue)
 }
 
 fn main() -> ExitCode {
-    if env::args().len() > 2 {
-        eprintln!("Usage: mylang [script]");
-        return ExitCode::FAILURE;
+    with_interpreter_stack(start)
+}
+
+fn start() -> ExitCode {
+    let mut options = Options::default();
+    let mut script = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--dump-tokens" => options.dump_tokens = true,
+            "--dump-tree" => options.dump_tree = true,
+            "--dump-ast" => options.dump_ast = true,
+            "--format" => options.format = true,
+            _ if script.is_none() && !arg.starts_with("--") => script = Some(arg),
+            _ => {
+                eprintln!(
+                    "Usage: mylang [--dump-tokens] [--dump-tree] [--dump-ast] [--format] [script]"
+                );
+                return ExitCode::FAILURE;
+            }
+        }
     }
-    let result = if env::args().len() == 2 {
-        run_file(env::args().nth(1).unwrap())
-    } else {
-        run_prompt()
-    };
-    if let Err(e) = result {
-        eprint!("{e}");
+    if let Some(script) = script {
+        return run_file(script, options);
+    }
+    if let Err(e) = run_prompt(options) {
+        eprint!("{}", renderer().render(&e));
         return ExitCode::FAILURE;
     }
     ExitCode::SUCCESS
@@ -0,0 +1,122 @@
+use std::io::{self, Write};
+
+use lc_core::*;
+use lc_interpreter::*;
+
+use crate::*;
+
+const HELP: &str = "\
+:env    list the global definitions
+:help   show this message
+:load   run a file in the current session, e.g. :load lib.lc
+:quit   exit the REPL
+:reset  discard all definitions
+";
+
+/// A REPL command, entered as a line starting with `:`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Env,
+    Help,
+    Load(String),
+    Quit,
+    Reset,
+    Unknown(String),
+}
+impl Command {
+    /// Returns `None` for lines that should be run as code.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        Some(match name {
+            "env" => Command::Env,
+            "help" => Command::Help,
+            "load" => Command::Load(argument.trim().to_string()),
+            "quit" => Command::Quit,
+            "reset" => Command::Reset,
+            _ => Command::Unknown(line.to_string()),
+        })
+    }
+}
+
+/// Whether the REPL should keep reading input after a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Whether `input` is code that stopped partway through, like `fn f() {`, so the REPL should
+/// read more lines before running it.
+pub fn is_incomplete(input: &str) -> bool {
+    if Command::parse(input).is_some() {
+        return false;
+    }
+    let (tokens, _) = Scanner::new(input.to_string()).scan_tokens();
+    let mut parser = Parser::new(tokens);
+    parser.parse();
+    parser.is_incomplete()
+}
+
+/// Appends lines from `next_line` to `input` for as long as it [`is_incomplete`], stopping
+/// early if `next_line` runs out.
+pub fn read_complete<E>(
+    mut input: String,
+    mut next_line: impl FnMut() -> Result<Option<String>, E>,
+) -> Result<String, E> {
+    while is_incomplete(&input) {
+        let Some(line) = next_line()? else {
+            break;
+        };
+        if !input.ends_with('\n') {
+            input.push('\n');
+        }
+        input.push_str(&line);
+    }
+    Ok(input)
+}
+
+/// Runs a command against the session, writing its output to `out`. Errors from `:load`ed
+/// files are rendered to `err` rather than ending the session.
+pub fn dispatch(
+    command: &Command,
+    context: &mut Interpreter,
+    renderer: Renderer,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> io::Result<Flow> {
+    match command {
+        Command::Env => {
+            let mut globals: Vec<_> = context
+                .environment
+                .globals()
+                .bindings()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.as_str()))
+                .collect();
+            globals.sort();
+            for (name, value) in globals {
+                writeln!(out, "{} = {}", name, value)?;
+            }
+        }
+        Command::Help => write!(out, "{}", HELP)?,
+        Command::Load(path) if path.is_empty() => writeln!(out, "Usage: :load <path>")?,
+        Command::Load(path) => match read_file(path) {
+            Ok(contents) => {
+                let source_map = SourceMap::new(&contents);
+                if let Err(e) = run(contents, context, Options::default()) {
+                    write!(err, "{}", renderer.with_source(&source_map).render(&e))?;
+                }
+            }
+            Err(e) => write!(err, "{}", renderer.render(&e))?,
+        },
+        Command::Quit => return Ok(Flow::Quit),
+        Command::Reset => context.reset(),
+        Command::Unknown(name) => writeln!(
+            out,
+            "Unknown command ':{}'. Type :help for a list of commands.",
+            name
+        )?,
+    }
+    Ok(Flow::Continue)
+}
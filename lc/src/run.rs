@@ -0,0 +1,123 @@
+use std::{
+    env,
+    fs::File,
+    io::{self, IsTerminal, Read},
+};
+
+use anyhow::{anyhow, Result};
+
+use lc_core::*;
+use lc_interpreter::*;
+
+/// Debugging output requested on the command line.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options {
+    pub dump_tokens: bool,
+    pub dump_tree: bool,
+    pub dump_ast: bool,
+    pub format: bool,
+}
+
+/// Renders to stderr, in colour when it is a terminal.
+pub fn renderer<'a>() -> Renderer<'a> {
+    // https://no-color.org: any non-empty value disables colour
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    Renderer::new(!no_color && io::stderr().is_terminal())
+}
+
+/// The contents of `filename`. Errors name the file, as they are shown on their own.
+pub fn read_file(filename: &str) -> Result<String> {
+    let mut contents = String::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|e| anyhow!("{}: {}", filename, e))?;
+    Ok(contents)
+}
+
+/// The statements as pretty-printed JSON, for external tooling.
+pub fn ast_to_json(statements: &[Stmt]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(statements)?)
+}
+
+pub fn run(input: String, context: &mut Interpreter, options: Options) -> Result<()> {
+    run_statements(input, context, options, false)?;
+    Ok(())
+}
+
+/// Like [`run`], but input that is a lone expression without a trailing `;` is evaluated and
+/// its value returned, for the REPL to echo. Anything else runs as usual and gives `None`.
+pub fn run_echo(
+    input: String,
+    context: &mut Interpreter,
+    options: Options,
+) -> Result<Option<Value>> {
+    run_statements(input, context, options, true)
+}
+
+fn run_statements(
+    input: String,
+    context: &mut Interpreter,
+    options: Options,
+    echo: bool,
+) -> Result<Option<Value>> {
+    let mut issues = TranslationErrors::new();
+    let source_map = SourceMap::new(&input);
+
+    // Lexing
+    let mut scanner = Scanner::new(input);
+    let (tokens, mut errs) = scanner.scan_tokens();
+    issues.merge(&mut errs);
+    if options.dump_tokens {
+        print!("{}", tokens_to_string(&tokens));
+    }
+
+    // Parsing
+    let mut parser = Parser::new(tokens.clone());
+    let (mut statements, mut errs) = parser.parse();
+    // Input that only fails to parse as statements for want of a `;` is a bare expression.
+    // Declarations come first, so `fn f() {}` still declares `f` rather than being a lambda.
+    let bare = if echo && errs.has_errors() && !issues.has_errors() {
+        Parser::new(tokens).parse_expression().ok()
+    } else {
+        None
+    };
+    let echo = bare.is_some();
+    match bare {
+        Some(ex) => statements = vec![Stmt::Expression(ex)],
+        None => issues.merge(&mut errs),
+    }
+    if options.dump_tree {
+        print!("{}", ast_to_string(&statements));
+    }
+    // The JSON dump and formatting replace execution entirely, so only a tree that parsed is worth
+    // showing
+    if options.dump_ast {
+        issues.check()?;
+        println!("{}", ast_to_json(&statements)?);
+        return Ok(None);
+    }
+    if options.format {
+        issues.check()?;
+        print!("{}", to_source(&statements));
+        return Ok(None);
+    }
+
+    // Resolving and binding
+    let mut resolver = Resolver::new(context);
+    let (_, mut errs) = resolver.resolve(&statements);
+    issues.merge(&mut errs);
+
+    // Warnings are shown even if translation failed
+    eprint!(
+        "{}",
+        renderer().with_source(&source_map).render_warnings(&issues)
+    );
+
+    // Execution
+    issues.check()?;
+    if echo {
+        return Ok(Some(context.interpret_value(statements)?));
+    }
+    context.interpret(statements)?;
+    Ok(None)
+}
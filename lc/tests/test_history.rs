@@ -0,0 +1,37 @@
+use std::{env, fs};
+
+use lc::*;
+
+#[test]
+fn history_round_trip() {
+    let path = env::temp_dir().join("mylang_history_round_trip");
+    let entries = vec!["let x = 1;".to_string(), "  ".into(), "print x;\n".into()];
+    save_history(&path, &entries).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "let x = 1;\nprint x;\n");
+    assert_eq!(load_history(&path).unwrap(), ["let x = 1;", "print x;"]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn history_missing_file() {
+    let path = env::temp_dir().join("mylang_history_missing");
+    assert!(load_history(&path).unwrap().is_empty());
+}
+
+#[test]
+fn history_capped() {
+    let path = env::temp_dir().join("mylang_history_capped");
+    let entries: Vec<String> = (0..HISTORY_LIMIT + 5)
+        .map(|i| format!("print {};", i))
+        .collect();
+    save_history(&path, &entries).unwrap();
+    let loaded = load_history(&path).unwrap();
+    assert_eq!(loaded.len(), HISTORY_LIMIT);
+    assert_eq!(loaded[0], "print 5;");
+    assert_eq!(loaded.last(), entries.last());
+
+    // Files written by hand or by older versions are trimmed on load too
+    fs::write(&path, entries.join("\n")).unwrap();
+    assert_eq!(load_history(&path).unwrap(), loaded);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,265 @@
+use std::{env, fs};
+
+use lc::*;
+use lc_core::*;
+use lc_interpreter::*;
+
+#[test]
+fn repl_command_parse() {
+    assert_eq!(Command::parse(":reset\n"), Some(Command::Reset));
+    assert_eq!(Command::parse("  :quit"), Some(Command::Quit));
+    assert_eq!(
+        Command::parse(":frobnicate"),
+        Some(Command::Unknown("frobnicate".into()))
+    );
+    assert_eq!(Command::parse("print 1;"), None);
+}
+
+#[test]
+fn repl_reset_clears_definitions() {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+
+    run("let x = 1;".into(), &mut context, Options::default()).unwrap();
+    run("print x;".into(), &mut context, Options::default()).unwrap();
+    let flow = dispatch(
+        &Command::Reset,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!(flow, Flow::Continue);
+    assert!(run("print x;".into(), &mut context, Options::default()).is_err());
+    // Builtins survive a reset
+    run("print typeof(1);".into(), &mut context, Options::default()).unwrap();
+
+    assert_eq!(
+        dispatch(
+            &Command::Quit,
+            &mut context,
+            Renderer::default(),
+            &mut out,
+            &mut err
+        )
+        .unwrap(),
+        Flow::Quit
+    );
+    drop(context);
+    assert_eq!(String::from_utf8(output).unwrap(), "1\nInt\n");
+}
+
+#[test]
+fn repl_env_lists_globals() {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    run(
+        "let y = 2; let x = \"one\";".into(),
+        &mut context,
+        Options::default(),
+    )
+    .unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+    dispatch(
+        &Command::Env,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    let env = String::from_utf8(out).unwrap();
+    assert!(env.contains("x = one\ny = 2\n"));
+    assert!(env.contains("clock = <native fn clock/0>\n"));
+}
+
+#[test]
+fn repl_load_into_session() {
+    let path = env::temp_dir().join("mylang_repl_load.lc");
+    fs::write(&path, "fn double(n) { return n * 2; }\nlet base = 20;\n").unwrap();
+    let command = Command::parse(&format!(":load {}\n", path.display())).unwrap();
+    assert_eq!(command, Command::Load(path.display().to_string()));
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+    let flow = dispatch(
+        &command,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!(flow, Flow::Continue);
+    run(
+        "print double(base + 1);".into(),
+        &mut context,
+        Options::default(),
+    )
+    .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    drop(context);
+    assert!(out.is_empty() && err.is_empty());
+    assert_eq!(String::from_utf8(output).unwrap(), "42\n");
+}
+
+#[test]
+fn repl_load_errors_keep_session() {
+    let path = env::temp_dir().join("mylang_repl_load_error.lc");
+    fs::write(&path, "let ok = 1;\nprint missing;\n").unwrap();
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+    let command = Command::Load(path.display().to_string());
+    let flow = dispatch(
+        &command,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!(flow, Flow::Continue);
+    fs::remove_file(&path).unwrap();
+    // Errors are diagnostics, written where the REPL's own errors go
+    assert!(out.is_empty());
+    let rendered = String::from_utf8(err).unwrap();
+    assert!(rendered.contains("missing"), "{}", rendered);
+    assert!(rendered.contains("print missing;"), "{}", rendered);
+
+    // Loading it again finds it gone
+    let mut err: Vec<u8> = Vec::new();
+    dispatch(
+        &command,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    let rendered = String::from_utf8(err).unwrap();
+    assert!(
+        rendered.starts_with(&format!("{}: ", path.display())),
+        "{}",
+        rendered
+    );
+
+    run("print ok;".into(), &mut context, Options::default()).unwrap();
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+    dispatch(
+        &Command::Load(String::new()),
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Usage: :load <path>\n");
+    assert!(err.is_empty());
+
+    drop(context);
+    assert_eq!(String::from_utf8(output).unwrap(), "1\n");
+}
+
+#[test]
+fn repl_load_errors_show_backtrace() {
+    let path = env::temp_dir().join("mylang_repl_load_backtrace.lc");
+    fs::write(&path, "fn f(x) {\n  return -x;\n}\nprint f(\"a\") + 1;\n").unwrap();
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+    let command = Command::Load(path.display().to_string());
+    dispatch(
+        &command,
+        &mut context,
+        Renderer::default(),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    fs::remove_file(&path).unwrap();
+    let rendered = String::from_utf8(err).unwrap();
+    assert!(
+        rendered.starts_with("[line 2] RuntimeError"),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.ends_with("\n  in <fn f/1>, called from line 4\n"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn repl_echoes_bare_expressions() {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut echo = |line: &str| {
+        run_echo(line.into(), &mut context, Options::default())
+            .unwrap()
+            .map(|value| value.as_str())
+    };
+
+    assert_eq!(echo("1 + 2"), Some("3".into()));
+    assert_eq!(echo("1 + 2;"), None);
+    assert_eq!(echo("let x = \"a\";"), None);
+    assert_eq!(echo("x + \"b\""), Some("ab".into()));
+    assert_eq!(echo("  [x, len(x)]\n"), Some("[\"a\", 1]".into()));
+    assert_eq!(echo("x = \"c\""), Some("c".into()));
+    assert_eq!(echo("print x;"), None);
+    assert_eq!(echo("fn f() { return 2; }"), None);
+    assert_eq!(echo("f() * f()"), Some("4".into()));
+    assert_eq!(echo(""), None);
+
+    // Errors in a bare expression are reported as usual
+    assert!(run_echo("1 +".into(), &mut context, Options::default()).is_err());
+    assert!(run_echo("y".into(), &mut context, Options::default()).is_err());
+    drop(context);
+    assert_eq!(String::from_utf8(output).unwrap(), "c\n");
+}
+
+#[test]
+fn repl_continues_unfinished_input() {
+    assert!(is_incomplete("fn f() {"));
+    assert!(is_incomplete("let xs = [1,\n2,"));
+    assert!(is_incomplete("print (1 +"));
+    assert!(is_incomplete("if (true) {\n  print 1;\n"));
+    assert!(!is_incomplete("fn f() { return 1; }"));
+    assert!(!is_incomplete("1 + 2"));
+    assert!(!is_incomplete("print 1"));
+    assert!(!is_incomplete("fn f() { return 1; }}"));
+    assert!(!is_incomplete(":load {"));
+
+    let mut lines = vec!["  return n * 2;", "}", "print 3;"].into_iter();
+    let input = read_complete::<()>("fn double(n) {".into(), || {
+        Ok(lines.next().map(String::from))
+    });
+    assert_eq!(input, Ok("fn double(n) {\n  return n * 2;\n}".into()));
+    assert_eq!(lines.next(), Some("print 3;"));
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    run(input.unwrap(), &mut context, Options::default()).unwrap();
+    run("print double(4);".into(), &mut context, Options::default()).unwrap();
+    drop(context);
+    assert_eq!(String::from_utf8(output).unwrap(), "8\n");
+
+    // Input that ends early is returned as it is, to fail when run
+    let mut lines = vec!["print 1;"].into_iter();
+    let input = read_complete::<()>("{".into(), || Ok(lines.next().map(String::from)));
+    assert_eq!(input, Ok("{\nprint 1;".into()));
+}
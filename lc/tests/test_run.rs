@@ -0,0 +1,26 @@
+use lc::*;
+use lc_core::*;
+use serde_json::json;
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let (tokens, errs) = Scanner::new(source.into()).scan_tokens();
+    assert!(!errs.has_errors());
+    let (statements, errs) = Parser::new(tokens).parse();
+    assert!(!errs.has_errors());
+    statements
+}
+
+#[test]
+fn ast_dumps_as_json() {
+    let statements = parse("let x = 1 + \"a\";");
+    let json: serde_json::Value = serde_json::from_str(&ast_to_json(&statements).unwrap()).unwrap();
+
+    let name = &json[0]["Let"][0];
+    assert_eq!(name["symbol"], "x");
+    assert_eq!(name["span"], json!({ "line": 1, "start": 4, "end": 5 }));
+    let binary = &json[0]["Let"][1]["kind"]["Binary"];
+    assert_eq!(binary[0]["kind"], json!({ "Literal": { "Int": 1 } }));
+    assert_eq!(binary[1], "Plus");
+    // Symbols are written out as their text
+    assert_eq!(binary[2]["kind"], json!({ "Literal": { "String": "a" } }));
+}
@@ -0,0 +1,145 @@
+use anyhow::Error;
+
+use crate::{backtrace_lines, RuntimeError, SourceMap, Span, SpannedError, TranslationErrors};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Formats errors for the terminal, optionally highlighting them with ANSI colours. Given a
+/// [`SourceMap`], each error is followed by the line of source it points at, with the span
+/// underlined by carets.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Renderer<'a> {
+    colored: bool,
+    source: Option<&'a SourceMap>,
+}
+impl<'a> Renderer<'a> {
+    pub fn new(colored: bool) -> Self {
+        Self {
+            colored,
+            source: None,
+        }
+    }
+
+    pub fn with_source(self, source: &'a SourceMap) -> Self {
+        Self {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    pub fn render(&self, error: &Error) -> String {
+        if let Some(errors) = error.downcast_ref::<TranslationErrors>() {
+            self.render_translation(errors)
+        } else if let Some(error) = error.downcast_ref::<RuntimeError>() {
+            self.render_runtime(error)
+        } else {
+            format!("{}\n", error)
+        }
+    }
+
+    pub fn render_translation(&self, errors: &TranslationErrors) -> String {
+        errors
+            .issues()
+            .iter()
+            .map(|issue| self.render_spanned(issue))
+            .collect()
+    }
+
+    pub fn render_warnings(&self, errors: &TranslationErrors) -> String {
+        errors
+            .warnings()
+            .iter()
+            .map(|warning| {
+                let label = format!("{}Warning", warning.phase.as_str());
+                format!(
+                    "{}{}: {}\n{}",
+                    self.line(warning.span),
+                    self.paint(&label, YELLOW),
+                    warning.message,
+                    self.snippet(warning.span, YELLOW)
+                )
+            })
+            .collect()
+    }
+
+    pub fn render_runtime(&self, error: &RuntimeError) -> String {
+        let label = self.paint("RuntimeError", RED);
+        let mut rendered = match error.span() {
+            Some(span) => format!(
+                "{}{}: {}\n{}",
+                self.line(span),
+                label,
+                error.message(),
+                self.snippet(span, RED)
+            ),
+            None => format!("{}: {}\n", label, error.message()),
+        };
+        for line in backtrace_lines(error.backtrace()) {
+            rendered.push_str(&line);
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    fn render_spanned(&self, issue: &SpannedError) -> String {
+        let label = format!("{}Error", issue.phase.as_str());
+        format!(
+            "{}{}: {}\n{}",
+            self.line(issue.span),
+            self.paint(&label, RED),
+            issue.message,
+            self.snippet(issue.span, RED)
+        )
+    }
+
+    fn snippet(&self, span: Span, color: &str) -> String {
+        let Some(source) = self.source else {
+            return String::new();
+        };
+        let Some(text) = source.line(span.line) else {
+            return String::new();
+        };
+        let mut snippet = format!(
+            "{} | {}\n",
+            self.paint(&format!("{:>4}", span.line), CYAN),
+            text
+        );
+        if let Some(underline) = Self::underline(source, span, text) {
+            snippet.push_str(&format!("     | {}\n", self.paint(&underline, color)));
+        }
+        snippet
+    }
+
+    /// Carets under the span's text on its first line, at least one wide so empty spans still
+    /// point somewhere. `None` if the span's offsets don't fall on its line.
+    fn underline(source: &SourceMap, span: Span, text: &str) -> Option<String> {
+        let (line, column) = source.location(span.start);
+        if line != span.line {
+            return None;
+        }
+        let before = text.get(..column)?;
+        let under = source.snippet(span)?;
+        // Tabs are kept so the carets line up however wide the terminal draws them
+        let indent: String = before
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(under.chars().count().max(1));
+        Some(format!("{}{}", indent, carets))
+    }
+
+    fn line(&self, span: Span) -> String {
+        format!("[{}] ", self.paint(&format!("line {}", span.line), CYAN))
+    }
+
+    fn paint(&self, text: &str, color: &str) -> String {
+        if self.colored {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
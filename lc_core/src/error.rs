@@ -10,14 +10,18 @@ pub type TranslationResult<T> = (T, TranslationErrors);
 #[derive(Default, Debug, Clone)]
 pub struct TranslationErrors {
     issues: Vec<SpannedError>,
+    /// Suspicious but valid code. Warnings never fail [`TranslationErrors::check`].
+    warnings: Vec<SpannedError>,
 }
 impl fmt::Display for TranslationErrors {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for issue in &self.issues {
             writeln!(
                 f,
-                "[line {}] TranslationError: {}",
-                issue.span.line, issue.message
+                "[line {}] {}Error: {}",
+                issue.span.line,
+                issue.phase.as_str(),
+                issue.message
             )?;
         }
         Ok(())
@@ -26,23 +30,41 @@ impl fmt::Display for TranslationErrors {
 impl error::Error for TranslationErrors {}
 impl From<Vec<SpannedError>> for TranslationErrors {
     fn from(issues: Vec<SpannedError>) -> Self {
-        Self { issues }
+        Self {
+            issues,
+            warnings: Vec::new(),
+        }
     }
 }
 impl From<Vec<SpannedMessage>> for TranslationErrors {
     fn from(issues: Vec<SpannedMessage>) -> Self {
         Self {
             issues: issues.iter().map(|i| i.clone().into()).collect(),
+            warnings: Vec::new(),
         }
     }
 }
 impl<'a> TranslationErrors {
     pub fn new() -> Self {
-        Self { issues: Vec::new() }
+        Self::default()
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<SpannedError>) -> Self {
+        self.warnings = warnings;
+        self
     }
 
     pub fn merge(&mut self, other: &mut TranslationErrors) {
         self.issues.append(&mut other.issues);
+        self.warnings.append(&mut other.warnings);
+    }
+
+    pub fn issues(&self) -> &[SpannedError] {
+        &self.issues
+    }
+
+    pub fn warnings(&self) -> &[SpannedError] {
+        &self.warnings
     }
 
     pub fn has_errors(&self) -> bool {
@@ -58,10 +80,55 @@ impl<'a> TranslationErrors {
     }
 }
 
+/// A call that was in progress when a runtime error was raised.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The function called, as the interpreter displays it, such as `<fn fib/1>`
+    pub name: String,
+    /// The call expression in the caller, the default span for calls made by the host
+    pub span: Span,
+}
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span == Span::default() {
+            write!(f, "in {}", self.name)
+        } else {
+            write!(f, "in {}, called from line {}", self.name, self.span.line)
+        }
+    }
+}
+impl Frame {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+/// One line per frame of `backtrace`, innermost call first. A run of frames that would print
+/// the same, as deep recursion leaves behind, is shown once followed by a count of the rest.
+pub fn backtrace_lines(backtrace: &[Frame]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut frames = backtrace.iter().peekable();
+    while let Some(frame) = frames.next() {
+        lines.push(format!("  {}", frame));
+        let mut repeats = 0;
+        while frames
+            .next_if(|next| next.name == frame.name && next.span.line == frame.span.line)
+            .is_some()
+        {
+            repeats += 1;
+        }
+        if repeats > 0 {
+            lines.push(format!("  ... repeated {} more times", repeats));
+        }
+    }
+    lines
+}
+
 #[derive(Clone, Debug)]
 pub struct RuntimeError {
     span: Option<Span>,
     message: String,
+    backtrace: Box<[Frame]>,
 }
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -69,7 +136,11 @@ impl fmt::Display for RuntimeError {
         if let Some(span) = self.span {
             line = format!("[line {}] ", span.line);
         }
-        writeln!(f, "{}RuntimeError: {}", line, self.message)
+        writeln!(f, "{}RuntimeError: {}", line, self.message)?;
+        for line in backtrace_lines(&self.backtrace) {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
     }
 }
 impl error::Error for RuntimeError {}
@@ -78,6 +149,7 @@ impl From<SpannedError> for RuntimeError {
         Self {
             span: Some(value.span),
             message: value.message,
+            backtrace: value.backtrace,
         }
     }
 }
@@ -86,6 +158,7 @@ impl RuntimeError {
         Self {
             span: None,
             message,
+            backtrace: Box::default(),
         }
     }
 
@@ -93,9 +166,29 @@ impl RuntimeError {
         Self {
             span: Some(span),
             message,
+            backtrace: Box::default(),
         }
     }
 
+    pub fn with_backtrace(mut self, backtrace: Box<[Frame]>) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The calls the error unwound through, innermost first. Empty for errors raised outside
+    /// any function.
+    pub fn backtrace(&self) -> &[Frame] {
+        &self.backtrace
+    }
+
     pub fn has_span(&self) -> bool {
         self.span.is_some()
     }
@@ -105,10 +198,33 @@ impl RuntimeError {
     }
 }
 
+/// The translation stage an error originated from.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Lex,
+    Parse,
+    Resolve,
+    #[default]
+    Runtime,
+}
+impl Phase {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Phase::Lex => "Lex",
+            Phase::Parse => "Parse",
+            Phase::Resolve => "Resolve",
+            Phase::Runtime => "Runtime",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SpannedError {
     pub span: Span,
     pub message: String,
+    pub phase: Phase,
+    /// Calls in progress when a runtime error was raised, innermost first
+    pub backtrace: Box<[Frame]>,
 }
 impl error::Error for SpannedError {}
 impl fmt::Display for SpannedError {
@@ -116,11 +232,29 @@ impl fmt::Display for SpannedError {
         write!(f, "{}", self.message)
     }
 }
+impl SpannedError {
+    pub fn with_phase(mut self, phase: Phase) -> Self {
+        self.phase = phase;
+        self
+    }
+}
+impl From<RuntimeError> for SpannedError {
+    fn from(value: RuntimeError) -> Self {
+        Self {
+            span: value.span.unwrap_or_default(),
+            message: value.message,
+            phase: Phase::Runtime,
+            backtrace: value.backtrace,
+        }
+    }
+}
 impl From<(&Token, &str)> for SpannedError {
     fn from(value: (&Token, &str)) -> Self {
         Self {
             span: value.0.span.to_owned(),
             message: value.1.to_string(),
+            phase: Phase::default(),
+            backtrace: Box::default(),
         }
     }
 }
@@ -129,6 +263,8 @@ impl From<(&Token, String)> for SpannedError {
         Self {
             span: value.0.span.to_owned(),
             message: value.1,
+            phase: Phase::default(),
+            backtrace: Box::default(),
         }
     }
 }
@@ -137,6 +273,8 @@ impl From<(Span, &str)> for SpannedError {
         Self {
             span: value.0.to_owned(),
             message: value.1.to_string(),
+            phase: Phase::default(),
+            backtrace: Box::default(),
         }
     }
 }
@@ -145,6 +283,8 @@ impl From<(Span, String)> for SpannedError {
         Self {
             span: value.0.to_owned(),
             message: value.1,
+            phase: Phase::default(),
+            backtrace: Box::default(),
         }
     }
 }
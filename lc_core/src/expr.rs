@@ -1,33 +1,68 @@
 use std::hash::Hash;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use serde::Serialize;
+
 use crate::token::Token;
-use crate::{Literal, Span, Symbol, TokenKind};
+use crate::{Literal, Span, Stmt, Symbol, TokenKind};
 
 pub const LIMIT_FN_ARGS: usize = 255;
 static EXPR_ID: AtomicUsize = AtomicUsize::new(0);
 
-#[derive(Clone, Debug, PartialEq, Hash)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize)]
 pub enum ExprKind {
+    /// (`elements`)
+    Array(Vec<Expr>),
     /// (`identifier`, `initializer`)
     Assign(Ident, Box<Expr>),
     /// (`left`, `op`, `right`)
     Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    /// (`statements`, `value`)
+    Block(Vec<Stmt>, Option<Box<Expr>>),
     /// (`callee`, `span`, `args`)
     Call(Box<Expr>, Span, Vec<Expr>),
+    /// (`object`, `property`)
+    Get(Box<Expr>, Ident),
     /// (`expression`)
     Grouping(Box<Expr>),
+    /// (`condition`, `then`, `else`)
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// (`object`, `index`)
+    Index(Box<Expr>, Box<Expr>),
+    /// (`parts`), string literals alternating with the embedded expressions
+    Interpolation(Vec<Expr>),
+    /// (`entries`) of keys and values
+    Map(Vec<(Expr, Expr)>),
+    /// (`value`, `type`)
+    Is(Box<Expr>, TypeName),
+    /// (`identifier`, `params`, `body`)
+    Lambda(Option<Ident>, Rc<[Ident]>, Rc<[Stmt]>),
     /// (`literal`)
     Literal(Literal),
     /// (`left`, `op`, `right`)
     Logical(Box<Expr>, LogicOp, Box<Expr>),
+    /// (`struct`, `fields`)
+    Record(Ident, Vec<(Ident, Expr)>),
+    /// (`object`, `property`, `value`)
+    Set(Box<Expr>, Ident, Box<Expr>),
+    /// (`object`, `index`, `value`)
+    SetIndex(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// (`keyword`, `method`), looked up through the superclass bound to `super`
+    Super(Ident, Ident),
+    /// (`condition`, `then`, `else`)
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// (`keyword`), looked up like a variable named `this`
+    This(Ident),
+    /// (`elements`)
+    Tuple(Vec<Expr>),
     /// (`op`, `right`)
     Unary(UnaryOp, Box<Expr>),
     /// (`identifier`)
     Variable(Ident),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct Ident {
     pub symbol: Symbol,
     pub span: Span,
@@ -48,7 +83,7 @@ impl Ident {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum BinaryOp {
     Equal,
     NotEqual,
@@ -60,6 +95,15 @@ pub enum BinaryOp {
     Minus,
     Multiply,
     Divide,
+    /// Division rounded down to a whole number
+    FloorDivide,
+    Modulo,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 impl From<TokenKind> for BinaryOp {
     fn from(value: TokenKind) -> Self {
@@ -74,6 +118,14 @@ impl From<TokenKind> for BinaryOp {
             TokenKind::Minus => Self::Minus,
             TokenKind::Star => Self::Multiply,
             TokenKind::Slash => Self::Divide,
+            TokenKind::TildeSlash => Self::FloorDivide,
+            TokenKind::Percent => Self::Modulo,
+            TokenKind::StarStar => Self::Power,
+            TokenKind::Ampersand => Self::BitAnd,
+            TokenKind::Pipe => Self::BitOr,
+            TokenKind::Caret => Self::BitXor,
+            TokenKind::LessLess => Self::ShiftLeft,
+            TokenKind::GreaterGreater => Self::ShiftRight,
             _ => unreachable!(),
         }
     }
@@ -96,20 +148,30 @@ impl BinaryOp {
             BinaryOp::Minus => "-",
             BinaryOp::Multiply => "*",
             BinaryOp::Divide => "/",
+            BinaryOp::FloorDivide => "~/",
+            BinaryOp::Modulo => "%",
+            BinaryOp::Power => "**",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum UnaryOp {
     Negative,
     Not,
+    BitNot,
 }
 impl From<TokenKind> for UnaryOp {
     fn from(value: TokenKind) -> Self {
         match value {
             TokenKind::Bang => Self::Not,
             TokenKind::Minus => Self::Negative,
+            TokenKind::Tilde => Self::BitNot,
             _ => unreachable!(),
         }
     }
@@ -124,11 +186,12 @@ impl UnaryOp {
         match self {
             UnaryOp::Negative => "-",
             UnaryOp::Not => "!",
+            UnaryOp::BitNot => "~",
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum LogicOp {
     And,
     Or,
@@ -136,8 +199,8 @@ pub enum LogicOp {
 impl From<TokenKind> for LogicOp {
     fn from(value: TokenKind) -> Self {
         match value {
-            TokenKind::And => Self::And,
-            TokenKind::Or => Self::Or,
+            TokenKind::And | TokenKind::AmpAmp => Self::And,
+            TokenKind::Or | TokenKind::PipePipe => Self::Or,
             _ => unreachable!(),
         }
     }
@@ -156,8 +219,62 @@ impl LogicOp {
     }
 }
 
-#[derive(Clone, Debug)]
+/// The built-in types an `is` expression can check for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum TypeName {
+    Array,
+    Bool,
+    Enum,
+    Function,
+    Int,
+    Map,
+    Null,
+    Number,
+    String,
+    Struct,
+    Tuple,
+}
+impl TypeName {
+    pub const ALL: [TypeName; 11] = [
+        TypeName::Array,
+        TypeName::Bool,
+        TypeName::Enum,
+        TypeName::Function,
+        TypeName::Int,
+        TypeName::Map,
+        TypeName::Null,
+        TypeName::Number,
+        TypeName::String,
+        TypeName::Struct,
+        TypeName::Tuple,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|type_name| type_name.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TypeName::Array => "Array",
+            TypeName::Bool => "Bool",
+            TypeName::Enum => "Enum",
+            TypeName::Function => "Function",
+            TypeName::Int => "Int",
+            TypeName::Map => "Map",
+            TypeName::Null => "Null",
+            TypeName::Number => "Number",
+            TypeName::String => "String",
+            TypeName::Struct => "Struct",
+            TypeName::Tuple => "Tuple",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Expr {
+    #[serde(skip)]
     id: usize,
     pub kind: ExprKind,
     pub span: Span,
@@ -179,6 +296,16 @@ impl Expr {
         Self { id, kind, span }
     }
 
+    /// A unique id for this expression, and the only part of it considered by equality and
+    /// hashing.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn array(elements: Vec<Expr>, span: Span) -> Self {
+        Self::new(ExprKind::Array(elements), span)
+    }
+
     pub fn assign(var: Ident, ex: Expr) -> Self {
         let span = var.span.to(ex.span);
         Self::new(ExprKind::Assign(var, Box::new(ex)), span)
@@ -192,14 +319,84 @@ impl Expr {
         )
     }
 
+    pub fn block(statements: Vec<Stmt>, value: Option<Expr>, span: Span) -> Self {
+        Self::new(ExprKind::Block(statements, value.map(Box::new)), span)
+    }
+
     pub fn call(callee: Expr, arg_span: Span, args: Vec<Expr>) -> Self {
         Self::new(ExprKind::Call(Box::new(callee), arg_span, args), arg_span)
     }
 
+    pub fn get(object: Expr, property: Ident) -> Self {
+        let span = object.span.to(property.span);
+        Self::new(ExprKind::Get(Box::new(object), property), span)
+    }
+
     pub fn grouping(ex: Expr) -> Self {
         Self::new(ExprKind::Grouping(Box::new(ex.to_owned())), ex.span)
     }
 
+    pub fn if_else(condition: Expr, ex_then: Expr, ex_else: Expr, span: Span) -> Self {
+        Self::new(
+            ExprKind::If(Box::new(condition), Box::new(ex_then), Box::new(ex_else)),
+            span,
+        )
+    }
+
+    pub fn index(object: Expr, index: Expr, span: Span) -> Self {
+        let span = object.span.to(span);
+        Self::new(ExprKind::Index(Box::new(object), Box::new(index)), span)
+    }
+
+    pub fn interpolation(parts: Vec<Expr>, span: Span) -> Self {
+        Self::new(ExprKind::Interpolation(parts), span)
+    }
+
+    pub fn is(ex: Expr, type_name: TypeName, span: Span) -> Self {
+        let span = ex.span.to(span);
+        Self::new(ExprKind::Is(Box::new(ex), type_name), span)
+    }
+
+    pub fn lambda(name: Option<Ident>, params: Vec<Ident>, body: Vec<Stmt>, span: Span) -> Self {
+        Self::new(ExprKind::Lambda(name, params.into(), body.into()), span)
+    }
+
+    pub fn map(entries: Vec<(Expr, Expr)>, span: Span) -> Self {
+        Self::new(ExprKind::Map(entries), span)
+    }
+
+    pub fn record(name: Ident, fields: Vec<(Ident, Expr)>, span: Span) -> Self {
+        Self::new(ExprKind::Record(name, fields), span)
+    }
+
+    pub fn set(object: Expr, property: Ident, value: Expr) -> Self {
+        let span = object.span.to(value.span);
+        Self::new(
+            ExprKind::Set(Box::new(object), property, Box::new(value)),
+            span,
+        )
+    }
+
+    pub fn ternary(condition: Expr, ex_then: Expr, ex_else: Expr) -> Self {
+        let span = condition.span.to(ex_else.span);
+        Self::new(
+            ExprKind::Ternary(Box::new(condition), Box::new(ex_then), Box::new(ex_else)),
+            span,
+        )
+    }
+
+    pub fn set_index(object: Expr, index: Expr, value: Expr) -> Self {
+        let span = object.span.to(value.span);
+        Self::new(
+            ExprKind::SetIndex(Box::new(object), Box::new(index), Box::new(value)),
+            span,
+        )
+    }
+
+    pub fn tuple(elements: Vec<Expr>, span: Span) -> Self {
+        Self::new(ExprKind::Tuple(elements), span)
+    }
+
     pub fn literal_string(str: String, span: Span) -> Self {
         Self::new(
             ExprKind::Literal(Literal::String(Symbol::string(str))),
@@ -207,6 +404,10 @@ impl Expr {
         )
     }
 
+    pub fn literal_int(num: i64, span: Span) -> Self {
+        Self::new(ExprKind::Literal(Literal::Int(num)), span)
+    }
+
     pub fn literal_number(num: f64, span: Span) -> Self {
         Self::new(ExprKind::Literal(Literal::Number(num)), span)
     }
@@ -236,4 +437,17 @@ impl Expr {
         let span = var.span;
         Self::new(ExprKind::Variable(Ident::from_token(var)), span)
     }
+
+    pub fn super_access(keyword: Token, method: Token) -> Self {
+        let span = keyword.span.to(method.span);
+        Self::new(
+            ExprKind::Super(Ident::from_token(keyword), Ident::from_token(method)),
+            span,
+        )
+    }
+
+    pub fn this(keyword: Token) -> Self {
+        let span = keyword.span;
+        Self::new(ExprKind::This(Ident::from_token(keyword)), span)
+    }
 }
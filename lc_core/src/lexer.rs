@@ -1,22 +1,30 @@
 use crate::{
     token::{Token, TokenKind},
-    Span, SpannedMessage, TranslationResult,
+    Phase, Span, SpannedError, TranslationResult,
 };
 use phf::*;
 
 static KEYWORDS: phf::Map<&'static str, TokenKind> = phf_map! {
     "and" => TokenKind::And,
+    "break" => TokenKind::Break,
     "class" => TokenKind::Class,
+    "const" => TokenKind::Const,
+    "continue" => TokenKind::Continue,
+    "do" => TokenKind::Do,
     "else" => TokenKind::Else,
+    "enum" => TokenKind::Enum,
     "false" => TokenKind::False,
     "fn" => TokenKind::Fn,
     "for" => TokenKind::For,
     "if" => TokenKind::If,
+    "in" => TokenKind::In,
+    "is" => TokenKind::Is,
     "let" => TokenKind::Let,
     "null" => TokenKind::Null,
     "or" => TokenKind::Or,
     "print" => TokenKind::Print,
     "return" => TokenKind::Return,
+    "struct" => TokenKind::Struct,
     "super" => TokenKind::Super,
     "this" => TokenKind::This,
     "true" => TokenKind::True,
@@ -26,10 +34,14 @@ static KEYWORDS: phf::Map<&'static str, TokenKind> = phf_map! {
 pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
+    /// Byte offset of the lexeme being scanned, so that spans can slice the source
     start: usize,
+    /// Byte offset of the next character, always on a character boundary
     current: usize,
     line: usize,
-    errors: Vec<SpannedMessage>,
+    /// For each `${` still open, the braces opened inside it and the token count at its start
+    interpolations: Vec<(usize, usize)>,
+    errors: Vec<SpannedError>,
 }
 impl Scanner {
     pub fn new(source: String) -> Self {
@@ -39,6 +51,7 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            interpolations: Vec::new(),
             errors: Vec::new(),
         }
     }
@@ -49,13 +62,16 @@ impl Scanner {
             self.start = self.current;
             self.scan_token()
         }
+        if !self.interpolations.is_empty() {
+            self.start = self.current;
+            self.report_error(self.line, String::from("Unterminated '${' in string"));
+        }
 
+        // Zero-width, at the end of the input on the last line reached
         self.tokens.push(Token::new(
             TokenKind::EOF,
             String::new(),
-            self.tokens
-                .last()
-                .map_or(Span::new(1, 0, self.current), |last| last.span),
+            Span::new(self.line, self.current, self.current),
         ));
         (self.tokens.to_owned(), self.errors.clone().into())
     }
@@ -67,11 +83,36 @@ impl Scanner {
             '\n' => self.line += 1,
             '(' => self.add_token(TokenKind::LeftParen),
             ')' => self.add_token(TokenKind::RightParen),
-            '{' => self.add_token(TokenKind::LeftBrace),
-            '}' => self.add_token(TokenKind::RightBrace),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenKind::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some((0, _)) => self.close_interpolation(),
+                Some((depth, _)) => {
+                    *depth -= 1;
+                    self.add_token(TokenKind::RightBrace)
+                }
+                None => self.add_token(TokenKind::RightBrace),
+            },
+            '[' => self.add_token(TokenKind::LeftBracket),
+            ']' => self.add_token(TokenKind::RightBracket),
             ',' => self.add_token(TokenKind::Comma),
             '.' => self.add_token(TokenKind::Dot),
             ';' => self.add_token(TokenKind::Semicolon),
+            ':' => self.add_token(TokenKind::Colon),
+            '?' => self.add_token(TokenKind::Question),
+            '^' => self.add_token(TokenKind::Caret),
+            // `//` starts a comment, so floor division is spelled `~/` instead
+            '~' => {
+                if self.match_next('/') {
+                    self.add_token(TokenKind::TildeSlash)
+                } else {
+                    self.add_token(TokenKind::Tilde)
+                }
+            }
             '+' => {
                 if self.match_next('=') {
                     self.add_token(TokenKind::PlusEqual)
@@ -90,9 +131,20 @@ impl Scanner {
                     self.add_token(TokenKind::Minus)
                 }
             }
+            '%' => {
+                if self.match_next('=') {
+                    self.add_token(TokenKind::PercentEqual)
+                } else {
+                    self.add_token(TokenKind::Percent)
+                }
+            }
             '*' => {
                 if self.match_next('=') {
                     self.add_token(TokenKind::StarEqual)
+                } else if self.peek() == '*' && self.peek_next() != '=' {
+                    // `**=` is not an operator, so it stays `*` followed by `*=`
+                    self.advance();
+                    self.add_token(TokenKind::StarStar)
                 } else {
                     self.add_token(TokenKind::Star)
                 }
@@ -114,6 +166,10 @@ impl Scanner {
             '<' => {
                 if self.match_next('=') {
                     self.add_token(TokenKind::LessEqual)
+                } else if self.peek() == '<' && self.peek_next() != '=' {
+                    // Like `**=`, `<<=` is not an operator and stays `<` followed by `<=`
+                    self.advance();
+                    self.add_token(TokenKind::LessLess)
                 } else {
                     self.add_token(TokenKind::Less)
                 }
@@ -121,10 +177,27 @@ impl Scanner {
             '>' => {
                 if self.match_next('=') {
                     self.add_token(TokenKind::GreaterEqual)
+                } else if self.peek() == '>' && self.peek_next() != '=' {
+                    self.advance();
+                    self.add_token(TokenKind::GreaterGreater)
                 } else {
                     self.add_token(TokenKind::Greater)
                 }
             }
+            '&' => {
+                if self.match_next('&') {
+                    self.add_token(TokenKind::AmpAmp)
+                } else {
+                    self.add_token(TokenKind::Ampersand)
+                }
+            }
+            '|' => {
+                if self.match_next('|') {
+                    self.add_token(TokenKind::PipePipe)
+                } else {
+                    self.add_token(TokenKind::Pipe)
+                }
+            }
             '/' => {
                 if self.match_next('/') {
                     while self.peek() != '\n' && !self.is_at_end() {
@@ -138,48 +211,170 @@ impl Scanner {
                     self.add_token(TokenKind::Slash)
                 }
             }
+            '"' if self.peek() == '"' && self.peek_next() == '"' => {
+                self.current += 2;
+                self.scan_triple_quoted_string()
+            }
             '"' => self.scan_string(),
+            '0' if matches!(self.peek(), 'x' | 'X') => self.scan_radix_number(16, "hex"),
+            '0' if matches!(self.peek(), 'o' | 'O') => self.scan_radix_number(8, "octal"),
+            '0' if matches!(self.peek(), 'b' | 'B') => self.scan_radix_number(2, "binary"),
             '0'..='9' => self.scan_number(),
             'a'..='z' | 'A'..='Z' | '_' => self.scan_identifier(),
             _ => self.report_error(self.line, format!("Unexpected character {}", c)),
         }
     }
 
+    /// Scans the rest of a string after its opening `"`, or after the `}` closing an
+    /// interpolation. A `${` ends the current part and starts an embedded expression.
     fn scan_string(&mut self) {
+        let mut value = String::new();
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+            match self.peek() {
+                '$' if self.peek_next() == '{' => {
+                    self.current += 2;
+                    self.add_token(TokenKind::Interpolation(value));
+                    self.interpolations.push((0, self.tokens.len()));
+                    return;
+                }
+                '\\' => {
+                    self.advance();
+                    self.scan_escape(&mut value);
+                    continue;
+                }
+                '\n' => self.line += 1,
+                _ => (),
             }
-            self.advance();
+            value.push(self.advance());
         }
         if self.is_at_end() {
             self.report_error(self.line, String::from("Unterminated string"));
             return;
         }
         self.advance(); // consume the closing "
-        let value = String::from(&self.source[self.start + 1..self.current - 1]);
         self.add_token(TokenKind::String(value));
     }
 
+    /// Decodes the escape following a `\` into `value`. An unknown escape is reported at
+    /// the escape itself and dropped, and scanning carries on to the end of the string.
+    fn scan_escape(&mut self, value: &mut String) {
+        if self.is_at_end() {
+            return;
+        }
+        let escaped = self.advance();
+        let decoded = match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '"' => '"',
+            '\\' => '\\',
+            '0' => '\0',
+            _ => {
+                let span = Span::new(self.line, self.current - 2, self.current);
+                let message = format!("Unknown escape sequence '\\{}'", escaped.escape_debug());
+                self.errors
+                    .push(SpannedError::from((span, message)).with_phase(Phase::Lex));
+                if escaped == '\n' {
+                    self.line += 1;
+                }
+                return;
+            }
+        };
+        value.push(decoded);
+    }
+
+    fn close_interpolation(&mut self) {
+        let (_, tokens_before) = self.interpolations.pop().unwrap();
+        if self.tokens.len() == tokens_before {
+            self.report_error(self.line, String::from("Empty '${}' in string"));
+        }
+        self.scan_string();
+    }
+
+    /// A `"""` string runs to the next `"""`, so it may contain `"` and `""`. Unlike regular
+    /// strings its contents are taken verbatim, newlines and backslashes included, except that
+    /// a line break straight after the opening quotes is dropped so the text can start on the
+    /// next line.
+    fn scan_triple_quoted_string(&mut self) {
+        while !self.at_triple_quote() && !self.is_at_end() {
+            if self.peek() == '\n' {
+                self.line += 1;
+            }
+            self.advance();
+        }
+        if self.is_at_end() {
+            self.report_error(self.line, String::from("Unterminated triple-quoted string"));
+            return;
+        }
+        self.current += 3; // consume the closing """
+        let value = &self.source[self.start + 3..self.current - 3];
+        let value = value
+            .strip_prefix('\n')
+            .or_else(|| value.strip_prefix("\r\n"))
+            .unwrap_or(value);
+        self.add_token(TokenKind::String(value.to_string()));
+    }
+
+    fn at_triple_quote(&self) -> bool {
+        self.source[self.current..].starts_with("\"\"\"")
+    }
+
     fn scan_number(&mut self) {
         while self.peek().is_ascii_digit() {
             self.advance();
         }
 
         // Look for fractional part
+        let mut fractional = false;
         if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // Consume the "."
             self.advance();
+            fractional = true;
             while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
 
-        self.add_token(TokenKind::Number(
-            self.source[self.start..self.current]
-                .parse::<f64>()
-                .unwrap(),
-        ));
+        let text = &self.source[self.start..self.current];
+        // Without a fractional part it's an int, unless too large for one
+        if !fractional {
+            if let Ok(value) = text.parse::<i64>() {
+                return self.add_token(TokenKind::Int(value));
+            }
+        }
+        // Digits always parse as f64, but a literal too large for it becomes infinity
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => self.add_token(TokenKind::Number(value)),
+            _ => self.report_error(self.line, String::from("Numeric literal out of range")),
+        }
+    }
+
+    /// Scans an int written in another base after its leading `0`, as in `0xFF`, `0o17` or
+    /// `0b1010`.
+    fn scan_radix_number(&mut self, radix: u32, name: &str) {
+        // Consume the prefix letter
+        self.advance();
+        // Take every letter and digit that follows, so a bad digit is reported rather than
+        // starting the next token
+        while Scanner::is_alphanumeric(self.peek()) {
+            self.advance();
+        }
+        let digits = &self.source[self.start + 2..self.current];
+        if digits.is_empty() {
+            let message = format!(
+                "Expected digits after '{}'",
+                &self.source[self.start..self.current]
+            );
+            return self.report_error(self.line, message);
+        }
+        if let Some(digit) = digits.chars().find(|c| !c.is_digit(radix)) {
+            let message = format!("Invalid digit '{}' in {} literal", digit, name);
+            return self.report_error(self.line, message);
+        }
+        match i64::from_str_radix(digits, radix) {
+            Ok(value) => self.add_token(TokenKind::Int(value)),
+            Err(_) => self.report_error(self.line, String::from("Numeric literal out of range")),
+        }
     }
 
     fn scan_identifier(&mut self) {
@@ -194,7 +389,10 @@ impl Scanner {
         self.add_token(t_type);
     }
 
+    /// Block comments nest, so each `/*` needs its own `*/`. Running out of input first is an
+    /// error on the line the comment started, since it may have hidden code after it.
     fn scan_multiline_comment(&mut self) {
+        let start_line = self.line;
         let mut nesting = 1;
         while !self.is_at_end() {
             if self.peek() == '\n' {
@@ -208,43 +406,37 @@ impl Scanner {
                 self.advance();
                 nesting -= 1;
                 if nesting <= 0 {
-                    break;
+                    return;
                 }
             }
         }
+        self.report_error(start_line, String::from("Unterminated block comment"));
     }
 
+    /// The next character, consuming it. At the end of the input this is `'\0'` and nothing
+    /// is consumed.
     fn advance(&mut self) -> char {
-        let res = self.source.chars().nth(self.current).unwrap();
-        self.current += 1;
+        let Some(res) = self.source[self.current..].chars().next() else {
+            return '\0';
+        };
+        self.current += res.len_utf8();
         res
     }
 
     fn match_next(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        }
-        if self.source.chars().nth(self.current).unwrap() != expected {
+        if self.is_at_end() || self.peek() != expected {
             return false;
         }
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            '\0'
-        } else {
-            self.source.chars().nth(self.current).unwrap()
-        }
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            '\0'
-        } else {
-            self.source.chars().nth(self.current + 1).unwrap()
-        }
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
     }
 
     fn add_token(&mut self, p_type: TokenKind) {
@@ -265,7 +457,9 @@ impl Scanner {
     }
 
     fn report_error(&mut self, line: usize, message: String) {
-        self.errors
-            .push((Span::new(line, self.start, self.current), message));
+        self.errors.push(
+            SpannedError::from((Span::new(line, self.start, self.current), message))
+                .with_phase(Phase::Lex),
+        );
     }
 }
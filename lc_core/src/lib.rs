@@ -1,17 +1,25 @@
+mod diagnostic;
 mod error;
 mod expr;
 mod lexer;
 mod literal;
 mod parser;
+mod printer;
+mod source_map;
 mod stmt;
+mod suggest;
 mod symbol;
 mod token;
 
+pub use crate::diagnostic::*;
 pub use crate::error::*;
 pub use crate::expr::*;
 pub use crate::lexer::*;
 pub use crate::literal::*;
 pub use crate::parser::*;
+pub use crate::printer::*;
+pub use crate::source_map::*;
 pub use crate::stmt::*;
+pub use crate::suggest::*;
 pub use crate::symbol::*;
 pub use crate::token::*;
@@ -1,19 +1,42 @@
+use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::{fmt, mem, ops};
 
-use crate::{RuntimeError, Symbol};
+use serde::Serialize;
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+use crate::{escape, BinaryOp, RuntimeError, Symbol};
+
+#[derive(Clone, Copy, Debug, Serialize)]
 pub enum Literal {
     String(Symbol),
+    Int(i64),
     Number(f64),
     Bool(bool),
     Null,
 }
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::String(left), Literal::String(right)) => left == right,
+            (Literal::Int(left), Literal::Int(right)) => left == right,
+            (Literal::Bool(left), Literal::Bool(right)) => left == right,
+            (Literal::Null, Literal::Null) => true,
+            // An int equals the float with the same value
+            _ => match (self.as_number(), other.as_number()) {
+                (Some(left), Some(right)) => left == right,
+                _ => false,
+            },
+        }
+    }
+}
+impl Eq for Literal {}
 impl Hash for Literal {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            Literal::Number(num) => num.to_ne_bytes().hash(state),
+            // Adding zero turns -0 into 0, which compares equal and so must hash the same. Ints
+            // hash as the float they equal.
+            Literal::Int(num) => (*num as f64 + 0.0).to_ne_bytes().hash(state),
+            Literal::Number(num) => (num + 0.0).to_ne_bytes().hash(state),
             Literal::String(val) => val.hash(state),
             Literal::Bool(val) => val.hash(state),
             Literal::Null => mem::discriminant(self).hash(state),
@@ -24,6 +47,7 @@ impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Literal::String(str) => write!(f, "{}", str),
+            Literal::Int(num) => write!(f, "{}", num),
             Literal::Number(num) => write!(f, "{}", num),
             Literal::Bool(lit) => write!(f, "{}", lit),
             Literal::Null => write!(f, "null"),
@@ -34,19 +58,14 @@ impl ops::Add for Literal {
     type Output = Result<Literal, RuntimeError>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let err = Err(RuntimeError::new(
-            "Operands must be two numbers or two strings.".into(),
-        ));
         match self {
-            Literal::Number(lhs) => match rhs {
-                Literal::Number(rhs) => Ok(Literal::Number(lhs + rhs)),
-                _ => err,
-            },
-            Literal::String(lhs) => match rhs {
-                Literal::String(rhs) => Ok(Literal::String(lhs + rhs)),
-                _ => err,
-            },
-            _ => err,
+            Literal::String(lhs) => Ok(Literal::String(lhs + Symbol::string(rhs.as_str()))),
+            _ => arithmetic(
+                self,
+                BinaryOp::Plus,
+                rhs,
+                "Operands must be two numbers or two strings.",
+            ),
         }
     }
 }
@@ -54,42 +73,26 @@ impl ops::Sub for Literal {
     type Output = Result<Literal, RuntimeError>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let err = Err(RuntimeError::new("Operands must be two numbers.".into()));
-        match self {
-            Literal::Number(lhs) => match rhs {
-                Literal::Number(rhs) => Ok(Literal::Number(lhs - rhs)),
-                _ => err,
-            },
-            _ => err,
-        }
+        arithmetic(self, BinaryOp::Minus, rhs, "Operands must be two numbers.")
     }
 }
 impl ops::Mul for Literal {
     type Output = Result<Literal, RuntimeError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let err = Err(RuntimeError::new("Operands must be two numbers.".into()));
-        match self {
-            Literal::Number(lhs) => match rhs {
-                Literal::Number(rhs) => Ok(Literal::Number(lhs * rhs)),
-                _ => err,
-            },
-            _ => err,
-        }
+        arithmetic(
+            self,
+            BinaryOp::Multiply,
+            rhs,
+            "Operands must be two numbers.",
+        )
     }
 }
 impl ops::Div for Literal {
     type Output = Result<Literal, RuntimeError>;
 
     fn div(self, rhs: Self) -> Self::Output {
-        let err = Err(RuntimeError::new("Operands must be two numbers.".into()));
-        match self {
-            Literal::Number(lhs) => match rhs {
-                Literal::Number(rhs) => Ok(Literal::Number(lhs / rhs)),
-                _ => err,
-            },
-            _ => err,
-        }
+        arithmetic(self, BinaryOp::Divide, rhs, "Operands must be two numbers.")
     }
 }
 impl ops::Neg for Literal {
@@ -97,6 +100,10 @@ impl ops::Neg for Literal {
 
     fn neg(self) -> Self::Output {
         match self {
+            Literal::Int(val) => val
+                .checked_neg()
+                .map(Literal::Int)
+                .ok_or_else(|| RuntimeError::new(INTEGER_OVERFLOW.into())),
             Literal::Number(val) => Ok(Literal::Number(-val)),
             _ => Err(RuntimeError::new("Operand must be a number.".into())),
         }
@@ -110,15 +117,44 @@ impl ops::Not for Literal {
     }
 }
 impl Literal {
+    /// The literal as `print` shows it, with strings written out as their raw text.
     pub fn as_str(&self) -> String {
         match self {
             Literal::String(str) => str.to_string(),
+            Literal::Int(num) => num.to_string(),
             Literal::Number(num) => num.to_string(),
             Literal::Bool(lit) => lit.to_string(),
             Literal::Null => String::from("null"),
         }
     }
 
+    /// Like [`Literal::as_str`], but strings are quoted and escaped as they would be in
+    /// source, so that `"a\nb"` can't be mistaken for two lines or `"1"` for a number.
+    pub fn repr(&self) -> String {
+        match self {
+            Literal::String(str) => format!("\"{}\"", escape(&str.to_string())),
+            _ => self.as_str(),
+        }
+    }
+
+    /// The bitwise complement of a number, truncated toward zero to an int first.
+    pub fn bit_not(self) -> Result<Literal, RuntimeError> {
+        match self {
+            Literal::Int(val) => Ok(Literal::Int(!val)),
+            Literal::Number(val) => Ok(Literal::Int(!(val.trunc() as i64))),
+            _ => Err(RuntimeError::new("Operand must be a number.".into())),
+        }
+    }
+
+    /// The value of an int or float as a float.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Literal::Int(num) => Some(*num as f64),
+            Literal::Number(num) => Some(*num),
+            _ => None,
+        }
+    }
+
     pub fn is_truthy(&self) -> bool {
         match self {
             Literal::Bool(b) => *b,
@@ -127,3 +163,132 @@ impl Literal {
         }
     }
 }
+
+/// The message for integer arithmetic whose result doesn't fit in an `i64`.
+pub const INTEGER_OVERFLOW: &str = "Integer overflow.";
+
+fn arithmetic(
+    lhs: Literal,
+    op: BinaryOp,
+    rhs: Literal,
+    message: &str,
+) -> Result<Literal, RuntimeError> {
+    let operands = Operands::new(&lhs, &rhs).ok_or_else(|| RuntimeError::new(message.into()))?;
+    operands
+        .apply(op)
+        .map_err(|message| RuntimeError::new(message.into()))
+}
+
+/// The integer a float holds, if it is whole and within an `i64`.
+fn whole_number(num: f64) -> Option<i64> {
+    let in_range = num >= i64::MIN as f64 && num < i64::MAX as f64;
+    (num.fract() == 0.0 && in_range).then_some(num as i64)
+}
+
+/// The two sides of a numeric operator. Arithmetic stays in integers while both sides are
+/// integers and is done on floats as soon as either side is one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operands {
+    Ints(i64, i64),
+    Floats(f64, f64),
+}
+impl Operands {
+    /// `None` unless both literals are numbers.
+    pub fn new(left: &Literal, right: &Literal) -> Option<Self> {
+        match (left, right) {
+            (Literal::Int(left), Literal::Int(right)) => Some(Operands::Ints(*left, *right)),
+            _ => Some(Operands::Floats(left.as_number()?, right.as_number()?)),
+        }
+    }
+
+    /// Applies one of the arithmetic operators `+ - * / ~/ % **`. Integer division truncates
+    /// toward zero while `~/` rounds down, and a negative integer power gives a float. Integer
+    /// results too large for an `i64` and integer division by zero are errors, while floats follow
+    /// IEEE 754.
+    ///
+    /// The bitwise operators `& | ^ << >>` work on whole numbers, floats included, and always
+    /// give an int.
+    pub fn apply(self, op: BinaryOp) -> Result<Literal, &'static str> {
+        if let BinaryOp::BitAnd
+        | BinaryOp::BitOr
+        | BinaryOp::BitXor
+        | BinaryOp::ShiftLeft
+        | BinaryOp::ShiftRight = op
+        {
+            return self.apply_bitwise(op);
+        }
+        let (left, right) = match self {
+            Operands::Floats(left, right) => {
+                return Ok(Literal::Number(match op {
+                    BinaryOp::Plus => left + right,
+                    BinaryOp::Minus => left - right,
+                    BinaryOp::Multiply => left * right,
+                    BinaryOp::Divide => left / right,
+                    BinaryOp::FloorDivide => (left / right).floor(),
+                    BinaryOp::Modulo => left % right,
+                    BinaryOp::Power => left.powf(right),
+                    _ => unreachable!("{:?} is not arithmetic", op),
+                }))
+            }
+            Operands::Ints(left, right) => (left, right),
+        };
+        let result = match op {
+            BinaryOp::Plus => left.checked_add(right),
+            BinaryOp::Minus => left.checked_sub(right),
+            BinaryOp::Multiply => left.checked_mul(right),
+            BinaryOp::Divide | BinaryOp::FloorDivide | BinaryOp::Modulo if right == 0 => {
+                return Err("Division by zero.")
+            }
+            BinaryOp::Divide => left.checked_div(right),
+            BinaryOp::FloorDivide => left.checked_div(right).map(|quotient| {
+                if left % right != 0 && (left < 0) != (right < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                }
+            }),
+            BinaryOp::Modulo => left.checked_rem(right),
+            BinaryOp::Power if right < 0 => {
+                return Ok(Literal::Number((left as f64).powf(right as f64)))
+            }
+            BinaryOp::Power => u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_pow(right)),
+            _ => unreachable!("{:?} is not arithmetic", op),
+        };
+        result.map(Literal::Int).ok_or(INTEGER_OVERFLOW)
+    }
+
+    fn apply_bitwise(self, op: BinaryOp) -> Result<Literal, &'static str> {
+        let (left, right) = match self {
+            Operands::Ints(left, right) => (left, right),
+            Operands::Floats(left, right) => match (whole_number(left), whole_number(right)) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return Err("Operands must be two integers."),
+            },
+        };
+        let shift = || {
+            u32::try_from(right)
+                .ok()
+                .filter(|shift| *shift < i64::BITS)
+                .ok_or("Shift amount must be between 0 and 63.")
+        };
+        Ok(Literal::Int(match op {
+            BinaryOp::BitAnd => left & right,
+            BinaryOp::BitOr => left | right,
+            BinaryOp::BitXor => left ^ right,
+            // Bits shifted past either end are dropped, and `>>` keeps the sign
+            BinaryOp::ShiftLeft => left << shift()?,
+            BinaryOp::ShiftRight => left >> shift()?,
+            _ => unreachable!("{:?} is not bitwise", op),
+        }))
+    }
+
+    /// How the left side orders against the right, `None` if either is NaN.
+    pub fn compare(self) -> Option<Ordering> {
+        match self {
+            Operands::Ints(left, right) => Some(left.cmp(&right)),
+            Operands::Floats(left, right) => left.partial_cmp(&right),
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use crate::{
+    closest_match,
     expr::{ExprKind, LIMIT_FN_ARGS},
     stmt::Stmt,
     token::{
         Token,
         TokenKind::{self, *},
     },
-    Expr, Ident, SpannedError, TranslationResult,
+    Expr, Ident, Phase, SpannedError, TranslationResult, TypeName,
 };
 
 type ExprResult = Result<Expr, SpannedError>;
@@ -15,6 +16,8 @@ pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     errors: Vec<SpannedError>,
+    /// Whether parsing failed at the end of the input with brackets left open
+    incomplete: bool,
 }
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
@@ -22,6 +25,7 @@ impl Parser {
             tokens,
             current: 0,
             errors: Vec::new(),
+            incomplete: false,
         }
     }
 
@@ -35,31 +39,62 @@ impl Parser {
         (statements, self.errors.clone().into())
     }
 
+    /// Whether the last parse failed only because the input stopped inside an unclosed
+    /// bracket, as in `fn f() {`. An interactive prompt can read more lines and parse again.
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
+    /// Parses the tokens as one expression with nothing after it, not even a `;`.
+    pub fn parse_expression(&mut self) -> ExprResult {
+        let ex = self.expression()?;
+        if let Some(e) = self.errors.first() {
+            return Err(e.clone());
+        }
+        if !self.is_at_end() {
+            return Err((&self.peek(), "Expected end of expression.".to_string()).into());
+        }
+        Ok(ex)
+    }
+
     fn declaration(&mut self) -> Option<Stmt> {
+        let start = self.current;
         let stmt = match self.peek().kind {
             Let => self.var_declaration(),
+            Const => self.const_declaration(),
             Fn => self.fn_declaration(),
             Class => self.class_declaration(),
+            Enum => self.enum_declaration(),
+            Struct => self.struct_declaration(),
             _ => self.statement(),
         };
         // Handle errors at statement-level
         match stmt {
             Ok(stmt) => Some(stmt),
             Err(e) => {
-                self.synchronize();
-                self.report_error(e);
+                self.recover(start, e);
                 None
             }
         }
     }
 
+    /// Reports the error that ended the statement starting at token `start` and skips the
+    /// rest of it, so that parsing can carry on with the next one.
+    fn recover(&mut self, start: usize, e: SpannedError) {
+        self.incomplete |= self.is_at_end() && self.has_unclosed_brackets();
+        self.synchronize(start);
+        self.report_error(e);
+    }
+
     fn statement(&mut self) -> StmtResult {
         match self.peek().kind {
             LeftBrace => self.block(),
+            Break | Continue => self.loop_control_stmt(),
             Return => self.return_stmt(),
             Print => self.print_stmt(),
             If => self.if_stmt(),
             While => self.while_stmt(),
+            Do => self.do_while_stmt(),
             For => self.for_stmt(),
             _ => self.expr_stmt(),
         }
@@ -67,7 +102,7 @@ impl Parser {
 
     fn expr_stmt(&mut self) -> StmtResult {
         let ex = self.expression()?;
-        self.consume(Semicolon, "Expected ';' after expression.")?;
+        self.consume_semicolon("Expected ';' after expression.")?;
         Ok(Stmt::Expression(ex))
     }
 
@@ -84,6 +119,17 @@ impl Parser {
         Ok(block)
     }
 
+    fn loop_control_stmt(&mut self) -> StmtResult {
+        let token = self.advance();
+        if token.kind == Break {
+            self.consume_semicolon("Expected ';' after 'break'.")?;
+            Ok(Stmt::Break(token.span))
+        } else {
+            self.consume_semicolon("Expected ';' after 'continue'.")?;
+            Ok(Stmt::Continue(token.span))
+        }
+    }
+
     fn return_stmt(&mut self) -> StmtResult {
         let token = self.advance();
         let value = if !self.check(&Semicolon) {
@@ -91,15 +137,18 @@ impl Parser {
         } else {
             Expr::literal_null(token.span)
         };
-        self.consume(Semicolon, "Expected ';' after return value.")?;
+        self.consume_semicolon("Expected ';' after return value.")?;
         Ok(Stmt::Return(value))
     }
 
     fn print_stmt(&mut self) -> StmtResult {
         self.advance();
-        let ex = self.expression()?;
-        self.consume(Semicolon, "Expected ';' after value.")?;
-        Ok(Stmt::Print(ex))
+        let mut expressions = vec![self.expression()?];
+        while self.match_next(vec![Comma]) {
+            expressions.push(self.expression()?);
+        }
+        self.consume_semicolon("Expected ';' after value.")?;
+        Ok(Stmt::Print(expressions))
     }
 
     fn if_stmt(&mut self) -> StmtResult {
@@ -123,12 +172,31 @@ impl Parser {
         let condition = self.expression()?;
         self.consume(RightParen, "Expected ')' after while condition.")?;
         let body = self.statement()?;
-        Ok(Stmt::new_while(condition, body))
+        Ok(Stmt::new_while(condition, body, None))
+    }
+
+    fn do_while_stmt(&mut self) -> StmtResult {
+        self.advance();
+        let body = self.statement()?;
+        self.consume(While, "Expected 'while' after do loop body.")?;
+        self.consume(LeftParen, "Expected '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(RightParen, "Expected ')' after while condition.")?;
+        self.consume_semicolon("Expected ';' after do loop condition.")?;
+        Ok(Stmt::new_do_while(body, condition))
     }
 
     fn for_stmt(&mut self) -> StmtResult {
         self.advance();
         self.consume(LeftParen, "Expected '(' after 'for'.")?;
+        let foreach = match self.peek_nth(0) {
+            Some(Let) => self.peek_nth(1) == Some(&Identifier) && self.peek_nth(2) == Some(&In),
+            Some(Identifier) => self.peek_nth(1) == Some(&In),
+            _ => false,
+        };
+        if foreach {
+            return self.foreach_stmt();
+        }
         let initializer = match self.peek().kind {
             Semicolon => {
                 self.advance();
@@ -138,12 +206,14 @@ impl Parser {
             _ => Some(self.expr_stmt()?),
         };
 
+        // Desugared nodes take their spans from the clause they were written in, so errors
+        // in the loop point at that clause rather than at the `for` header
         let condition = if !self.check(&Semicolon) {
             self.expression()?
         } else {
-            Expr::literal_bool(true, self.previous().span)
+            Expr::literal_bool(true, self.peek().span)
         };
-        self.consume(Semicolon, "Expected ';' after loop condition.")?;
+        self.consume_semicolon("Expected ';' after loop condition.")?;
 
         let increment = if !self.check(&RightParen) {
             Some(self.expression()?)
@@ -152,37 +222,85 @@ impl Parser {
         };
         self.consume(RightParen, "Expected ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-        if let Some(increment) = increment {
-            match &mut body {
-                Stmt::Block(body) => {
-                    body.push(Stmt::Expression(increment));
-                }
-                _ => body = Stmt::Block(vec![body, Stmt::Expression(increment)]),
-            }
-        }
-        body = Stmt::new_while(condition, body);
+        let body = self.statement()?;
+        let mut body = Stmt::new_while(condition, body, increment);
         if let Some(initializer) = initializer {
             body = Stmt::Block(vec![initializer, body]);
         }
         Ok(body)
     }
 
+    /// The rest of `for (let item in iterable) body` after the `(`. The `let` is optional, as
+    /// the loop always binds a fresh variable.
+    fn foreach_stmt(&mut self) -> StmtResult {
+        self.match_next(vec![Let]);
+        let name = self.advance();
+        self.advance();
+        let iterable = self.expression()?;
+        self.consume(RightParen, "Expected ')' after for-in iterable.")?;
+        let body = self.statement()?;
+        Ok(Stmt::ForEach(
+            Ident::new(name.lexeme, name.span),
+            iterable,
+            Box::new(body),
+        ))
+    }
+
     fn var_declaration(&mut self) -> StmtResult {
         self.advance();
+        if self.match_next(vec![LeftParen]) {
+            return self.tuple_declaration();
+        }
         let name = self.consume(Identifier, "Expected variable name.")?;
         let mut initializer = Expr::literal_null(name.span);
         if self.match_next(vec![Equal]) {
             initializer = self.expression()?;
         }
-        self.consume(Semicolon, "Expect ';' after variable declaration")?;
+        self.consume_semicolon("Expect ';' after variable declaration")?;
         Ok(Stmt::Let(Ident::from_token(name), initializer))
     }
 
+    /// `const name = value;`, which unlike `let` always needs a value.
+    fn const_declaration(&mut self) -> StmtResult {
+        self.advance();
+        let name = self.consume(Identifier, "Expected constant name.")?;
+        self.consume(Equal, "Expected '=' after constant name.")?;
+        let initializer = self.expression()?;
+        self.consume_semicolon("Expect ';' after constant declaration")?;
+        Ok(Stmt::Const(Ident::from_token(name), initializer))
+    }
+
+    fn tuple_declaration(&mut self) -> StmtResult {
+        let mut names = Vec::new();
+        loop {
+            names.push(Ident::from_token(
+                self.consume(Identifier, "Expected variable name in tuple pattern.")?,
+            ));
+            if !self.match_next(vec![Comma]) || self.check(&RightParen) {
+                break;
+            }
+        }
+        self.consume(RightParen, "Expected ')' after tuple pattern.")?;
+        self.consume(Equal, "Expected '=' after tuple pattern.")?;
+        let initializer = self.expression()?;
+        self.consume_semicolon("Expect ';' after variable declaration")?;
+        Ok(Stmt::LetTuple(names, initializer))
+    }
+
     fn fn_declaration(&mut self) -> StmtResult {
         self.advance();
         let name = self.consume(Identifier, "Expected function name.")?;
         self.consume(LeftParen, "Expected '(' after function name.")?;
+        let parameters = self.parameters()?;
+        let body = self.function_body()?;
+        Ok(Stmt::Function(
+            Ident::from_token(name),
+            parameters.into(),
+            body.into(),
+        ))
+    }
+
+    fn parameters(&mut self) -> Result<Vec<Ident>, SpannedError> {
         let mut parameters = Vec::new();
         if !self.check(&RightParen) {
             loop {
@@ -198,30 +316,79 @@ impl Parser {
                 parameters.push(Ident::from_token(
                     self.consume(Identifier, "Expected parameter name.")?,
                 ));
-                if !self.match_next(vec![Comma]) {
+                if !self.match_next(vec![Comma]) || self.check(&RightParen) {
                     break;
                 }
             }
         }
         self.consume(RightParen, "Expected ')' after parameters.")?;
+        Ok(parameters)
+    }
+
+    fn function_body(&mut self) -> Result<Vec<Stmt>, SpannedError> {
         if !self.check(&LeftBrace) {
             return Err((&self.peek(), "Expected '{' before function body.").into());
         }
         let Stmt::Block(body) = self.block()? else {
             return Err((&self.peek(), "Incomplete function body.").into());
         };
-        Ok(Stmt::Function(Ident::from_token(name), parameters, body))
+        Ok(body)
     }
 
     fn class_declaration(&mut self) -> StmtResult {
+        self.advance();
         let name = self.consume(Identifier, "Expected class name.")?;
+        let superclass = if self.match_next(vec![Less]) {
+            Some(Expr::var(
+                self.consume(Identifier, "Expected superclass name.")?,
+            ))
+        } else {
+            None
+        };
         self.consume(LeftBrace, "Expected '{' before class body.")?;
         let mut methods = Vec::new();
         while !self.check(&RightBrace) && !self.is_at_end() {
+            if !self.check(&Fn) {
+                return Err((&self.peek(), "Expected 'fn' before method declaration.").into());
+            }
             methods.push(self.fn_declaration()?)
         }
         self.consume(RightBrace, "Expected '}' after class body.")?;
-        Ok(Stmt::Class(Ident::from_token(name), methods))
+        Ok(Stmt::Class(Ident::from_token(name), superclass, methods))
+    }
+
+    fn enum_declaration(&mut self) -> StmtResult {
+        self.advance();
+        let name = self.consume(Identifier, "Expected enum name.")?;
+        self.consume(LeftBrace, "Expected '{' before enum variants.")?;
+        let mut variants = Vec::new();
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            variants.push(Ident::from_token(
+                self.consume(Identifier, "Expected variant name.")?,
+            ));
+            if !self.match_next(vec![Comma]) {
+                break;
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after enum variants.")?;
+        Ok(Stmt::Enum(Ident::from_token(name), variants))
+    }
+
+    fn struct_declaration(&mut self) -> StmtResult {
+        self.advance();
+        let name = self.consume(Identifier, "Expected struct name.")?;
+        self.consume(LeftBrace, "Expected '{' before struct fields.")?;
+        let mut fields = Vec::new();
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            fields.push(Ident::from_token(
+                self.consume(Identifier, "Expected field name.")?,
+            ));
+            if !self.match_next(vec![Comma]) {
+                break;
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after struct fields.")?;
+        Ok(Stmt::Struct(Ident::from_token(name), fields))
     }
 
     fn expression(&mut self) -> ExprResult {
@@ -234,8 +401,8 @@ impl Parser {
             let equals = self.previous();
             let value = self.assignment()?;
 
-            if let ExprKind::Variable(ident) = ex.kind {
-                return Ok(Expr::assign(ident, value));
+            if let Some(assignment) = Self::assignment_target(&ex, value) {
+                return Ok(assignment);
             }
             // Report error but don't throw because parser isn't in a confused state
             self.report_error((&equals, "Invalid assignment target.").into());
@@ -243,9 +410,68 @@ impl Parser {
         Ok(ex)
     }
 
+    /// Builds the expression storing `value` into the place `target` refers to, or `None` if
+    /// `target` is not something that can be assigned to.
+    fn assignment_target(target: &Expr, value: Expr) -> Option<Expr> {
+        match &target.kind {
+            ExprKind::Variable(ident) => Some(Expr::assign(*ident, value)),
+            ExprKind::Get(object, property) => {
+                Some(Expr::set(object.as_ref().to_owned(), *property, value))
+            }
+            ExprKind::Index(object, index) => Some(Expr::set_index(
+                object.as_ref().to_owned(),
+                index.as_ref().to_owned(),
+                value,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Builds `target = target op value` for compound assignment and `++`/`--`, or `None` if
+    /// `target` can't be assigned to. An object or index that could have side effects is
+    /// evaluated once into a temporary, wrapping the assignment in a block.
+    fn compound_assignment(target: &Expr, op: Token, value: Expr) -> Option<Expr> {
+        let mut declarations = Vec::new();
+        let mut once = |ex: &Expr, name: &str| {
+            if matches!(
+                ex.kind,
+                ExprKind::Variable(_) | ExprKind::This(_) | ExprKind::Literal(_)
+            ) {
+                return ex.to_owned();
+            }
+            // Temporaries use names that can't be written in source, so they never clash
+            let temp = Ident::new(format!("<compound {}>", name), ex.span);
+            declarations.push(Stmt::Let(temp, ex.to_owned()));
+            Expr::new(ExprKind::Variable(temp), ex.span)
+        };
+        let target = match &target.kind {
+            ExprKind::Variable(_) => target.to_owned(),
+            ExprKind::Get(object, property) => Expr::get(once(object, "object"), *property),
+            ExprKind::Index(object, index) => {
+                let object = once(object, "object");
+                Expr::index(object, once(index, "index"), target.span)
+            }
+            _ => return None,
+        };
+        let span = target.span.to(value.span);
+        let assignment =
+            Self::assignment_target(&target, Expr::binary(target.to_owned(), op, value))?;
+        if declarations.is_empty() {
+            Some(assignment)
+        } else {
+            Some(Expr::block(declarations, Some(assignment), span))
+        }
+    }
+
     fn compound_assign(&mut self) -> ExprResult {
-        let ex = self.logic_or()?;
-        if self.match_next(vec![PlusEqual, MinusEqual, StarEqual, SlashEqual]) {
+        let ex = self.ternary()?;
+        if self.match_next(vec![
+            PlusEqual,
+            MinusEqual,
+            StarEqual,
+            SlashEqual,
+            PercentEqual,
+        ]) {
             let op_assign = self.previous();
             let right = self.assignment()?;
             let mut op_arithmetic = op_assign.clone();
@@ -254,12 +480,12 @@ impl Parser {
                 MinusEqual => Minus,
                 StarEqual => Star,
                 SlashEqual => Slash,
+                PercentEqual => Percent,
                 _ => unreachable!(),
             };
 
-            let right = Expr::binary(ex.to_owned(), op_arithmetic, right);
-            if let ExprKind::Variable(op) = ex.kind {
-                return Ok(Expr::assign(op, right));
+            if let Some(assignment) = Self::compound_assignment(&ex, op_arithmetic, right) {
+                return Ok(assignment);
             }
 
             self.report_error((&op_assign, "Invalid assignment target.").into());
@@ -267,9 +493,24 @@ impl Parser {
         Ok(ex)
     }
 
+    /// `a ? b : c ? d : e` is `a ? b : (c ? d : e)`, so the else branch recurses.
+    fn ternary(&mut self) -> ExprResult {
+        let condition = self.logic_or()?;
+        if !self.match_next(vec![Question]) {
+            return Ok(condition);
+        }
+        let ex_then = self.expression()?;
+        self.consume(
+            Colon,
+            "Expected ':' after then branch of conditional expression.",
+        )?;
+        let ex_else = self.ternary()?;
+        Ok(Expr::ternary(condition, ex_then, ex_else))
+    }
+
     fn logic_or(&mut self) -> ExprResult {
         let mut ex = self.logic_and()?;
-        while self.match_next(vec![Or]) {
+        while self.match_next(vec![Or, PipePipe]) {
             let op = self.previous();
             let right = self.logic_and()?;
             ex = Expr::logical(ex, op, right);
@@ -279,7 +520,7 @@ impl Parser {
 
     fn logic_and(&mut self) -> ExprResult {
         let mut ex = self.equality()?;
-        while self.match_next(vec![And]) {
+        while self.match_next(vec![And, AmpAmp]) {
             let op = self.previous();
             let right = self.equality()?;
             ex = Expr::logical(ex, op, right);
@@ -288,8 +529,41 @@ impl Parser {
     }
 
     fn equality(&mut self) -> ExprResult {
-        let mut ex = self.comparison()?;
+        let mut ex = self.bit_or()?;
         while self.match_next(vec![BangEqual, EqualEqual]) {
+            let op = self.previous();
+            let right = self.bit_or()?;
+            ex = Expr::binary(ex, op, right);
+        }
+        Ok(ex)
+    }
+
+    /// The bitwise operators sit between equality and comparison, with `&` binding tightest
+    /// and `|` loosest as in C, so `a & b == c` is `(a & b) == c` but `a & b < c` is
+    /// `a & (b < c)`.
+    fn bit_or(&mut self) -> ExprResult {
+        let mut ex = self.bit_xor()?;
+        while self.match_next(vec![Pipe]) {
+            let op = self.previous();
+            let right = self.bit_xor()?;
+            ex = Expr::binary(ex, op, right);
+        }
+        Ok(ex)
+    }
+
+    fn bit_xor(&mut self) -> ExprResult {
+        let mut ex = self.bit_and()?;
+        while self.match_next(vec![Caret]) {
+            let op = self.previous();
+            let right = self.bit_and()?;
+            ex = Expr::binary(ex, op, right);
+        }
+        Ok(ex)
+    }
+
+    fn bit_and(&mut self) -> ExprResult {
+        let mut ex = self.comparison()?;
+        while self.match_next(vec![Ampersand]) {
             let op = self.previous();
             let right = self.comparison()?;
             ex = Expr::binary(ex, op, right);
@@ -298,8 +572,75 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> ExprResult {
-        let mut ex = self.term()?;
+        let ex = self.shift()?;
+        let mut ops = Vec::new();
+        let mut operands = Vec::new();
         while self.match_next(vec![Greater, GreaterEqual, Less, LessEqual]) {
+            ops.push(self.previous());
+            operands.push(self.shift()?);
+        }
+        let mut ex = match ops.len() {
+            0 => ex,
+            1 => Expr::binary(ex, ops.remove(0), operands.remove(0)),
+            _ => Self::comparison_chain(ex, ops, operands),
+        };
+        while self.match_next(vec![Is]) {
+            ex = self.type_check(ex)?;
+        }
+        Ok(ex)
+    }
+
+    fn type_check(&mut self, ex: Expr) -> ExprResult {
+        let name = self.consume(Identifier, "Expected a type name after 'is'.")?;
+        let Some(type_name) = TypeName::from_name(&name.lexeme) else {
+            let names = TypeName::ALL.map(|type_name| type_name.as_str());
+            let message = match closest_match(&name.lexeme, names.iter().copied()) {
+                Some(suggestion) => format!(
+                    "Unknown type '{}'. Did you mean '{}'?",
+                    name.lexeme, suggestion
+                ),
+                None => format!("Unknown type '{}'.", name.lexeme),
+            };
+            return Err((&name, message).into());
+        };
+        Ok(Expr::is(ex, type_name, name.span))
+    }
+
+    /// Desugars `a < b < c` into `{ let t; a < (t = b) and t < c }` so that each operand is
+    /// evaluated at most once and in source order.
+    fn comparison_chain(first: Expr, ops: Vec<Token>, operands: Vec<Expr>) -> Expr {
+        let span = first.span.to(operands[operands.len() - 1].span);
+        let last = operands.len() - 1;
+        let mut declarations = Vec::new();
+        let mut chain: Option<Expr> = None;
+        let mut left = first;
+        for (i, (op, operand)) in ops.into_iter().zip(operands).enumerate() {
+            let (right, next_left) = if i < last {
+                // Temporaries use names that can't be written in source, so they never clash
+                let temp = Ident::new(format!("<chain {}>", i), operand.span);
+                declarations.push(Stmt::Let(temp, Expr::literal_null(operand.span)));
+                let read = Expr::new(ExprKind::Variable(temp), operand.span);
+                (Expr::assign(temp, operand), Some(read))
+            } else {
+                (operand, None)
+            };
+            let and = Token::new(And, "and".into(), op.span);
+            let comparison = Expr::binary(left, op, right);
+            chain = Some(match chain {
+                Some(chain) => Expr::logical(chain, and, comparison),
+                None => comparison,
+            });
+            match next_left {
+                Some(next_left) => left = next_left,
+                None => break,
+            }
+        }
+        Expr::block(declarations, chain, span)
+    }
+
+    fn shift(&mut self) -> ExprResult {
+        let mut ex = self.term()?;
+        while self.match_next(vec![LessLess, GreaterGreater]) {
             let op = self.previous();
             let right = self.term()?;
             ex = Expr::binary(ex, op, right);
@@ -319,7 +660,7 @@ impl Parser {
 
     fn factor(&mut self) -> ExprResult {
         let mut ex = self.unary()?;
-        while self.match_next(vec![Slash, Star]) {
+        while self.match_next(vec![Slash, TildeSlash, Star, Percent]) {
             let op = self.previous();
             let right = self.unary()?;
             ex = Expr::binary(ex, op, right);
@@ -328,12 +669,24 @@ impl Parser {
     }
 
     fn unary(&mut self) -> ExprResult {
-        if self.match_next(vec![Bang, Minus]) {
+        if self.match_next(vec![Bang, Minus, Tilde]) {
             let op = self.previous();
             let ex = self.unary()?;
             return Ok(Expr::unary(op, ex));
         }
-        self.inc_dec()
+        self.power()
+    }
+
+    /// `**` binds tighter than a unary operator on its left, so `-2 ** 2` is `-(2 ** 2)`, but
+    /// its right operand may itself be unary, and it is right-associative.
+    fn power(&mut self) -> ExprResult {
+        let ex = self.inc_dec()?;
+        if self.match_next(vec![StarStar]) {
+            let op = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::binary(ex, op, right));
+        }
+        Ok(ex)
     }
 
     fn inc_dec(&mut self) -> ExprResult {
@@ -346,13 +699,10 @@ impl Parser {
                 MinusMinus => Minus,
                 _ => unreachable!(),
             };
-            let right = Expr::binary(
-                ex.to_owned(),
-                op_expanded.to_owned(),
-                Expr::literal_number(1.0, ex.span.to(op_expanded.span)),
-            );
-            if let ExprKind::Variable(op) = ex.kind {
-                return Ok(Expr::assign(op, right));
+            // The implicit `1` is attributed to the operator that implies it
+            let one = Expr::literal_int(1, op_expanded.span);
+            if let Some(assignment) = Self::compound_assignment(&ex, op_expanded.to_owned(), one) {
+                return Ok(assignment);
             }
             self.report_error((&op_expanded, "Invalid increment/decrement target.").into());
         }
@@ -364,6 +714,13 @@ impl Parser {
         loop {
             if self.match_next(vec![LeftParen]) {
                 ex = self.finish_call(&ex)?;
+            } else if self.match_next(vec![Dot]) {
+                let name = self.consume(Identifier, "Expected property name after '.'.")?;
+                ex = Expr::get(ex, Ident::from_token(name));
+            } else if self.match_next(vec![LeftBracket]) {
+                let index = self.expression()?;
+                let right_bracket = self.consume(RightBracket, "Expected ']' after index.")?;
+                ex = Expr::index(ex, index, right_bracket.span);
             } else {
                 break;
             }
@@ -386,7 +743,7 @@ impl Parser {
                     )
                 }
                 arguments.push(self.expression()?);
-                if !self.match_next(vec![Comma]) {
+                if !self.match_next(vec![Comma]) || self.check(&RightParen) {
                     break;
                 }
             }
@@ -402,6 +759,10 @@ impl Parser {
     fn primary(&mut self) -> ExprResult {
         let token = self.peek();
         match token.kind {
+            LeftBrace if self.is_map_start() => self.map(),
+            LeftBrace => self.block_expr(),
+            If => self.if_expr(),
+            Fn => self.lambda(),
             False => {
                 let token = self.advance();
                 Ok(Expr::literal_bool(false, token.span))
@@ -414,6 +775,10 @@ impl Parser {
                 let token = self.advance();
                 Ok(Expr::literal_null(token.span))
             }
+            Int(num) => {
+                let token = self.advance();
+                Ok(Expr::literal_int(num, token.span))
+            }
             Number(num) => {
                 let token = self.advance();
                 Ok(Expr::literal_number(num, token.span))
@@ -422,18 +787,35 @@ impl Parser {
                 let token = self.advance();
                 Ok(Expr::literal_string(str, token.span))
             }
+            Interpolation(_) => self.interpolation(),
+            LeftBracket => self.array(),
             LeftParen => {
                 self.advance();
                 let ex = self.expression()?;
+                if self.check(&Comma) {
+                    return self.tuple(token, ex);
+                }
                 self.consume(RightParen, "Expected ')' after expression.")?;
                 Ok(Expr::grouping(ex))
             }
+            Identifier if self.is_record_start() => self.record(),
             Identifier => {
                 self.advance();
                 Ok(Expr::var(token))
             }
+            This => {
+                self.advance();
+                Ok(Expr::this(token))
+            }
+            Super => {
+                self.advance();
+                self.consume(Dot, "Expected '.' after 'super'.")?;
+                let method = self.consume(Identifier, "Expected superclass method name.")?;
+                Ok(Expr::super_access(token, method))
+            }
             BangEqual | EqualEqual | Greater | GreaterEqual | Less | LessEqual | Plus | Slash
-            | Star => {
+            | TildeSlash | Star | Percent | Ampersand | Pipe | Caret | LessLess
+            | GreaterGreater => {
                 self.advance();
                 Err((
                     &token,
@@ -445,6 +827,206 @@ impl Parser {
         }
     }
 
+    fn block_expr(&mut self) -> ExprResult {
+        let left_brace = self.advance();
+        let mut statements = Vec::new();
+        let mut value = None;
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            match self.peek().kind {
+                If => {
+                    let (start, errors) = (self.current, self.errors.len());
+                    let statement = self.declaration();
+                    if statement.is_none() || self.errors.len() > errors {
+                        // Branches that aren't statements make an if expression, which can be
+                        // the block's value as in `{ if (c) 1 else 2 }`
+                        let (end, reported) = (self.current, self.errors.split_off(errors));
+                        self.current = start;
+                        match self.expression() {
+                            Ok(ex) if self.errors.len() == errors && self.check(&RightBrace) => {
+                                value = Some(ex);
+                            }
+                            Ok(ex) if self.errors.len() == errors && self.check(&Semicolon) => {
+                                self.advance();
+                                statements.push(Stmt::Expression(ex));
+                            }
+                            // Neither form parses, so the statement's errors stand
+                            _ => {
+                                self.current = end;
+                                self.errors.truncate(errors);
+                                self.errors.extend(reported);
+                                statements.extend(statement);
+                            }
+                        }
+                    } else {
+                        statements.extend(statement);
+                    }
+                }
+                Let | Const | Fn | Class | Enum | Struct | LeftBrace | Return | Break
+                | Continue | Print | While | Do | For => {
+                    if let Some(statement) = self.declaration() {
+                        statements.push(statement);
+                    }
+                }
+                _ => {
+                    // A trailing expression without a semicolon becomes the block's value
+                    let start = self.current;
+                    let ex = match self.expression() {
+                        Ok(ex) => ex,
+                        Err(e) => {
+                            self.recover(start, e);
+                            continue;
+                        }
+                    };
+                    if self.check(&RightBrace) {
+                        value = Some(ex);
+                    } else if let Err(e) = self.consume_semicolon("Expected ';' after expression.")
+                    {
+                        self.recover(start, e);
+                    } else {
+                        statements.push(Stmt::Expression(ex));
+                    }
+                }
+            }
+        }
+        let right_brace = self.consume(RightBrace, "Excepted '}' after block.")?;
+        Ok(Expr::block(
+            statements,
+            value,
+            left_brace.span.to(right_brace.span),
+        ))
+    }
+
+    /// A comma after the first element makes a tuple, so `(a,)` is a one-element tuple while
+    /// `(a)` is just a grouping.
+    fn tuple(&mut self, left_paren: Token, first: Expr) -> ExprResult {
+        let mut elements = vec![first];
+        while self.match_next(vec![Comma]) {
+            if self.check(&RightParen) {
+                break;
+            }
+            elements.push(self.expression()?);
+        }
+        let right_paren = self.consume(RightParen, "Expected ')' after tuple elements.")?;
+        Ok(Expr::tuple(elements, left_paren.span.to(right_paren.span)))
+    }
+
+    fn array(&mut self) -> ExprResult {
+        let left_bracket = self.advance();
+        let mut elements = Vec::new();
+        while !self.check(&RightBracket) && !self.is_at_end() {
+            elements.push(self.expression()?);
+            if !self.match_next(vec![Comma]) {
+                break;
+            }
+        }
+        let right_bracket = self.consume(RightBracket, "Expected ']' after array elements.")?;
+        Ok(Expr::array(
+            elements,
+            left_bracket.span.to(right_bracket.span),
+        ))
+    }
+
+    fn interpolation(&mut self) -> ExprResult {
+        let start = self.peek().span;
+        let mut parts = Vec::new();
+        loop {
+            let token = self.advance();
+            match token.kind {
+                Interpolation(str) => {
+                    parts.push(Expr::literal_string(str, token.span));
+                    parts.push(self.expression()?);
+                }
+                String(str) => {
+                    parts.push(Expr::literal_string(str, token.span));
+                    return Ok(Expr::interpolation(parts, start.to(token.span)));
+                }
+                _ => return Err((&token, "Expected '}' after interpolated expression.").into()),
+            }
+        }
+    }
+
+    /// A record construction looks like `Name { field: ... }` or `Name {}`.
+    fn is_record_start(&self) -> bool {
+        self.peek_nth(1) == Some(&LeftBrace)
+            && match self.peek_nth(2) {
+                Some(RightBrace) => true,
+                Some(Identifier) => self.peek_nth(3) == Some(&Colon),
+                _ => false,
+            }
+    }
+
+    /// A map literal starts with a literal or variable key and a colon, or is the empty map
+    /// `{:}`. Any other brace in expression position opens a block.
+    fn is_map_start(&self) -> bool {
+        match self.peek_nth(1) {
+            Some(String(_) | Int(_) | Number(_) | True | False | Null | Identifier) => {
+                self.peek_nth(2) == Some(&Colon)
+            }
+            Some(Colon) => self.peek_nth(2) == Some(&RightBrace),
+            _ => false,
+        }
+    }
+
+    fn map(&mut self) -> ExprResult {
+        let left_brace = self.advance();
+        let mut entries = Vec::new();
+        if !self.match_next(vec![Colon]) {
+            while !self.check(&RightBrace) && !self.is_at_end() {
+                let key = self.expression()?;
+                self.consume(Colon, "Expected ':' after map key.")?;
+                entries.push((key, self.expression()?));
+                if !self.match_next(vec![Comma]) {
+                    break;
+                }
+            }
+        }
+        let right_brace = self.consume(RightBrace, "Expected '}' after map entries.")?;
+        Ok(Expr::map(entries, left_brace.span.to(right_brace.span)))
+    }
+
+    fn record(&mut self) -> ExprResult {
+        let name = self.advance();
+        self.advance();
+        let mut fields = Vec::new();
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            let field = self.consume(Identifier, "Expected field name.")?;
+            self.consume(Colon, "Expected ':' after field name.")?;
+            fields.push((Ident::from_token(field), self.expression()?));
+            if !self.match_next(vec![Comma]) {
+                break;
+            }
+        }
+        let right_brace = self.consume(RightBrace, "Expected '}' after record fields.")?;
+        let span = name.span.to(right_brace.span);
+        Ok(Expr::record(Ident::from_token(name), fields, span))
+    }
+
+    fn if_expr(&mut self) -> ExprResult {
+        let token = self.advance();
+        self.consume(LeftParen, "Expected '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(RightParen, "Expected ')' after if condition.")?;
+        let then_branch = self.expression()?;
+        self.consume(Else, "Expected 'else' branch in if expression.")?;
+        let else_branch = self.expression()?;
+        let span = token.span.to(else_branch.span);
+        Ok(Expr::if_else(condition, then_branch, else_branch, span))
+    }
+
+    fn lambda(&mut self) -> ExprResult {
+        let token = self.advance();
+        let name = if self.check(&Identifier) {
+            Some(Ident::from_token(self.advance()))
+        } else {
+            None
+        };
+        self.consume(LeftParen, "Expected '(' after 'fn'.")?;
+        let parameters = self.parameters()?;
+        let body = self.function_body()?;
+        let span = token.span.to(self.previous().span);
+        Ok(Expr::lambda(name, parameters, body, span))
+    }
+
     fn match_next(&mut self, types: Vec<TokenKind>) -> bool {
         for t_type in &types {
             if self.check(t_type) {
@@ -470,6 +1052,27 @@ impl Parser {
         self.previous()
     }
 
+    fn has_unclosed_brackets(&self) -> bool {
+        let depth = self
+            .tokens
+            .iter()
+            .fold(0isize, |depth, token| match token.kind {
+                LeftParen | LeftBrace | LeftBracket => depth + 1,
+                RightParen | RightBrace | RightBracket => depth - 1,
+                _ => depth,
+            });
+        depth > 0
+    }
+
+    /// How many more `{` than `}` there are among `tokens`.
+    fn brace_depth(tokens: &[Token]) -> isize {
+        tokens.iter().fold(0, |depth, token| match token.kind {
+            LeftBrace => depth + 1,
+            RightBrace => depth - 1,
+            _ => depth,
+        })
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().kind == EOF
     }
@@ -478,10 +1081,24 @@ impl Parser {
         self.tokens[self.current].to_owned()
     }
 
+    fn peek_nth(&self, n: usize) -> Option<&TokenKind> {
+        self.tokens.get(self.current + n).map(|token| &token.kind)
+    }
+
     fn previous(&self) -> Token {
         self.tokens[self.current - 1].to_owned()
     }
 
+    /// A missing `;` is reported in the gap after the previous token, where the semicolon
+    /// belongs, rather than at whichever token happens to follow it.
+    fn consume_semicolon(&mut self, message: &'static str) -> Result<Token, SpannedError> {
+        if self.check(&Semicolon) {
+            return Ok(self.advance());
+        }
+        let span = self.previous().span.between(self.peek().span);
+        Err((span, message).into())
+    }
+
     fn consume(&mut self, t_type: TokenKind, message: &'static str) -> Result<Token, SpannedError> {
         if self.check(&t_type) {
             Ok(self.advance())
@@ -490,23 +1107,40 @@ impl Parser {
         }
     }
 
-    fn synchronize(&mut self) {
-        self.advance();
+    /// Skips to the start of the next statement after one that failed to parse, which began at
+    /// token `start`: past a `;` or up to a statement keyword. Braces the failed statement
+    /// opened are skipped with it, and inside a block the `}` closing the block is left for
+    /// it to consume, so that one mistake doesn't spill into the code around it.
+    fn synchronize(&mut self, start: usize) {
+        let mut depth = Self::brace_depth(&self.tokens[start..self.current]).max(0);
+        let in_block = Self::brace_depth(&self.tokens[..start]) > 0;
+        let mut skipped = false;
         while !self.is_at_end() {
-            if self.previous().kind == Semicolon {
-                return;
-            }
-            match self.peek().kind {
-                Class | Fn | Let | For | If | While | Print | Return => {
+            if depth == 0 {
+                if skipped && self.previous().kind == Semicolon {
                     return;
                 }
+                match self.peek().kind {
+                    RightBrace if in_block => return,
+                    Class | Enum | Struct | Fn | Let | Const | For | If | While | Do | Print
+                    | Return | Break | Continue
+                        if skipped =>
+                    {
+                        return;
+                    }
+                    _ => (),
+                }
+            }
+            match self.advance().kind {
+                LeftBrace => depth += 1,
+                RightBrace if depth > 0 => depth -= 1,
                 _ => (),
             }
-            self.advance();
+            skipped = true;
         }
     }
 
     fn report_error(&mut self, e: SpannedError) {
-        self.errors.push(e);
+        self.errors.push(e.with_phase(Phase::Parse));
     }
 }
@@ -0,0 +1,912 @@
+use std::fmt::{self, Write};
+
+use crate::{BinaryOp, Expr, ExprKind, Ident, Literal, LogicOp, Stmt, Token};
+
+const INDENT: &str = "  ";
+const SOURCE_INDENT: &str = "    ";
+
+/// Renders one token per line as `line:start..end`, the token kind and its lexeme.
+pub fn tokens_to_string(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        let span = format!(
+            "{}:{}..{}",
+            token.span.line, token.span.start, token.span.end
+        );
+        let line = format!("{:<12} {:<14} {}", span, token.kind.as_str(), token.lexeme);
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    out
+}
+
+/// Renders a program as an indented tree, one node per line with its children beneath it.
+pub fn ast_to_string(statements: &[Stmt]) -> String {
+    let mut printer = TreePrinter::default();
+    for stmt in statements {
+        printer.stmt(stmt);
+    }
+    printer.out
+}
+
+#[derive(Default)]
+struct TreePrinter {
+    out: String,
+    depth: usize,
+}
+impl TreePrinter {
+    fn node(&mut self, label: &str) {
+        writeln!(self.out, "{}{}", INDENT.repeat(self.depth), label).unwrap();
+    }
+
+    fn nested(&mut self, f: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        f(self);
+        self.depth -= 1;
+    }
+
+    fn labelled_stmt(&mut self, label: &str, stmt: &Stmt) {
+        self.node(label);
+        self.nested(|p| p.stmt(stmt));
+    }
+
+    fn labelled_expr(&mut self, label: &str, ex: &Expr) {
+        self.node(label);
+        self.nested(|p| p.expr(ex));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(statements) => {
+                self.node("Block");
+                self.nested(|p| statements.iter().for_each(|st| p.stmt(st)));
+            }
+            Stmt::Break(_) => self.node("Break"),
+            Stmt::Class(id, superclass, methods) => {
+                match superclass {
+                    Some(superclass) => self.node(&format!("Class {} < {}", id.symbol, superclass)),
+                    None => self.node(&format!("Class {}", id.symbol)),
+                }
+                self.nested(|p| methods.iter().for_each(|st| p.stmt(st)));
+            }
+            Stmt::Continue(_) => self.node("Continue"),
+            Stmt::Enum(id, variants) => {
+                self.node(&format!("Enum {} {{{}}}", id.symbol, idents(variants)))
+            }
+            Stmt::Expression(ex) => {
+                self.node("Expression");
+                self.nested(|p| p.expr(ex));
+            }
+            Stmt::Function(id, params, body) => {
+                self.node(&format!("Function {}({})", id.symbol, idents(params)));
+                self.nested(|p| body.iter().for_each(|st| p.stmt(st)));
+            }
+            Stmt::If(condition, st_then, st_else) => {
+                self.node("If");
+                self.nested(|p| {
+                    p.expr(condition);
+                    p.labelled_stmt("Then", st_then);
+                    if let Some(st_else) = st_else {
+                        p.labelled_stmt("Else", st_else);
+                    }
+                });
+            }
+            Stmt::Print(expressions) => {
+                self.node("Print");
+                self.nested(|p| expressions.iter().for_each(|ex| p.expr(ex)));
+            }
+            Stmt::Return(ex) => {
+                self.node("Return");
+                self.nested(|p| p.expr(ex));
+            }
+            Stmt::Let(id, initializer) => {
+                self.node(&format!("Let {}", id.symbol));
+                self.nested(|p| p.expr(initializer));
+            }
+            Stmt::Const(id, initializer) => {
+                self.node(&format!("Const {}", id.symbol));
+                self.nested(|p| p.expr(initializer));
+            }
+            Stmt::LetTuple(ids, initializer) => {
+                self.node(&format!("Let ({})", idents(ids)));
+                self.nested(|p| p.expr(initializer));
+            }
+            Stmt::Struct(id, fields) => {
+                self.node(&format!("Struct {} {{{}}}", id.symbol, idents(fields)))
+            }
+            Stmt::DoWhile(body, condition) => {
+                self.node("DoWhile");
+                self.nested(|p| {
+                    p.stmt(body);
+                    p.expr(condition);
+                });
+            }
+            Stmt::ForEach(id, iterable, body) => {
+                self.node(&format!("ForEach {}", id.symbol));
+                self.nested(|p| {
+                    p.expr(iterable);
+                    p.stmt(body);
+                });
+            }
+            Stmt::While(condition, body, increment) => {
+                self.node("While");
+                self.nested(|p| {
+                    p.expr(condition);
+                    p.stmt(body);
+                    if let Some(increment) = increment {
+                        p.labelled_expr("Increment", increment);
+                    }
+                });
+            }
+        }
+    }
+
+    fn expr(&mut self, ex: &Expr) {
+        match &ex.kind {
+            ExprKind::Array(elements) => {
+                self.node("Array");
+                self.nested(|p| elements.iter().for_each(|element| p.expr(element)));
+            }
+            ExprKind::Assign(id, value) => {
+                self.node(&format!("Assign {}", id.symbol));
+                self.nested(|p| p.expr(value));
+            }
+            ExprKind::Binary(left, op, right) => {
+                self.node(&format!("Binary {}", op.as_str()));
+                self.nested(|p| {
+                    p.expr(left);
+                    p.expr(right);
+                });
+            }
+            ExprKind::Block(statements, value) => {
+                self.node("Block");
+                self.nested(|p| {
+                    statements.iter().for_each(|st| p.stmt(st));
+                    if let Some(value) = value {
+                        p.labelled_expr("Value", value);
+                    }
+                });
+            }
+            ExprKind::Call(callee, _, args) => {
+                self.node("Call");
+                self.nested(|p| {
+                    p.expr(callee);
+                    args.iter().for_each(|arg| p.expr(arg));
+                });
+            }
+            ExprKind::Get(object, property) => {
+                self.node(&format!("Get .{}", property.symbol));
+                self.nested(|p| p.expr(object));
+            }
+            ExprKind::Grouping(inner) => {
+                self.node("Grouping");
+                self.nested(|p| p.expr(inner));
+            }
+            ExprKind::If(condition, ex_then, ex_else) => {
+                self.node("If");
+                self.nested(|p| {
+                    p.expr(condition);
+                    p.labelled_expr("Then", ex_then);
+                    p.labelled_expr("Else", ex_else);
+                });
+            }
+            ExprKind::Index(object, index) => {
+                self.node("Index");
+                self.nested(|p| {
+                    p.expr(object);
+                    p.expr(index);
+                });
+            }
+            ExprKind::Interpolation(parts) => {
+                self.node("Interpolation");
+                self.nested(|p| parts.iter().for_each(|part| p.expr(part)));
+            }
+            ExprKind::Is(ex, type_name) => {
+                self.node(&format!("Is {}", type_name.as_str()));
+                self.nested(|p| p.expr(ex));
+            }
+            ExprKind::Map(entries) => {
+                self.node("Map");
+                self.nested(|p| {
+                    for (key, value) in entries {
+                        p.expr(key);
+                        p.nested(|p| p.expr(value));
+                    }
+                });
+            }
+            ExprKind::Lambda(name, params, body) => {
+                let name = name.map_or(String::new(), |name| format!(" {}", name.symbol));
+                self.node(&format!("Lambda{}({})", name, idents(params)));
+                self.nested(|p| body.iter().for_each(|st| p.stmt(st)));
+            }
+            ExprKind::Literal(lit) => self.node(&format!("Literal {}", literal(lit))),
+            ExprKind::Logical(left, op, right) => {
+                self.node(&format!("Logical {}", op.as_str()));
+                self.nested(|p| {
+                    p.expr(left);
+                    p.expr(right);
+                });
+            }
+            ExprKind::Record(name, fields) => {
+                self.node(&format!("Record {}", name.symbol));
+                self.nested(|p| {
+                    for (field, value) in fields {
+                        p.labelled_expr(&format!("Field {}", field.symbol), value);
+                    }
+                });
+            }
+            ExprKind::Set(object, property, value) => {
+                self.node(&format!("Set .{}", property.symbol));
+                self.nested(|p| {
+                    p.expr(object);
+                    p.expr(value);
+                });
+            }
+            ExprKind::SetIndex(object, index, value) => {
+                self.node("SetIndex");
+                self.nested(|p| {
+                    p.expr(object);
+                    p.expr(index);
+                    p.expr(value);
+                });
+            }
+            ExprKind::Ternary(condition, ex_then, ex_else) => {
+                self.node("Ternary");
+                self.nested(|p| {
+                    p.expr(condition);
+                    p.labelled_expr("Then", ex_then);
+                    p.labelled_expr("Else", ex_else);
+                });
+            }
+            ExprKind::Tuple(elements) => {
+                self.node("Tuple");
+                self.nested(|p| elements.iter().for_each(|element| p.expr(element)));
+            }
+            ExprKind::Unary(op, right) => {
+                self.node(&format!("Unary {}", op.as_str()));
+                self.nested(|p| p.expr(right));
+            }
+            ExprKind::Super(_, method) => self.node(&format!("Super {}", method.symbol)),
+            ExprKind::This(_) => self.node("This"),
+            ExprKind::Variable(id) => self.node(&format!("Variable {}", id.symbol)),
+        }
+    }
+}
+
+fn idents(ids: &[Ident]) -> String {
+    ids.iter()
+        .map(|id| id.symbol.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::String(str) => format!("\"{}\"", str),
+        _ => lit.as_str(),
+    }
+}
+
+// Source printing. `Display` for statements and expressions writes source on a single line
+// that parses back into the same tree, adding parentheses only where precedence requires
+// them. `to_source` writes the same with each nested statement on its own indented line.
+
+/// Binding strength of each level of the grammar, weakest first.
+const ASSIGNMENT: u8 = 1;
+const TERNARY: u8 = 2;
+const OR: u8 = 3;
+const AND: u8 = 4;
+const EQUALITY: u8 = 5;
+const BIT_OR: u8 = 6;
+const BIT_XOR: u8 = 7;
+const BIT_AND: u8 = 8;
+const COMPARISON: u8 = 9;
+const SHIFT: u8 = 10;
+const TERM: u8 = 11;
+const FACTOR: u8 = 12;
+const UNARY: u8 = 13;
+const POWER: u8 = 14;
+const CALL: u8 = 15;
+const PRIMARY: u8 = 16;
+
+/// Renders a program as source, one statement per line and blocks indented beneath them.
+pub fn to_source(statements: &[Stmt]) -> String {
+    struct Program<'a>(&'a [Stmt]);
+    impl fmt::Display for Program<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut writer = SourceWriter::new(f, Some(0));
+            for statement in self.0 {
+                writer.stmt(statement)?;
+                writer.str("\n")?;
+            }
+            Ok(())
+        }
+    }
+    Program(statements).to_string()
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        SourceWriter::new(f, None).stmt(self)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        SourceWriter::new(f, None).expr(self, ASSIGNMENT)
+    }
+}
+
+struct SourceWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    /// How deeply blocks are nested, or `None` to write them on a single line.
+    depth: Option<usize>,
+}
+impl<'a, 'b> SourceWriter<'a, 'b> {
+    fn new(f: &'a mut fmt::Formatter<'b>, depth: Option<usize>) -> Self {
+        Self { f, depth }
+    }
+
+    /// Writes into a string instead, at the same depth.
+    fn render(&self, write: impl Fn(&mut SourceWriter) -> fmt::Result) -> String {
+        struct Render<F>(Option<usize>, F);
+        impl<F: Fn(&mut SourceWriter) -> fmt::Result> fmt::Display for Render<F> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                (self.1)(&mut SourceWriter::new(f, self.0))
+            }
+        }
+        Render(self.depth, write).to_string()
+    }
+
+    fn str(&mut self, text: &str) -> fmt::Result {
+        self.f.write_str(text)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> fmt::Result {
+        match stmt {
+            Stmt::Block(statements) => self.block(statements, None),
+            Stmt::Break(_) => self.str("break;"),
+            Stmt::Continue(_) => self.str("continue;"),
+            Stmt::Class(id, superclass, methods) => {
+                write!(self.f, "class {} ", id.symbol)?;
+                if let Some(superclass) = superclass {
+                    self.str("< ")?;
+                    self.expr(superclass, ASSIGNMENT)?;
+                    self.str(" ")?;
+                }
+                self.block(methods, None)
+            }
+            Stmt::Enum(id, variants) => {
+                write!(self.f, "enum {} {{{}}}", id.symbol, braced(variants))
+            }
+            Stmt::Expression(ex) => {
+                self.statement_expr(ex)?;
+                self.str(";")
+            }
+            Stmt::Function(id, params, body) => {
+                write!(self.f, "fn {}({}) ", id.symbol, idents(params))?;
+                self.block(body, None)
+            }
+            Stmt::If(condition, st_then, st_else) => {
+                self.condition("if", condition)?;
+                self.str(" ")?;
+                match st_else {
+                    // Braces keep the else from attaching to the nested if
+                    Some(st_else) if matches!(**st_then, Stmt::If(_, _, None)) => {
+                        self.block(std::slice::from_ref(st_then), None)?;
+                        self.str(" else ")?;
+                        self.stmt(st_else)
+                    }
+                    Some(st_else) => {
+                        self.stmt(st_then)?;
+                        self.str(" else ")?;
+                        self.stmt(st_else)
+                    }
+                    None => self.stmt(st_then),
+                }
+            }
+            Stmt::Print(expressions) => {
+                self.str("print ")?;
+                self.exprs(expressions)?;
+                self.str(";")
+            }
+            Stmt::Return(ex) => {
+                self.str("return ")?;
+                self.expr(ex, ASSIGNMENT)?;
+                self.str(";")
+            }
+            Stmt::Let(id, initializer) => {
+                write!(self.f, "let {} = ", id.symbol)?;
+                self.expr(initializer, ASSIGNMENT)?;
+                self.str(";")
+            }
+            Stmt::Const(id, initializer) => {
+                write!(self.f, "const {} = ", id.symbol)?;
+                self.expr(initializer, ASSIGNMENT)?;
+                self.str(";")
+            }
+            Stmt::LetTuple(ids, initializer) => {
+                write!(self.f, "let ({}) = ", idents(ids))?;
+                self.expr(initializer, ASSIGNMENT)?;
+                self.str(";")
+            }
+            Stmt::Struct(id, fields) => {
+                write!(self.f, "struct {} {{{}}}", id.symbol, braced(fields))
+            }
+            Stmt::DoWhile(body, condition) => {
+                self.str("do ")?;
+                self.stmt(body)?;
+                self.str(" ")?;
+                self.condition("while", condition)?;
+                self.str(";")
+            }
+            Stmt::ForEach(id, iterable, body) => {
+                write!(self.f, "for (let {} in ", id.symbol)?;
+                self.expr(iterable, ASSIGNMENT)?;
+                self.str(") ")?;
+                self.stmt(body)
+            }
+            Stmt::While(condition, body, None) => {
+                self.condition("while", condition)?;
+                self.str(" ")?;
+                self.stmt(body)
+            }
+            // Only a desugared `for` has an increment, and its initializer is printed as the
+            // enclosing block's first statement
+            Stmt::While(condition, body, Some(increment)) => {
+                self.str("for (; ")?;
+                self.expr(condition, ASSIGNMENT)?;
+                self.str("; ")?;
+                self.expr(increment, ASSIGNMENT)?;
+                self.str(") ")?;
+                self.stmt(body)
+            }
+        }
+    }
+
+    /// Writes `keyword (condition)`.
+    fn condition(&mut self, keyword: &str, condition: &Expr) -> fmt::Result {
+        write!(self.f, "{} (", keyword)?;
+        self.expr(condition, ASSIGNMENT)?;
+        self.str(")")
+    }
+
+    fn block(&mut self, statements: &[Stmt], value: Option<&Expr>) -> fmt::Result {
+        if statements.is_empty() && value.is_none() {
+            return self.str("{}");
+        }
+        let Some(depth) = self.depth else {
+            self.str("{")?;
+            for statement in statements {
+                self.str(" ")?;
+                self.stmt(statement)?;
+            }
+            if let Some(value) = value {
+                self.str(" ")?;
+                self.block_value(value)?;
+            }
+            return self.str(" }");
+        };
+        let indent = SOURCE_INDENT.repeat(depth + 1);
+        self.str("{")?;
+        self.depth = Some(depth + 1);
+        for statement in statements {
+            write!(self.f, "\n{}", indent)?;
+            self.stmt(statement)?;
+        }
+        if let Some(value) = value {
+            write!(self.f, "\n{}", indent)?;
+            self.block_value(value)?;
+        }
+        self.depth = Some(depth);
+        write!(self.f, "\n{}}}", SOURCE_INDENT.repeat(depth))
+    }
+
+    /// Writes the value at the end of a block. An `if` expression there is left bare unless it
+    /// would read as an `if` statement.
+    fn block_value(&mut self, ex: &Expr) -> fmt::Result {
+        if matches!(ex.kind, ExprKind::If(..)) && !reads_as_if_statement(ex) {
+            self.expr(ex, ASSIGNMENT)
+        } else {
+            self.statement_expr(ex)
+        }
+    }
+
+    /// Writes an expression where a statement could start, parenthesized if it would
+    /// otherwise read as a block, `if` or function statement.
+    fn statement_expr(&mut self, ex: &Expr) -> fmt::Result {
+        if starts_like_statement(ex) {
+            self.str("(")?;
+            self.expr(ex, ASSIGNMENT)?;
+            self.str(")")
+        } else {
+            self.expr(ex, ASSIGNMENT)
+        }
+    }
+
+    fn exprs(&mut self, expressions: &[Expr]) -> fmt::Result {
+        for (i, ex) in expressions.iter().enumerate() {
+            if i > 0 {
+                self.str(", ")?;
+            }
+            self.expr(ex, ASSIGNMENT)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `key: value` pairs between braces, as maps and records share.
+    fn entries<K>(
+        &mut self,
+        entries: &[(K, Expr)],
+        mut key: impl FnMut(&mut Self, &K) -> fmt::Result,
+    ) -> fmt::Result {
+        if entries.is_empty() {
+            return self.str("{}");
+        }
+        self.str("{ ")?;
+        for (i, (k, value)) in entries.iter().enumerate() {
+            if i > 0 {
+                self.str(", ")?;
+            }
+            key(self, k)?;
+            self.str(": ")?;
+            self.expr(value, ASSIGNMENT)?;
+        }
+        self.str(" }")
+    }
+
+    fn expr(&mut self, ex: &Expr, min_precedence: u8) -> fmt::Result {
+        if precedence(ex) < min_precedence {
+            self.str("(")?;
+            self.expr(ex, ASSIGNMENT)?;
+            return self.str(")");
+        }
+        if let Some((first, rest)) = comparison_chain(ex) {
+            self.expr(first, SHIFT)?;
+            for (op, operand) in rest {
+                write!(self.f, " {} ", op.as_str())?;
+                self.expr(operand, SHIFT)?;
+            }
+            return Ok(());
+        }
+        if let Some((target, op, value)) = compound_assignment(ex) {
+            self.expr(&target, CALL)?;
+            write!(self.f, " {}= ", op.as_str())?;
+            return self.expr(value, ASSIGNMENT);
+        }
+        match &ex.kind {
+            ExprKind::Array(elements) => {
+                self.str("[")?;
+                self.exprs(elements)?;
+                self.str("]")
+            }
+            ExprKind::Assign(id, value) => {
+                write!(self.f, "{} = ", id.symbol)?;
+                self.expr(value, ASSIGNMENT)
+            }
+            ExprKind::Binary(left, op, right) => {
+                let level = binary_precedence(op);
+                let (left_min, right_min) = match op {
+                    // `**` takes a call on its left and a unary on its right
+                    BinaryOp::Power => (CALL, UNARY),
+                    // Unparenthesized, `(a < b) < c` would read as a chained comparison
+                    _ if level == COMPARISON => (level + 1, level + 1),
+                    _ => (level, level + 1),
+                };
+                self.expr(left, left_min)?;
+                write!(self.f, " {} ", op.as_str())?;
+                self.expr(right, right_min)
+            }
+            ExprKind::Block(statements, value) => self.block(statements, value.as_deref()),
+            ExprKind::Call(callee, _, args) => {
+                self.expr(callee, CALL)?;
+                self.str("(")?;
+                self.exprs(args)?;
+                self.str(")")
+            }
+            ExprKind::Get(object, property) => {
+                self.expr(object, CALL)?;
+                write!(self.f, ".{}", property.symbol)
+            }
+            ExprKind::Grouping(inner) => {
+                self.str("(")?;
+                self.expr(inner, ASSIGNMENT)?;
+                self.str(")")
+            }
+            ExprKind::If(condition, ex_then, ex_else) => {
+                self.condition("if", condition)?;
+                self.str(" ")?;
+                self.expr(ex_then, ASSIGNMENT)?;
+                self.str(" else ")?;
+                self.expr(ex_else, ASSIGNMENT)
+            }
+            ExprKind::Index(object, index) => {
+                self.expr(object, CALL)?;
+                self.str("[")?;
+                self.expr(index, ASSIGNMENT)?;
+                self.str("]")
+            }
+            ExprKind::Interpolation(parts) => {
+                self.str("\"")?;
+                for (i, part) in parts.iter().enumerate() {
+                    match &part.kind {
+                        ExprKind::Literal(Literal::String(text)) if i % 2 == 0 => {
+                            self.str(&escape(&text.to_string()))?
+                        }
+                        // A string literal stays on one line
+                        _ => write!(self.f, "${{{}}}", part)?,
+                    }
+                }
+                self.str("\"")
+            }
+            ExprKind::Is(ex, type_name) => {
+                self.expr(ex, COMPARISON)?;
+                write!(self.f, " is {}", type_name.as_str())
+            }
+            ExprKind::Lambda(name, params, body) => {
+                self.str("fn")?;
+                if let Some(name) = name {
+                    write!(self.f, " {}", name.symbol)?;
+                }
+                write!(self.f, "({}) ", idents(params))?;
+                self.block(body, None)
+            }
+            ExprKind::Map(entries) if entries.is_empty() => self.str("{:}"),
+            ExprKind::Map(entries) => self.entries(entries, |w, key| w.expr(key, ASSIGNMENT)),
+            ExprKind::Literal(Literal::String(text)) => {
+                let text = text.to_string();
+                // Plain strings would interpolate `${`, so those need the verbatim form. A line
+                // break straight after its opening quotes is dropped, so a leading one is doubled.
+                if text.contains("${") {
+                    let lead = if text.starts_with('\n') { "\n" } else { "" };
+                    write!(self.f, "\"\"\"{}{}\"\"\"", lead, text)
+                } else {
+                    write!(self.f, "\"{}\"", escape(&text))
+                }
+            }
+            ExprKind::Literal(lit) => write!(self.f, "{}", lit),
+            ExprKind::Logical(left, op, right) => {
+                let level = logic_precedence(op);
+                self.expr(left, level)?;
+                write!(self.f, " {} ", op.as_str())?;
+                self.expr(right, level + 1)
+            }
+            ExprKind::Record(name, fields) => {
+                write!(self.f, "{} ", name.symbol)?;
+                self.entries(fields, |w, field| write!(w.f, "{}", field.symbol))
+            }
+            ExprKind::Set(object, property, value) => {
+                self.expr(object, CALL)?;
+                write!(self.f, ".{} = ", property.symbol)?;
+                self.expr(value, ASSIGNMENT)
+            }
+            ExprKind::SetIndex(object, index, value) => {
+                self.expr(object, CALL)?;
+                self.str("[")?;
+                self.expr(index, ASSIGNMENT)?;
+                self.str("] = ")?;
+                self.expr(value, ASSIGNMENT)
+            }
+            ExprKind::Ternary(condition, ex_then, ex_else) => {
+                self.expr(condition, OR)?;
+                self.str(" ? ")?;
+                self.expr(ex_then, ASSIGNMENT)?;
+                self.str(" : ")?;
+                self.expr(ex_else, TERNARY)
+            }
+            ExprKind::Tuple(elements) => {
+                self.str("(")?;
+                self.exprs(elements)?;
+                if elements.len() == 1 {
+                    self.str(",")?;
+                }
+                self.str(")")
+            }
+            ExprKind::Unary(op, right) => {
+                let operand = self.render(|w| w.expr(right, UNARY));
+                // `- -x` must not lex as `--`
+                let space = if operand.starts_with(op.as_str()) {
+                    " "
+                } else {
+                    ""
+                };
+                write!(self.f, "{}{}{}", op.as_str(), space, operand)
+            }
+            ExprKind::Super(_, method) => write!(self.f, "super.{}", method.symbol),
+            ExprKind::This(_) => self.str("this"),
+            ExprKind::Variable(id) => write!(self.f, "{}", id.symbol),
+        }
+    }
+}
+
+/// Whether an `if` expression's branches are all blocks without values, which would parse as
+/// the branches of an `if` statement.
+fn reads_as_if_statement(ex: &Expr) -> bool {
+    let is_statement = |branch: &Expr| match &branch.kind {
+        ExprKind::Block(_, None) => true,
+        ExprKind::If(..) => reads_as_if_statement(branch),
+        _ => false,
+    };
+    match &ex.kind {
+        ExprKind::If(_, ex_then, ex_else) => is_statement(ex_then) && is_statement(ex_else),
+        _ => false,
+    }
+}
+
+fn starts_like_statement(ex: &Expr) -> bool {
+    if let Some((first, _)) = comparison_chain(ex) {
+        return starts_like_statement(first);
+    }
+    if let Some((target, _, _)) = compound_assignment(ex) {
+        return starts_like_statement(&target);
+    }
+    match &ex.kind {
+        ExprKind::Block(..) | ExprKind::If(..) | ExprKind::Lambda(..) | ExprKind::Map(..) => true,
+        ExprKind::Binary(left, _, _)
+        | ExprKind::Logical(left, _, _)
+        | ExprKind::Is(left, _)
+        | ExprKind::Ternary(left, _, _) => {
+            precedence(left) >= precedence(ex) && starts_like_statement(left)
+        }
+        ExprKind::Call(inner, _, _)
+        | ExprKind::Get(inner, _)
+        | ExprKind::Index(inner, _)
+        | ExprKind::Set(inner, _, _)
+        | ExprKind::SetIndex(inner, _, _) => {
+            precedence(inner) >= CALL && starts_like_statement(inner)
+        }
+        _ => false,
+    }
+}
+
+fn precedence(ex: &Expr) -> u8 {
+    if comparison_chain(ex).is_some() {
+        return COMPARISON;
+    }
+    if compound_assignment(ex).is_some() {
+        return ASSIGNMENT;
+    }
+    match &ex.kind {
+        // An if expression's else branch would swallow any operator that follows it
+        ExprKind::Assign(..) | ExprKind::Set(..) | ExprKind::SetIndex(..) | ExprKind::If(..) => {
+            ASSIGNMENT
+        }
+        ExprKind::Binary(_, op, _) => binary_precedence(op),
+        ExprKind::Is(..) => COMPARISON,
+        ExprKind::Ternary(..) => TERNARY,
+        ExprKind::Logical(_, op, _) => logic_precedence(op),
+        ExprKind::Unary(..) => UNARY,
+        ExprKind::Call(..) | ExprKind::Get(..) | ExprKind::Index(..) => CALL,
+        _ => PRIMARY,
+    }
+}
+
+fn binary_precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Equal | BinaryOp::NotEqual => EQUALITY,
+        BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::Less | BinaryOp::LessEqual => {
+            COMPARISON
+        }
+        BinaryOp::BitOr => BIT_OR,
+        BinaryOp::BitXor => BIT_XOR,
+        BinaryOp::BitAnd => BIT_AND,
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight => SHIFT,
+        BinaryOp::Plus | BinaryOp::Minus => TERM,
+        BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::FloorDivide | BinaryOp::Modulo => FACTOR,
+        BinaryOp::Power => POWER,
+    }
+}
+
+fn logic_precedence(op: &LogicOp) -> u8 {
+    match op {
+        LogicOp::Or => OR,
+        LogicOp::And => AND,
+    }
+}
+
+/// Recovers `a < b <= c` from the block the parser desugars it into, returning the first
+/// operand and each following operator and operand.
+fn comparison_chain(ex: &Expr) -> Option<(&Expr, Vec<(BinaryOp, &Expr)>)> {
+    let ExprKind::Block(statements, Some(value)) = &ex.kind else {
+        return None;
+    };
+    let is_temporary = |id: &Ident| id.symbol.to_string().starts_with("<chain ");
+    if statements.is_empty()
+        || !statements
+            .iter()
+            .all(|st| matches!(st, Stmt::Let(id, _) if is_temporary(id)))
+    {
+        return None;
+    }
+    let mut comparisons = Vec::new();
+    let mut chain = value.as_ref();
+    while let ExprKind::Logical(left, LogicOp::And, right) = &chain.kind {
+        comparisons.push(right.as_ref());
+        chain = left;
+    }
+    comparisons.push(chain);
+    comparisons.reverse();
+
+    let mut first = None;
+    let mut rest = Vec::new();
+    for comparison in comparisons {
+        let ExprKind::Binary(left, op, right) = &comparison.kind else {
+            return None;
+        };
+        if first.is_none() {
+            first = Some(left.as_ref());
+        }
+        let operand = match &right.kind {
+            ExprKind::Assign(id, operand) if is_temporary(id) => operand.as_ref(),
+            _ => right.as_ref(),
+        };
+        rest.push((*op, operand));
+    }
+    Some((first?, rest))
+}
+
+/// Recovers `a[i()] += 1` from the block the parser desugars it into when the target's object
+/// or index is evaluated once, returning the target as written, the operator and the value.
+fn compound_assignment(ex: &Expr) -> Option<(Expr, BinaryOp, &Expr)> {
+    let ExprKind::Block(statements, Some(assignment)) = &ex.kind else {
+        return None;
+    };
+    let mut temporaries = Vec::new();
+    for statement in statements {
+        match statement {
+            Stmt::Let(id, initializer) if id.symbol.to_string().starts_with("<compound ") => {
+                temporaries.push((id.symbol, initializer))
+            }
+            _ => return None,
+        }
+    }
+    if temporaries.is_empty() {
+        return None;
+    }
+    let written = |ex: &Expr| match &ex.kind {
+        ExprKind::Variable(id) => temporaries
+            .iter()
+            .find(|(symbol, _)| *symbol == id.symbol)
+            .map_or_else(
+                || ex.to_owned(),
+                |(_, initializer)| (*initializer).to_owned(),
+            ),
+        _ => ex.to_owned(),
+    };
+    let (target, value) = match &assignment.kind {
+        ExprKind::Set(object, property, value) => (Expr::get(written(object), *property), value),
+        ExprKind::SetIndex(object, index, value) => (
+            Expr::index(written(object), written(index), assignment.span),
+            value,
+        ),
+        _ => return None,
+    };
+    let ExprKind::Binary(_, op, value) = &value.kind else {
+        return None;
+    };
+    Some((target, *op, value.as_ref()))
+}
+
+fn braced(ids: &[Ident]) -> String {
+    if ids.is_empty() {
+        String::new()
+    } else {
+        format!(" {} ", idents(ids))
+    }
+}
+
+/// Escapes text for a plain string literal, the reverse of what the scanner decodes.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\0' => escaped.push_str("\\0"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
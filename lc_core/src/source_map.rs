@@ -0,0 +1,57 @@
+use crate::Span;
+
+/// A source file with its line-start offsets precomputed, so diagnostics can find the text of
+/// any line in constant time instead of rescanning the source for every error.
+///
+/// Offsets are byte offsets into the source, lines are 1-based and columns are 0-based.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    source: String,
+    line_starts: Vec<usize>,
+}
+impl SourceMap {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source: source.to_string(),
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The text of a line, without its line terminator.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let text = &self.source[start..end];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// The offset of a column on a line, or `None` if it lies past the end of that line.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        (column <= self.line(line)?.len()).then_some(start + column)
+    }
+
+    /// The `(line, column)` an offset falls on.
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        (line, offset - self.line_starts[line - 1])
+    }
+
+    /// The source text covered by a span, cut off at the end of the span's first line.
+    pub fn snippet(&self, span: Span) -> Option<&str> {
+        let (line, column) = self.location(span.start);
+        let text = self.line(line)?;
+        let end = (column + span.len()).min(text.len());
+        text.get(column.min(end)..end)
+    }
+}
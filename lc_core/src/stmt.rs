@@ -1,32 +1,103 @@
-use crate::{Expr, Ident};
+use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+use serde::Serialize;
+
+use crate::{Expr, Ident, Span};
+
+#[derive(Clone, Debug, PartialEq, Hash, Serialize)]
 pub enum Stmt {
     /// (`statements`)
     Block(Vec<Stmt>),
-    /// (`identifer`, `methods`)
-    Class(Ident, Vec<Stmt>),
+    /// (`keyword`)
+    Break(Span),
+    /// (`identifer`, `superclass`, `methods`)
+    Class(Ident, Option<Expr>, Vec<Stmt>),
+    /// (`identifier`, `initializer`), a binding that can't be assigned to again
+    Const(Ident, Expr),
+    /// (`keyword`)
+    Continue(Span),
+    /// (`body`, `condition`), a loop that runs its body before each check of the condition
+    DoWhile(Box<Stmt>, Expr),
+    /// (`identifier`, `variants`)
+    Enum(Ident, Vec<Ident>),
     /// (`expression`)
     Expression(Expr),
+    /// (`identifier`, `iterable`, `body`), binding each element in turn
+    ForEach(Ident, Expr, Box<Stmt>),
     /// (`identifier`, `params`, `body`)
-    Function(Ident, Vec<Ident>, Vec<Stmt>),
+    Function(Ident, Rc<[Ident]>, Rc<[Stmt]>),
     /// (`condition`, `then`, `else`)
     If(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    /// (`expression`)
-    Print(Expr),
+    /// (`expressions`)
+    Print(Vec<Expr>),
     /// (`expression`)
     Return(Expr),
     /// (`identifier`, `initializer`)
     Let(Ident, Expr),
-    /// (`condition`, `body`)
-    While(Expr, Box<Stmt>),
+    /// (`identifiers`, `initializer`)
+    LetTuple(Vec<Ident>, Expr),
+    /// (`identifier`, `fields`)
+    Struct(Ident, Vec<Ident>),
+    /// (`condition`, `body`, `increment`), the increment of a desugared `for` running after
+    /// every iteration, including those cut short by `continue`
+    While(Expr, Box<Stmt>, Option<Expr>),
 }
 impl Stmt {
+    /// Whether any of the statements introduces a binding into the enclosing scope. Blocks
+    /// that don't can share their parent's scope; the resolver and interpreter both consult
+    /// this so that their scope depths agree.
+    pub fn declares_bindings(statements: &[Stmt]) -> bool {
+        statements.iter().any(|statement| {
+            matches!(
+                statement,
+                Stmt::Class(..)
+                    | Stmt::Const(..)
+                    | Stmt::Enum(..)
+                    | Stmt::Function(..)
+                    | Stmt::Let(..)
+                    | Stmt::LetTuple(..)
+                    | Stmt::Struct(..)
+            )
+        })
+    }
+
+    /// Where the statement is in the source: the declared name for declarations, the condition
+    /// for `if` and the loops, the iterable for `for`-`in`, and otherwise the expressions it
+    /// evaluates. An empty block has no source of its own and gets the default span.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block(statements) => match (statements.first(), statements.last()) {
+                (Some(first), Some(last)) => first.span().to(last.span()),
+                _ => Span::default(),
+            },
+            Stmt::Break(span) | Stmt::Continue(span) => *span,
+            Stmt::Class(id, _, _) | Stmt::Function(id, _, _) => id.span,
+            Stmt::Enum(id, _) => id.span,
+            Stmt::Struct(id, _) => id.span,
+            Stmt::Expression(ex) | Stmt::Return(ex) => ex.span,
+            Stmt::If(condition, _, _) | Stmt::While(condition, _, _) => condition.span,
+            Stmt::DoWhile(_, condition) => condition.span,
+            Stmt::ForEach(_, iterable, _) => iterable.span,
+            Stmt::Print(expressions) => match (expressions.first(), expressions.last()) {
+                (Some(first), Some(last)) => first.span.to(last.span),
+                _ => Span::default(),
+            },
+            Stmt::Const(id, initializer) | Stmt::Let(id, initializer) => {
+                id.span.to(initializer.span)
+            }
+            Stmt::LetTuple(ids, initializer) => ids[0].span.to(initializer.span),
+        }
+    }
+
     pub fn new_if(ex: Expr, st_then: Stmt, st_else: Option<Stmt>) -> Self {
         Self::If(ex, Box::new(st_then), st_else.map(Box::new))
     }
 
-    pub fn new_while(ex: Expr, stmt: Stmt) -> Self {
-        Self::While(ex, Box::new(stmt))
+    pub fn new_do_while(stmt: Stmt, ex: Expr) -> Self {
+        Self::DoWhile(Box::new(stmt), ex)
+    }
+
+    pub fn new_while(ex: Expr, stmt: Stmt, increment: Option<Expr>) -> Self {
+        Self::While(ex, Box::new(stmt), increment)
     }
 }
@@ -2,6 +2,7 @@ use core::fmt;
 use std::{hash, ops, sync::Mutex};
 
 use once_cell::sync::Lazy;
+use serde::{Serialize, Serializer};
 use stringtern::{InternedKey, StringInterner};
 
 use crate::Literal;
@@ -11,6 +12,13 @@ type InternTable = Lazy<Mutex<StringInterner>>;
 static STRING_TABLE: InternTable = Lazy::new(|| Mutex::new(StringInterner::default()));
 static IDENT_TABLE: InternTable = Lazy::new(|| Mutex::new(StringInterner::default()));
 
+/// A string interned into one of two process-wide tables, one for string literals and one for
+/// identifiers.
+///
+/// Within a process, interning the same text into the same table always yields the same key,
+/// so equal strings produce symbols that compare and hash equal, and likewise for any
+/// [`Literal::String`] wrapping them. Symbols from different tables are never equal, even for
+/// the same text. Keys are not stable across processes and must not be persisted.
 #[derive(Clone, Copy)]
 pub struct Symbol {
     symbol: InternedKey,
@@ -36,16 +44,21 @@ impl fmt::Debug for Symbol {
             .finish()
     }
 }
+/// Serializes as the resolved text, since the key itself means nothing outside this process.
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.resolve())
+    }
+}
 impl PartialEq for Symbol {
     fn eq(&self, other: &Self) -> bool {
-        self.symbol == other.symbol
-            && self.table as *const InternTable == other.table as *const InternTable
+        self.symbol == other.symbol && std::ptr::eq(self.table, other.table)
     }
 }
 impl Eq for Symbol {}
 impl PartialOrd for Symbol {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.symbol.partial_cmp(&other.symbol)
+        Some(self.cmp(other))
     }
 }
 impl Ord for Symbol {
@@ -63,6 +76,8 @@ impl ops::Add for Symbol {
     }
 }
 impl Symbol {
+    /// Interns `string` into the string-literal table, reusing the existing key if the same
+    /// text has been interned before.
     pub fn string(string: String) -> Self {
         Self {
             symbol: STRING_TABLE.lock().unwrap().get_or_insert(string),
@@ -70,6 +85,8 @@ impl Symbol {
         }
     }
 
+    /// Interns `string` into the identifier table, reusing the existing key if the same text
+    /// has been interned before.
     pub fn ident(string: String) -> Self {
         Self {
             symbol: IDENT_TABLE.lock().unwrap().get_or_insert(string),
@@ -1,20 +1,33 @@
 use std::{cmp, hash::Hash};
 
+use serde::Serialize;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     // Literals
     Identifier,
     String(String),
+    /// The text of a string up to a `${`. The embedded expression's tokens follow, then
+    /// either another `Interpolation` or the `String` that finishes the literal.
+    Interpolation(String),
+    Int(i64),
     Number(f64),
     // Single character
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Semicolon,
+    Colon,
+    Question,
+    Caret,
     // One or two characters
+    Tilde,
+    TildeSlash,
     Minus,
     MinusEqual,
     MinusMinus,
@@ -23,8 +36,11 @@ pub enum TokenKind {
     PlusPlus,
     Slash,
     SlashEqual,
+    Percent,
+    PercentEqual,
     Star,
     StarEqual,
+    StarStar,
     Bang,
     BangEqual,
     Equal,
@@ -33,27 +49,118 @@ pub enum TokenKind {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    Ampersand,
+    AmpAmp,
+    Pipe,
+    PipePipe,
     // Keywords
     And,
+    Break,
     Class,
+    Const,
+    Continue,
+    Do,
     Else,
+    Enum,
     False,
     Fn,
     For,
     If,
+    In,
+    Is,
     Let,
     Null,
     Or,
     Print,
     Return,
+    Struct,
     Super,
     This,
     True,
     While,
     EOF,
 }
+impl TokenKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenKind::Identifier => "Identifier",
+            TokenKind::String(_) => "String",
+            TokenKind::Interpolation(_) => "Interpolation",
+            TokenKind::Int(_) => "Int",
+            TokenKind::Number(_) => "Number",
+            TokenKind::LeftParen => "LeftParen",
+            TokenKind::RightParen => "RightParen",
+            TokenKind::LeftBrace => "LeftBrace",
+            TokenKind::RightBrace => "RightBrace",
+            TokenKind::LeftBracket => "LeftBracket",
+            TokenKind::RightBracket => "RightBracket",
+            TokenKind::Comma => "Comma",
+            TokenKind::Dot => "Dot",
+            TokenKind::Semicolon => "Semicolon",
+            TokenKind::Colon => "Colon",
+            TokenKind::Question => "Question",
+            TokenKind::Caret => "Caret",
+            TokenKind::Tilde => "Tilde",
+            TokenKind::TildeSlash => "TildeSlash",
+            TokenKind::Minus => "Minus",
+            TokenKind::MinusEqual => "MinusEqual",
+            TokenKind::MinusMinus => "MinusMinus",
+            TokenKind::Plus => "Plus",
+            TokenKind::PlusEqual => "PlusEqual",
+            TokenKind::PlusPlus => "PlusPlus",
+            TokenKind::Slash => "Slash",
+            TokenKind::SlashEqual => "SlashEqual",
+            TokenKind::Percent => "Percent",
+            TokenKind::PercentEqual => "PercentEqual",
+            TokenKind::Star => "Star",
+            TokenKind::StarEqual => "StarEqual",
+            TokenKind::StarStar => "StarStar",
+            TokenKind::Bang => "Bang",
+            TokenKind::BangEqual => "BangEqual",
+            TokenKind::Equal => "Equal",
+            TokenKind::EqualEqual => "EqualEqual",
+            TokenKind::Greater => "Greater",
+            TokenKind::GreaterEqual => "GreaterEqual",
+            TokenKind::Less => "Less",
+            TokenKind::LessEqual => "LessEqual",
+            TokenKind::LessLess => "LessLess",
+            TokenKind::GreaterGreater => "GreaterGreater",
+            TokenKind::Ampersand => "Ampersand",
+            TokenKind::AmpAmp => "AmpAmp",
+            TokenKind::Pipe => "Pipe",
+            TokenKind::PipePipe => "PipePipe",
+            TokenKind::And => "And",
+            TokenKind::Break => "Break",
+            TokenKind::Class => "Class",
+            TokenKind::Continue => "Continue",
+            TokenKind::Const => "Const",
+            TokenKind::Do => "Do",
+            TokenKind::Else => "Else",
+            TokenKind::Enum => "Enum",
+            TokenKind::False => "False",
+            TokenKind::Fn => "Fn",
+            TokenKind::For => "For",
+            TokenKind::If => "If",
+            TokenKind::In => "In",
+            TokenKind::Is => "Is",
+            TokenKind::Let => "Let",
+            TokenKind::Null => "Null",
+            TokenKind::Or => "Or",
+            TokenKind::Print => "Print",
+            TokenKind::Return => "Return",
+            TokenKind::Struct => "Struct",
+            TokenKind::Super => "Super",
+            TokenKind::This => "This",
+            TokenKind::True => "True",
+            TokenKind::While => "While",
+            TokenKind::EOF => "EOF",
+        }
+    }
+}
 
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct Span {
     pub line: usize,
     pub start: usize,
@@ -80,11 +187,14 @@ impl Span {
         )
     }
 
+    /// The gap separating two spans, in either order. Overlapping spans have an empty gap
+    /// where the first of them ends.
     pub fn between(&self, end: Span) -> Span {
+        let start = cmp::min(self.end, end.end);
         Span::new(
             cmp::min(self.line, end.line),
-            cmp::max(self.end, end.end),
-            cmp::min(self.start, end.start),
+            start,
+            cmp::max(start, cmp::max(self.start, end.start)),
         )
     }
 
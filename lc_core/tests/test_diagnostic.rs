@@ -0,0 +1,134 @@
+use lc_core::*;
+
+fn sample_errors() -> TranslationErrors {
+    vec![
+        SpannedError::from((Span::new(3, 10, 11), "Expected expression.")).with_phase(Phase::Parse),
+        SpannedError::from((Span::new(5, 20, 21), "Unexpected character @")).with_phase(Phase::Lex),
+    ]
+    .into()
+}
+
+#[test]
+fn renderer_plain() {
+    let errors = sample_errors();
+    let rendered = Renderer::new(false).render_translation(&errors);
+    assert_eq!(rendered, errors.to_string());
+    assert!(!rendered.contains('\x1b'));
+
+    let runtime = RuntimeError::with_span("Operand must be a number.".into(), Span::new(2, 0, 1));
+    assert_eq!(
+        Renderer::new(false).render_runtime(&runtime),
+        runtime.to_string()
+    );
+}
+
+#[test]
+fn renderer_runtime_backtrace() {
+    let source = SourceMap::new("fn f() {\n  return -\"a\";\n}\nf();\n");
+    let runtime = RuntimeError::with_span("Operand must be a number.".into(), Span::new(2, 18, 22))
+        .with_backtrace(Box::new([Frame::new(
+            "<fn f/0>".into(),
+            Span::new(4, 24, 27),
+        )]));
+    let plain = Renderer::new(false).render_runtime(&runtime);
+    assert_eq!(plain, runtime.to_string());
+    assert!(
+        plain.ends_with("\n  in <fn f/0>, called from line 4\n"),
+        "{}",
+        plain
+    );
+
+    // The trace follows the source snippet
+    let rendered = Renderer::new(false)
+        .with_source(&source)
+        .render_runtime(&runtime);
+    assert_eq!(
+        rendered,
+        "[line 2] RuntimeError: Operand must be a number.
+   2 |   return -\"a\";
+     |          ^^^^
+  in <fn f/0>, called from line 4
+"
+    );
+
+    // Calls made by the host have no call site
+    let runtime = RuntimeError::new("boom".into())
+        .with_backtrace(Box::new([Frame::new("<fn f/0>".into(), Span::default())]));
+    assert_eq!(runtime.to_string(), "RuntimeError: boom\n  in <fn f/0>\n");
+}
+
+#[test]
+fn renderer_colored() {
+    let errors = sample_errors();
+    let rendered = Renderer::new(true).render_translation(&errors);
+    assert!(rendered.contains("\x1b[36mline 3\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31mParseError\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31mLexError\x1b[0m"));
+}
+
+#[test]
+fn renderer_source_line() {
+    let source = SourceMap::new("let x = 1;\nprint x +;\n");
+    let error =
+        SpannedError::from((Span::new(2, 20, 21), "Expected expression.")).with_phase(Phase::Parse);
+    let rendered = Renderer::new(false)
+        .with_source(&source)
+        .render_translation(&vec![error].into());
+    assert_eq!(
+        rendered,
+        "[line 2] ParseError: Expected expression.\n   2 | print x +;\n     |          ^\n"
+    );
+}
+
+#[test]
+fn renderer_warnings() {
+    let source = SourceMap::new("if (x = 5) print x;\n");
+    let warning =
+        SpannedError::from((Span::new(1, 4, 9), "Did you mean '=='?")).with_phase(Phase::Resolve);
+    let errors = TranslationErrors::new().with_warnings(vec![warning]);
+    assert!(errors.check().is_ok());
+    assert_eq!(Renderer::new(false).render_translation(&errors), "");
+    assert_eq!(
+        Renderer::new(false)
+            .with_source(&source)
+            .render_warnings(&errors),
+        "[line 1] ResolveWarning: Did you mean '=='?\n   1 | if (x = 5) print x;\n     |     ^^^^^\n"
+    );
+    let colored = Renderer::new(true).render_warnings(&errors);
+    assert!(colored.contains("\x1b[1;33mResolveWarning\x1b[0m"));
+}
+
+#[test]
+fn renderer_underline() {
+    let source = SourceMap::new("let s = \"one\ntwo\";\n\tprint;\nx");
+    let render = |span| {
+        let error = SpannedError::from((span, "message")).with_phase(Phase::Parse);
+        Renderer::new(false)
+            .with_source(&source)
+            .render_translation(&vec![error].into())
+    };
+    // A span running onto the next line is underlined to the end of its first line
+    assert_eq!(
+        render(Span::new(1, 8, 18)),
+        "[line 1] ParseError: message\n   1 | let s = \"one\n     |         ^^^^\n"
+    );
+    // Tabs before the span are kept, and an empty span still gets a caret
+    assert_eq!(
+        render(Span::new(3, 25, 25)),
+        "[line 3] ParseError: message\n   3 | \tprint;\n     | \t     ^\n"
+    );
+    // Offsets that don't fall on the span's line get no underline
+    assert_eq!(
+        render(Span::new(4, 0, 1)),
+        "[line 4] ParseError: message\n   4 | x\n"
+    );
+
+    let colored = Renderer::new(true)
+        .with_source(&source)
+        .render_translation(&vec![SpannedError::from((Span::new(4, 27, 28), "m"))].into());
+    assert!(
+        colored.ends_with("     | \x1b[1;31m^\x1b[0m\n"),
+        "{:?}",
+        colored
+    );
+}
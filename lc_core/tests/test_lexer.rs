@@ -68,7 +68,7 @@ fn scanner_tokens() {
 fn scanner_comments() {
     assert_lexer_tokens(
         "let x = 0; // comment begins; x++;",
-        vec![Let, Identifier, Equal, Number(0.0), Semicolon, EOF],
+        vec![Let, Identifier, Equal, Int(0), Semicolon, EOF],
         6,
     );
     assert_lexer_tokens(
@@ -107,7 +107,7 @@ fn scanner_literals() {
         vec![
             Identifier,
             Equal,
-            Number(13.0),
+            Int(13),
             Equal,
             String("string".into()),
             String("another string".into()),
@@ -131,9 +131,9 @@ fn scanner_line_numbers() {
     */
     return";
     let output = vec![
-        Number(6.0),
+        Int(6),
         Comma,
-        Number(7.0),
+        Int(7),
         Semicolon,
         Identifier,
         Comma,
@@ -173,7 +173,7 @@ fn scanner_identifiers() {
             Semicolon,
             Identifier,
             Semicolon,
-            Number(12.0),
+            Int(12),
             Identifier,
             Semicolon,
             Identifier,
@@ -186,18 +186,441 @@ fn scanner_identifiers() {
 #[test]
 fn scanner_invalid() {
     assert_lexer_tokens(
-        "@test;$let?;256%8'ident'~\"#lc@email.au\"",
+        "@test;$let?;256%8'ident'`\"#lc@email.au\"",
         vec![
             Identifier,
             Semicolon,
             Let,
+            Question,
             Semicolon,
-            Number(256.0),
-            Number(8.0),
+            Int(256),
+            Percent,
+            Int(8),
             Identifier,
             String("#lc@email.au".into()),
             EOF,
         ],
+        11,
+    );
+}
+
+#[test]
+fn scanner_logical_symbols() {
+    assert_lexer_tokens(
+        "a && b || c & d | e",
+        vec![
+            Identifier, AmpAmp, Identifier, PipePipe, Identifier, Ampersand, Identifier, Pipe,
+            Identifier, EOF,
+        ],
+        10,
+    );
+}
+
+#[test]
+fn scanner_bitwise_symbols() {
+    assert_lexer_tokens(
+        "a&b|c^d<<e>>f<<=g>>=~h",
+        vec![
+            Identifier,
+            Ampersand,
+            Identifier,
+            Pipe,
+            Identifier,
+            Caret,
+            Identifier,
+            LessLess,
+            Identifier,
+            GreaterGreater,
+            Identifier,
+            Less,
+            LessEqual,
+            Identifier,
+            Greater,
+            GreaterEqual,
+            Tilde,
+            Identifier,
+            EOF,
+        ],
+        19,
+    );
+    assert_lexer_tokens(
+        "a~/b~c ~ /d // e ~/ f",
+        vec![
+            Identifier, TildeSlash, Identifier, Tilde, Identifier, Tilde, Slash, Identifier, EOF,
+        ],
         9,
     );
 }
+
+#[test]
+fn scanner_power() {
+    assert_lexer_tokens(
+        "2**3 ** -1 **= *",
+        vec![
+            Int(2),
+            StarStar,
+            Int(3),
+            StarStar,
+            Minus,
+            Int(1),
+            Star,
+            StarEqual,
+            Star,
+            EOF,
+        ],
+        10,
+    );
+}
+
+#[test]
+fn scanner_trivia_only() {
+    for (source, line) in [
+        ("", 1),
+        ("   \t\r\n\n  ", 3),
+        ("// just a comment", 1),
+        ("// one\n/* two\n   /* nested */ */\n", 4),
+    ] {
+        let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+        assert!(errors.check().is_ok(), "{:?}", source);
+        assert_eq!(tokens.len(), 1, "{:?}", source);
+        assert_eq!(tokens[0].kind, EOF);
+        assert_eq!(tokens[0].span, Span::new(line, source.len(), source.len()));
+    }
+}
+
+#[test]
+fn scanner_eof_span() {
+    let (tokens, _) = Scanner::new("let x;\n// trailing\n".to_string()).scan_tokens();
+    let eof = tokens.last().unwrap();
+    assert_eq!(eof.kind, EOF);
+    assert_eq!(eof.span, Span::new(3, 19, 19));
+    assert!(eof.span.is_empty());
+}
+
+#[test]
+fn scanner_triple_quoted_string() {
+    let source = "let s = \"\"\"first \"line\"\n  second \"\" line\n\"\"\";\nx";
+    let tokens = assert_lexer_tokens(
+        source,
+        vec![
+            Let,
+            Identifier,
+            Equal,
+            String("first \"line\"\n  second \"\" line\n".into()),
+            Semicolon,
+            Identifier,
+            EOF,
+        ],
+        7,
+    );
+    assert_eq!(tokens[4].span.line, 3);
+    assert_eq!(tokens[5].span.line, 4);
+    assert_lexer_tokens("\"\"\"\"\"\"", vec![String("".into()), EOF], 2);
+    assert_lexer_tokens(
+        "\"\" \"x\"",
+        vec![String("".into()), String("x".into()), EOF],
+        3,
+    );
+}
+
+#[test]
+fn scanner_triple_quoted_string_leading_newline() {
+    let source = "\"\"\"\n  \"quoted\" \\n\n\"\"\" \"\"\"\n\nkept\"\"\" \"\"\"\r\ncrlf\"\"\" x";
+    let tokens = assert_lexer_tokens(
+        source,
+        vec![
+            String("  \"quoted\" \\n\n".into()),
+            String("\nkept".into()),
+            String("crlf".into()),
+            Identifier,
+            EOF,
+        ],
+        5,
+    );
+    assert_eq!(tokens[0].span.line, 3);
+    assert_eq!(tokens[1].span.line, 5);
+    assert_eq!(tokens[3].span.line, 6);
+}
+
+#[test]
+fn scanner_unterminated_triple_quoted_string() {
+    for source in ["\"\"\"never\nclosed", "\"\"\"almost\"\"", "\"\"\""] {
+        let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+        assert_eq!(tokens.len(), 1, "{:?}", source);
+        assert_eq!(errors.issues().len(), 1, "{:?}", source);
+        assert_eq!(
+            errors.issues()[0].message,
+            "Unterminated triple-quoted string"
+        );
+    }
+}
+
+#[test]
+fn scanner_unterminated_block_comment() {
+    // The error is on the line the comment started
+    for (source, len, line) in [
+        ("/* oops", 1, 1),
+        ("let x = 1;\n/* outer /* inner */\nprint x;", 6, 2),
+    ] {
+        let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+        assert_eq!(tokens.len(), len, "{:?}", source);
+        assert_eq!(errors.issues().len(), 1, "{:?}", source);
+        let error = &errors.issues()[0];
+        assert_eq!(error.message, "Unterminated block comment");
+        assert_eq!(error.span.line, line, "{:?}", source);
+    }
+    let (_, errors) = Scanner::new("/* a /* b */ c */ 1".to_string()).scan_tokens();
+    assert!(errors.issues().is_empty());
+}
+
+#[test]
+fn scanner_non_ascii_source() {
+    let source = "// ünïcödé comment\nlet s = \"héllo ${world}\" + \"\"\"日本\"\"\"; /* ✓ */ s;";
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.issues().is_empty(), "{}", errors);
+    let lexemes: Vec<&str> = tokens
+        .iter()
+        .map(|token| &source[token.span.start..token.span.end])
+        .collect();
+    assert_eq!(
+        lexemes,
+        [
+            "let",
+            "s",
+            "=",
+            "\"héllo ${",
+            "world",
+            "}\"",
+            "+",
+            "\"\"\"日本\"\"\"",
+            ";",
+            "s",
+            ";",
+            ""
+        ]
+    );
+    assert_eq!(tokens[7].kind, String("日本".into()));
+    assert_eq!(tokens[3].kind, Interpolation("héllo ".into()));
+    assert_eq!(tokens[11].span.start, source.len());
+
+    for (source, message) in [
+        ("let é = 1;", "Unexpected character é"),
+        ("\"ends in é", "Unterminated string"),
+        ("\"ends in \\", "Unterminated string"),
+    ] {
+        let (_, errors) = Scanner::new(source.to_string()).scan_tokens();
+        let messages: Vec<&str> = errors.issues().iter().map(|e| e.message.as_str()).collect();
+        assert!(messages.contains(&message), "{:?}: {:?}", source, messages);
+    }
+}
+
+#[test]
+fn scanner_interpolation() {
+    assert_lexer_tokens(
+        "\"Hi ${name}, ${n + 1}!\"",
+        vec![
+            Interpolation("Hi ".into()),
+            Identifier,
+            Interpolation(", ".into()),
+            Identifier,
+            Plus,
+            Int(1),
+            String("!".into()),
+            EOF,
+        ],
+        8,
+    );
+    // Braces and strings inside the expression, and a `$` that doesn't start one
+    assert_lexer_tokens(
+        "\"${ { \"in ${x}\" } } $5\"",
+        vec![
+            Interpolation("".into()),
+            LeftBrace,
+            Interpolation("in ".into()),
+            Identifier,
+            String("".into()),
+            RightBrace,
+            String(" $5".into()),
+            EOF,
+        ],
+        8,
+    );
+    assert_lexer_tokens("\"\"\"${x}\"\"\"", vec![String("${x}".into()), EOF], 2);
+}
+
+#[test]
+fn scanner_malformed_interpolation() {
+    for (source, message) in [
+        ("\"a ${b", "Unterminated '${' in string"),
+        ("\"a ${ {b} \"", "Unterminated '${' in string"),
+        ("\"a ${} b\"", "Empty '${}' in string"),
+    ] {
+        let (_, errors) = Scanner::new(source.to_string()).scan_tokens();
+        let messages: Vec<&str> = errors.issues().iter().map(|e| e.message.as_str()).collect();
+        assert!(messages.contains(&message), "{:?}: {:?}", source, messages);
+    }
+}
+
+#[test]
+fn scanner_number_out_of_range() {
+    let huge = format!("1{}", "0".repeat(400));
+    for source in [huge.clone(), format!("{}.5", huge)] {
+        let (tokens, errors) = Scanner::new(source.clone()).scan_tokens();
+        assert_eq!(tokens.len(), 1, "{:?}", source);
+        assert_eq!(errors.issues().len(), 1, "{:?}", source);
+        assert_eq!(errors.issues()[0].message, "Numeric literal out of range");
+        assert_eq!(errors.issues()[0].span, Span::new(1, 0, source.len()));
+    }
+    // The largest finite value is still fine, as are values that round to zero
+    let source = format!("{} 0.{}1", f64::MAX, "0".repeat(400));
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.issues().is_empty());
+    let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![Number(f64::MAX), Number(0.0), EOF]);
+}
+
+#[test]
+fn scanner_ints_and_floats() {
+    let source = "0 42 4.0 0.5 9223372036854775807 9223372036854775808 7.";
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.issues().is_empty());
+    let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            Int(0),
+            Int(42),
+            Number(4.0),
+            Number(0.5),
+            Int(i64::MAX),
+            // Too large for an int, so it reads as a float
+            Number(9223372036854775808.0),
+            Int(7),
+            Dot,
+            EOF
+        ]
+    );
+}
+
+#[test]
+fn scanner_radix_literals() {
+    assert_lexer_tokens(
+        "0xFF 0xff 0X1a 0o17 0O777 0b1010 0B0 0x0;0 0o0",
+        vec![
+            Int(255),
+            Int(255),
+            Int(26),
+            Int(15),
+            Int(511),
+            Int(10),
+            Int(0),
+            Int(0),
+            Semicolon,
+            Int(0),
+            Int(0),
+            EOF,
+        ],
+        12,
+    );
+    assert_lexer_tokens(
+        "0x7fffffffffffffff -0b1",
+        vec![Int(i64::MAX), Minus, Int(1), EOF],
+        4,
+    );
+}
+
+#[test]
+fn scanner_radix_errors() {
+    for (source, message) in [
+        ("0xG", "Invalid digit 'G' in hex literal"),
+        ("0x1g2", "Invalid digit 'g' in hex literal"),
+        ("0o18", "Invalid digit '8' in octal literal"),
+        ("0b102", "Invalid digit '2' in binary literal"),
+        ("0x", "Expected digits after '0x'"),
+        ("0b_1", "Invalid digit '_' in binary literal"),
+        ("0x8000000000000000", "Numeric literal out of range"),
+        ("0b1{}", "Numeric literal out of range"),
+    ] {
+        let source = source.replace("{}", &"0".repeat(64));
+        let (tokens, errors) = Scanner::new(source.clone()).scan_tokens();
+        // The whole literal is one error, not an error and more tokens
+        assert_eq!(tokens.len(), 1, "{:?}", source);
+        assert_eq!(errors.issues().len(), 1, "{:?}", source);
+        assert_eq!(errors.issues()[0].message, message, "{:?}", source);
+        assert_eq!(errors.issues()[0].span, Span::new(1, 0, source.len()));
+    }
+}
+
+#[test]
+fn scanner_string_escapes() {
+    assert_lexer_tokens(
+        r#""a\nb\tc\rd\"e\\f\0g" "\\n""#,
+        vec![
+            String("a\nb\tc\rd\"e\\f\0g".into()),
+            String("\\n".into()),
+            EOF,
+        ],
+        3,
+    );
+    assert_lexer_tokens(
+        r#""\"${x}\"""#,
+        vec![
+            Interpolation("\"".into()),
+            Identifier,
+            String("\"".into()),
+            EOF,
+        ],
+        4,
+    );
+    // Only a real newline moves to the next line
+    let (tokens, _) = Scanner::new("\"a\\nb\" x \"c\nd\" y".to_string()).scan_tokens();
+    let lines: Vec<usize> = tokens.iter().map(|token| token.span.line).collect();
+    assert_eq!(lines, vec![1, 1, 2, 2, 2]);
+}
+
+#[test]
+fn scanner_unknown_escape() {
+    let (tokens, errors) = Scanner::new(r#"print "a\qb"; x"#.to_string()).scan_tokens();
+    assert_eq!(errors.issues().len(), 1);
+    assert_eq!(errors.issues()[0].message, r"Unknown escape sequence '\q'");
+    assert_eq!(errors.issues()[0].span, Span::new(1, 8, 10));
+    // The rest of the string and source still scan
+    let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![Print, String("ab".into()), Semicolon, Identifier, EOF]
+    );
+}
+
+#[test]
+fn scanner_token_offsets() {
+    let source = "let name = \"ab\";\n  print name;";
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let map = SourceMap::new(source);
+    let located: Vec<(&str, (usize, usize))> = tokens
+        .iter()
+        .map(|token| {
+            (
+                &source[token.span.start..token.span.end],
+                map.location(token.span.start),
+            )
+        })
+        .collect();
+    assert_eq!(
+        located,
+        [
+            ("let", (1, 0)),
+            ("name", (1, 4)),
+            ("=", (1, 9)),
+            ("\"ab\"", (1, 11)),
+            (";", (1, 15)),
+            ("print", (2, 2)),
+            ("name", (2, 8)),
+            (";", (2, 12)),
+            ("", (2, 13)),
+        ]
+    );
+    for token in &tokens {
+        assert_eq!(map.location(token.span.start).0, token.span.line);
+    }
+}
@@ -0,0 +1,219 @@
+use lc_core::*;
+
+fn parse_errors(source: &str) -> Vec<SpannedError> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let (_, errors) = Parser::new(tokens).parse();
+    errors.issues().to_vec()
+}
+
+#[test]
+fn parser_missing_semicolon_span() {
+    let errors = parse_errors("let x = 10\nprint x;");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expect ';' after variable declaration");
+    // Points just after `10` on the first line rather than at `print` on the next
+    assert_eq!(errors[0].span, Span::new(1, 10, 11));
+
+    let errors = parse_errors("print 1 print 2;");
+    assert_eq!(errors[0].span, Span::new(1, 7, 8));
+
+    let errors = parse_errors("x = 1");
+    assert_eq!(errors[0].message, "Expected ';' after expression.");
+    assert_eq!(errors[0].span, Span::new(1, 5, 5));
+}
+
+#[test]
+fn span_between() {
+    let a = Span::new(1, 0, 3);
+    let b = Span::new(2, 7, 9);
+    assert_eq!(a.between(b), Span::new(1, 3, 7));
+    assert_eq!(b.between(a), Span::new(1, 3, 7));
+    assert_eq!(a.between(a), Span::new(1, 3, 3));
+}
+
+#[test]
+fn parser_trivia_only() {
+    for source in ["", " \n\t\n", "// comment\n/* block */"] {
+        let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+        let (statements, errors) = Parser::new(tokens).parse();
+        assert!(statements.is_empty(), "{:?}", source);
+        assert!(errors.issues().is_empty(), "{:?}", source);
+    }
+}
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(!errors.has_errors(), "{}", errors);
+    statements
+}
+
+#[test]
+fn parser_desugared_spans() {
+    let source = "x++;\np.y -= 2;";
+    let statements = parse(source);
+    let text = |span: Span| &source[span.start..span.end];
+
+    // x++ is x = x + 1, with the 1 standing in for the ++
+    let Stmt::Expression(ex) = &statements[0] else {
+        panic!("{:?}", statements[0]);
+    };
+    let ExprKind::Assign(_, value) = &ex.kind else {
+        panic!("{:?}", ex);
+    };
+    let ExprKind::Binary(left, op, right) = &value.kind else {
+        panic!("{:?}", value);
+    };
+    assert_eq!(*op, BinaryOp::Plus);
+    assert_eq!(text(ex.span), "x++");
+    assert_eq!(text(left.span), "x");
+    assert_eq!(text(right.span), "++");
+
+    // p.y -= 2 is p.y = p.y - 2
+    let Stmt::Expression(ex) = &statements[1] else {
+        panic!("{:?}", statements[1]);
+    };
+    let ExprKind::Set(_, _, value) = &ex.kind else {
+        panic!("{:?}", ex);
+    };
+    assert_eq!(text(ex.span), "p.y -= 2");
+    assert_eq!(text(value.span), "p.y -= 2");
+    assert_eq!(value.span.line, 2);
+}
+
+#[test]
+fn parser_for_spans() {
+    let source = "for (let i = 0;\n  ;\n  i = i + 1)\n  print i;";
+    let statements = parse(source);
+    let Stmt::Block(outer) = &statements[0] else {
+        panic!("{:?}", statements[0]);
+    };
+    let Stmt::While(condition, _, Some(increment)) = &outer[1] else {
+        panic!("{:?}", outer[1]);
+    };
+    // The omitted condition sits at the `;` that closes it
+    assert_eq!(condition.span.line, 2);
+    assert_eq!(&source[condition.span.start..condition.span.end], ";");
+    assert_eq!(increment.span.line, 3);
+    assert_eq!(
+        &source[increment.span.start..increment.span.end],
+        "i = i + 1"
+    );
+}
+
+#[test]
+fn parser_ternary_right_associative() {
+    let expect = "\
+Expression
+  Ternary
+    Variable a
+    Then
+      Variable b
+    Else
+      Ternary
+        Variable c
+        Then
+          Variable d
+        Else
+          Variable e
+";
+    assert_eq!(ast_to_string(&parse("a ? b : c ? d : e;")), expect);
+
+    let errors = parse_errors("a ? b;");
+    assert_eq!(
+        errors[0].message,
+        "Expected ':' after then branch of conditional expression."
+    );
+}
+
+#[test]
+fn parser_class_declaration() {
+    let statements = parse("class Point { fn norm() {} fn scale(k) {} }");
+    let [Stmt::Class(name, None, methods)] = statements.as_slice() else {
+        panic!("{:?}", statements);
+    };
+    assert_eq!(name.symbol.to_string(), "Point");
+    let names: Vec<String> = methods
+        .iter()
+        .map(|method| match method {
+            Stmt::Function(name, _, _) => name.symbol.to_string(),
+            _ => panic!("{:?}", method),
+        })
+        .collect();
+    assert_eq!(names, ["norm", "scale"]);
+
+    let errors = parse_errors("class Point { norm() {} }");
+    assert_eq!(
+        errors[0].message,
+        "Expected 'fn' before method declaration."
+    );
+    assert_eq!(errors[0].span, Span::new(1, 14, 18));
+}
+
+#[test]
+fn parser_trailing_commas() {
+    let statements = parse("f(1, 2,);\nfn g(a, b,) {}\nlet h = fn (c,) {};\nf(\n  3,\n);");
+    let display: Vec<String> = statements.iter().map(|st| st.to_string()).collect();
+    assert_eq!(
+        display,
+        ["f(1, 2);", "fn g(a, b) {}", "let h = fn(c) {};", "f(3);"]
+    );
+
+    for (source, message) in [
+        ("f(,);", "Expected expression."),
+        ("f(1,,);", "Expected expression."),
+        ("fn g(,) {}", "Expected parameter name."),
+        ("fn g(a,,) {}", "Expected parameter name."),
+    ] {
+        let errors = parse_errors(source);
+        assert_eq!(errors[0].message, message, "{}", source);
+    }
+}
+
+#[test]
+fn parser_recovers_within_blocks() {
+    for source in [
+        // A missing `;` before `}` used to swallow the rest of the program
+        "fn f() {\n  let x = 1\n}\nprint 2;",
+        "{ let x = ; print x; }\nprint 3;",
+        "{ let m = {\"a\": ; }; print m; }\nprint 3;",
+        "let v = { let a = 1; a + ; };\nprint v;",
+        "let v = { a b; 2 };\nprint v;",
+        "fn f() {\n  print 1 +;\n  print 2;\n}\nfn g() { return 1; }",
+    ] {
+        let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+        let (statements, errors) = Parser::new(tokens).parse();
+        assert_eq!(errors.issues().len(), 1, "{}\n{}", source, errors);
+        assert_eq!(statements.len(), 2, "{}", source);
+    }
+
+    // The statements around the bad one are kept
+    let (tokens, _) =
+        Scanner::new("fn f() {\n  print 1;\n  let = 2;\n  print 3;\n}".to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert_eq!(errors.issues().len(), 1, "{}", errors);
+    assert_eq!(statements[0].to_string(), "fn f() { print 1; print 3; }");
+
+    // A stray `}` at the top level is skipped rather than looped on
+    assert_eq!(parse_errors("}\nprint 1;").len(), 1);
+}
+
+#[test]
+fn parser_missing_operand() {
+    for (source, operator) in [
+        ("print * 1;", "*"),
+        ("print & 1;", "&"),
+        ("print | 1;", "|"),
+        ("print ^ 1;", "^"),
+        ("print << 1;", "<<"),
+        ("print >> 1;", ">>"),
+    ] {
+        let errors = parse_errors(source);
+        assert_eq!(
+            errors[0].message,
+            format!("Binary operator '{}' missing operand(s)", operator),
+            "{}",
+            source
+        );
+    }
+}
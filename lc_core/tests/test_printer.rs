@@ -0,0 +1,282 @@
+use lc_core::*;
+
+const SOURCE: &str = "\
+let p = (1, \"a\");
+if (!p) print -2; else p = fn f(x) { return x.y; };";
+
+#[test]
+fn printer_tokens() {
+    let (tokens, _) = Scanner::new(SOURCE.to_string()).scan_tokens();
+    let expect = "\
+1:0..3       Let            let
+1:4..5       Identifier     p
+1:6..7       Equal          =
+1:8..9       LeftParen      (
+1:9..10      Int            1
+1:10..11     Comma          ,
+1:12..15     String         \"a\"
+1:15..16     RightParen     )
+1:16..17     Semicolon      ;
+2:18..20     If             if
+2:21..22     LeftParen      (
+2:22..23     Bang           !
+2:23..24     Identifier     p
+2:24..25     RightParen     )
+2:26..31     Print          print
+2:32..33     Minus          -
+2:33..34     Int            2
+2:34..35     Semicolon      ;
+2:36..40     Else           else
+2:41..42     Identifier     p
+2:43..44     Equal          =
+2:45..47     Fn             fn
+2:48..49     Identifier     f
+2:49..50     LeftParen      (
+2:50..51     Identifier     x
+2:51..52     RightParen     )
+2:53..54     LeftBrace      {
+2:55..61     Return         return
+2:62..63     Identifier     x
+2:63..64     Dot            .
+2:64..65     Identifier     y
+2:65..66     Semicolon      ;
+2:67..68     RightBrace     }
+2:68..69     Semicolon      ;
+2:69..69     EOF
+";
+    assert_eq!(tokens_to_string(&tokens), expect);
+}
+
+#[test]
+fn printer_ast() {
+    let (tokens, _) = Scanner::new(SOURCE.to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.issues().is_empty());
+    let expect = "\
+Let p
+  Tuple
+    Literal 1
+    Literal \"a\"
+If
+  Unary !
+    Variable p
+  Then
+    Print
+      Unary -
+        Literal 2
+  Else
+    Expression
+      Assign p
+        Lambda f(x)
+          Return
+            Get .y
+              Variable x
+";
+    assert_eq!(ast_to_string(&statements), expect);
+}
+
+fn display(source: &str) -> String {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.issues().is_empty(), "{:?}", errors.issues());
+    let lines: Vec<String> = statements.iter().map(Stmt::to_string).collect();
+    lines.join("\n")
+}
+
+#[test]
+fn display_statements() {
+    assert_eq!(
+        display(SOURCE),
+        "\
+let p = (1, \"a\");
+if (!p) print -2; else p = fn f(x) { return x.y; };"
+    );
+    assert_eq!(
+        display("fn f(a, b) { while (a) { a = a - 1; } return b; } struct P { x, y } enum E {}"),
+        "\
+fn f(a, b) { while (a) { a = a - 1; } return b; }
+struct P { x, y }
+enum E {}"
+    );
+    assert_eq!(
+        display("let (a, b) = (1,); if (a) if (b) print a; else print b, 2;"),
+        "\
+let (a, b) = (1,);
+if (a) if (b) print a; else print b, 2;"
+    );
+    assert_eq!(
+        display("const n = 1 + 2; { const m = n; }"),
+        "const n = 1 + 2;\n{ const m = n; }"
+    );
+    assert_eq!(
+        display("if (a) { if (b) print a; } else print b;"),
+        "if (a) { if (b) print a; } else print b;"
+    );
+}
+
+#[test]
+fn display_precedence() {
+    assert_eq!(display("(1 + 2) * 3 - (4 - 5);"), "(1 + 2) * 3 - (4 - 5);");
+    assert_eq!(display("-(-x) ** 2 ** -y;"), "-(-x) ** 2 ** -y;");
+    assert_eq!(display("- -x;"), "- -x;");
+    assert_eq!(display("~-x & ~(a | b);"), "~-x & ~(a | b);");
+    assert_eq!(display("a ~/ (b * c) ~/ ~d;"), "a ~/ (b * c) ~/ ~d;");
+    assert_eq!(display("a or b and !c == d;"), "a or b and !c == d;");
+    assert_eq!(display("a < b <= c;"), "a < b <= c;");
+    assert_eq!(display("1 + (if (a) 1 else 2);"), "1 + (if (a) 1 else 2);");
+    assert_eq!(display("({ 1 }) + f(x).y;"), "({ 1 }) + f(x).y;");
+    // An if ending a block is its value unless it would read as a statement
+    assert_eq!(
+        display("let v = { if (a) 1 else { 2 } };"),
+        "let v = { if (a) 1 else { 2 } };"
+    );
+    assert_eq!(
+        display("let v = { (if (a) {} else {}) };"),
+        "let v = { (if (a) {} else {}) };"
+    );
+    assert_eq!(display("(a | b) & c ^ d << 1;"), "(a | b) & c ^ d << 1;");
+    assert_eq!(
+        display("a & b < c == (d << e < f);"),
+        "a & b < c == (d << e < f);"
+    );
+}
+
+#[test]
+fn display_literals() {
+    assert_eq!(
+        display(r#"print "a${b + 1}c", """say "hi" ${x}""", P { x: 1.5 }, true, null;"#),
+        r#"print "a${b + 1}c", """say "hi" ${x}""", P { x: 1.5 }, true, null;"#
+    );
+    // The line break after the opening quotes is dropped, so the one that is text is doubled
+    assert_eq!(
+        display("print \"\"\"\n\n${x}\"\"\", \"\"\"\n${x}\"\"\";"),
+        "print \"\"\"\n\n${x}\"\"\", \"\"\"${x}\"\"\";"
+    );
+}
+
+#[test]
+fn display_type_checks() {
+    assert_eq!(display("x is Number;"), "x is Number;");
+    assert_eq!(display("a < b is Bool is Bool;"), "a < b is Bool is Bool;");
+    assert_eq!(
+        display("(x is Null) < 1 == y is Tuple;"),
+        "(x is Null) < 1 == y is Tuple;"
+    );
+}
+
+#[test]
+fn display_string_escapes() {
+    assert_eq!(
+        display(r#"print "a\n\"b\"\\", "tab\t${x}\0";"#),
+        r#"print "a\n\"b\"\\", "tab\t${x}\0";"#
+    );
+    assert_eq!(display("print \"line\nbreak\";"), r#"print "line\nbreak";"#);
+}
+
+#[test]
+fn display_loops() {
+    assert_eq!(
+        display("for (let i = 0; i < 3; i++) { if (i == 1) continue; break; }"),
+        "{ let i = 0; for (; i < 3; i = i + 1) { if (i == 1) continue; break; } }"
+    );
+    assert_eq!(display("for (;;) print 1;"), "while (true) print 1;");
+    assert_eq!(
+        display("do { i++; } while (i < 3);"),
+        "do { i = i + 1; } while (i < 3);"
+    );
+    assert_eq!(
+        display("for (x in [1, 2]) { print x; }"),
+        "for (let x in [1, 2]) { print x; }"
+    );
+}
+
+#[test]
+fn display_ternary() {
+    assert_eq!(display("a ? b : c ? d : e;"), "a ? b : c ? d : e;");
+    assert_eq!(display("(a ? b : c) ? d : e;"), "(a ? b : c) ? d : e;");
+    assert_eq!(
+        display("x = a or b ? y = 1 : 2;"),
+        "x = a or b ? y = 1 : 2;"
+    );
+    assert_eq!(display("(a ? 1 : 2) + 3;"), "(a ? 1 : 2) + 3;");
+}
+
+#[test]
+fn display_arrays() {
+    assert_eq!(
+        display("let a = [1, [2, 3], []]; a[0] = a[1][0] + f()[2]; a[1] += 1;"),
+        "\
+let a = [1, [2, 3], []];
+a[0] = a[1][0] + f()[2];
+a[1] = a[1] + 1;"
+    );
+    // Targets with side effects keep their compound form, evaluating them once
+    assert_eq!(
+        display("a[i()] += 1; f().x--; a[0][i()] *= 2;"),
+        "a[i()] += 1;\nf().x -= 1;\na[0][i()] *= 2;"
+    );
+}
+
+#[test]
+fn display_maps() {
+    assert_eq!(
+        display("let m = {\"a\": {:}, 1: { 2 }}; ({:}); m[\"a\"][true] = null;"),
+        "\
+let m = { \"a\": {:}, 1: { 2 } };
+({:});
+m[\"a\"][true] = null;"
+    );
+}
+
+#[test]
+fn display_classes() {
+    assert_eq!(
+        display("class A { fn get() { return this.x; } }"),
+        "class A { fn get() { return this.x; } }"
+    );
+}
+
+#[test]
+fn display_subclasses() {
+    assert_eq!(
+        display("class B < A { fn m() { return super.m(); } }"),
+        "class B < A { fn m() { return super.m(); } }"
+    );
+}
+
+fn format(source: &str) -> String {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.issues().is_empty(), "{:?}", errors.issues());
+    to_source(&statements)
+}
+
+#[test]
+fn format_indents_blocks() {
+    assert_eq!(
+        format("class A { fn f(x) { if (x) { return -(1 + x) * 2; } else print {}; } } let g = fn() {};"),
+        "\
+class A {
+    fn f(x) {
+        if (x) {
+            return -(1 + x) * 2;
+        } else print {};
+    }
+}
+let g = fn() {};
+"
+    );
+    // Blocks inside expressions are indented too, but not those inside strings
+    assert_eq!(
+        format("let y = { let t = f(fn(a) { return a; }); t } + 1; print \"${{ 1 }}\";"),
+        "\
+let y = {
+    let t = f(fn(a) {
+        return a;
+    });
+    t
+} + 1;
+print \"${{ 1 }}\";
+"
+    );
+}
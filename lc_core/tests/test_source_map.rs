@@ -0,0 +1,47 @@
+use lc_core::*;
+
+const SOURCE: &str = "let a = 1;\nprint a;\r\n\n  fn f() {}\nlast";
+
+fn naive_line(source: &str, line: usize) -> Option<&str> {
+    source.lines().nth(line - 1)
+}
+
+fn naive_location(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map_or(0, |i| i + 1);
+    (line, column)
+}
+
+#[test]
+fn source_map_lines() {
+    let map = SourceMap::new(SOURCE);
+    assert_eq!(map.line_count(), 5);
+    for line in 1..=map.line_count() {
+        assert_eq!(map.line(line), naive_line(SOURCE, line));
+    }
+    assert_eq!(map.line(0), None);
+    assert_eq!(map.line(6), None);
+}
+
+#[test]
+fn source_map_locations() {
+    let map = SourceMap::new(SOURCE);
+    for offset in 0..SOURCE.len() {
+        let (line, column) = map.location(offset);
+        assert_eq!((line, column), naive_location(SOURCE, offset));
+        assert_eq!(map.offset(line, column).unwrap_or(offset), offset);
+    }
+    assert_eq!(map.offset(2, 0), Some(11));
+    assert_eq!(map.offset(2, 9), None);
+}
+
+#[test]
+fn source_map_snippets() {
+    let map = SourceMap::new(SOURCE);
+    assert_eq!(map.snippet(Span::new(1, 4, 5)), Some("a"));
+    assert_eq!(map.snippet(Span::new(2, 17, 18)), Some("a"));
+    assert_eq!(map.snippet(Span::new(4, 24, 26)), Some("fn"));
+    // Multi-line spans are cut off at the end of their first line
+    assert_eq!(map.snippet(Span::new(2, 11, 30)), Some("print a;"));
+}
@@ -0,0 +1,21 @@
+use lc_core::*;
+
+#[test]
+fn suggest_edit_distance() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("abc", ""), 3);
+    assert_eq!(edit_distance("length", "lenght"), 2);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("count", "counts"), 1);
+}
+
+#[test]
+fn suggest_closest_match() {
+    let names = ["length", "left", "clock", "typeof"];
+    assert_eq!(closest_match("lenght", names), Some("length"));
+    assert_eq!(closest_match("clok", names), Some("clock"));
+    assert_eq!(closest_match("x", ["y", "z"]), Some("y"));
+    assert_eq!(closest_match("banana", names), None);
+    // An exact match is never a suggestion
+    assert_eq!(closest_match("left", ["left"]), None);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use crate::common::t_hash;
+use lc_core::*;
+
+#[test]
+fn symbol_interned_twice() {
+    let first = Symbol::string("interned twice".to_string());
+    let second = Symbol::string_str("interned twice");
+    assert_eq!(first, second);
+    assert_eq!(first.index(), second.index());
+    assert_eq!(t_hash(first), t_hash(second));
+    assert_eq!(t_hash(first.as_lit()), t_hash(second.as_lit()));
+
+    let ident = Symbol::ident_str("interned twice");
+    assert_eq!(ident, Symbol::ident("interned twice".to_string()));
+    assert_eq!(t_hash(ident), t_hash(Symbol::ident_str("interned twice")));
+}
+
+#[test]
+fn symbol_tables_distinct() {
+    let string = Symbol::string_str("same text");
+    let ident = Symbol::ident_str("same text");
+    assert_ne!(string, ident);
+    assert_ne!(t_hash(string), t_hash(ident));
+    assert_ne!(Symbol::string_str("other text"), string);
+}
+
+#[test]
+fn symbol_concatenation_interned() {
+    let joined = Symbol::string_str("con") + Symbol::string_str("cat");
+    assert_eq!(joined, Symbol::string_str("concat"));
+    assert_eq!(t_hash(joined), t_hash(Symbol::string_str("concat")));
+}
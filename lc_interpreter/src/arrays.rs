@@ -0,0 +1,184 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::*;
+use lc_core::*;
+
+pub fn define_arrays(environment: &mut Environment) {
+    environment.define_builtin::<LcMap>("map");
+    environment.define_builtin::<LcFilter>("filter");
+    environment.define_builtin::<LcReduce>("reduce");
+    environment.define_builtin::<LcPush>("push");
+    environment.define_builtin::<LcPop>("pop");
+}
+
+/// Checks the arity and that the first argument is an array, naming the builtin in the error.
+fn array_argument<'v>(
+    name: &str,
+    span: Span,
+    arity: usize,
+    arguments: &'v [Value],
+) -> Result<&'v Rc<RefCell<Vec<Value>>>, Throw> {
+    check_arity(span, arity, arguments)?;
+    match &arguments[0] {
+        Value::Array(elements) => Ok(elements),
+        _ => Err((span, format!("{} expects an array", name)).into()),
+    }
+}
+
+/// Checks the arity and that the arguments start with an array and a function, naming the
+/// builtin in the error. The elements are copied out, so the function may change the array
+/// while it is called on them.
+fn array_and_function(
+    name: &str,
+    span: Span,
+    arity: usize,
+    arguments: &[Value],
+) -> Result<(Vec<Value>, Value), Throw> {
+    check_arity(span, arity, arguments)?;
+    match &arguments[..2] {
+        [Value::Array(elements), function @ Value::Function(_)] => {
+            Ok((elements.borrow().clone(), function.clone()))
+        }
+        _ => Err((span, format!("{} expects an array and a function", name)).into()),
+    }
+}
+
+fn array_value(elements: Vec<Value>) -> Throw {
+    Value::Array(Rc::new(RefCell::new(elements))).into()
+}
+
+/// `map(array, function)`, a new array of the function's result for each element.
+#[derive(Clone, Debug, Default)]
+pub struct LcMap;
+impl<'a> Callable<'a> for LcMap {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let (elements, function) = match array_and_function("map", span, self.arity(), arguments) {
+            Ok(arguments) => arguments,
+            Err(throw) => return throw,
+        };
+        let mut mapped = Vec::with_capacity(elements.len());
+        for element in elements {
+            match interpreter.call_back(&function, span, vec![element]) {
+                Ok(value) => mapped.push(value),
+                Err(throw) => return throw,
+            }
+        }
+        array_value(mapped)
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn map/{}>", self.arity())
+    }
+}
+
+/// `filter(array, function)`, a new array of the elements for which the function's result is
+/// truthy.
+#[derive(Clone, Debug, Default)]
+pub struct LcFilter;
+impl<'a> Callable<'a> for LcFilter {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let (elements, function) = match array_and_function("filter", span, self.arity(), arguments)
+        {
+            Ok(arguments) => arguments,
+            Err(throw) => return throw,
+        };
+        let mut kept = Vec::new();
+        for element in elements {
+            match interpreter.call_back(&function, span, vec![element.clone()]) {
+                Ok(keep) if keep.is_truthy() => kept.push(element),
+                Ok(_) => (),
+                Err(throw) => return throw,
+            }
+        }
+        array_value(kept)
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn filter/{}>", self.arity())
+    }
+}
+
+/// `reduce(array, function, initial)`, which folds the elements from the left by calling
+/// `function(accumulator, element)`, starting with `initial`.
+#[derive(Clone, Debug, Default)]
+pub struct LcReduce;
+impl<'a> Callable<'a> for LcReduce {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let (elements, function) = match array_and_function("reduce", span, self.arity(), arguments)
+        {
+            Ok(arguments) => arguments,
+            Err(throw) => return throw,
+        };
+        let mut accumulator = arguments[2].clone();
+        for element in elements {
+            match interpreter.call_back(&function, span, vec![accumulator, element]) {
+                Ok(value) => accumulator = value,
+                Err(throw) => return throw,
+            }
+        }
+        accumulator.into()
+    }
+
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn reduce/{}>", self.arity())
+    }
+}
+
+/// `push(array, value)`, which appends the value to the array in place.
+#[derive(Clone, Debug, Default)]
+pub struct LcPush;
+impl<'a> Callable<'a> for LcPush {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match array_argument("push", span, self.arity(), arguments) {
+            Ok(elements) => {
+                elements.borrow_mut().push(arguments[1].clone());
+                Literal::Null.into()
+            }
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn push/{}>", self.arity())
+    }
+}
+
+/// `pop(array)`, which removes the last element and returns it, or null if there is none.
+#[derive(Clone, Debug, Default)]
+pub struct LcPop;
+impl<'a> Callable<'a> for LcPop {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match array_argument("pop", span, self.arity(), arguments) {
+            Ok(elements) => elements
+                .borrow_mut()
+                .pop()
+                .unwrap_or(Value::Literal(Literal::Null))
+                .into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn pop/{}>", self.arity())
+    }
+}
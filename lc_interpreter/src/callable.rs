@@ -1,10 +1,20 @@
 use std::{
+    cell::RefCell,
     fmt::Debug,
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
     thread,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime,
+};
 use dyn_clone::DynClone;
+use indexmap::IndexMap;
 
 use crate::*;
 use lc_core::*;
@@ -13,19 +23,235 @@ use lc_core::*;
 pub enum Value {
     Literal(Literal),
     Function(Box<dyn for<'a> Callable<'a>>),
+    Enum {
+        name: Symbol,
+        variants: Rc<[Symbol]>,
+    },
+    EnumVariant {
+        enum_name: Symbol,
+        /// Those of the enum it belongs to, whose identity tells apart variants of two enums
+        /// declared with the same name
+        variants: Rc<[Symbol]>,
+        variant: Symbol,
+        ordinal: usize,
+    },
+    Struct {
+        name: Symbol,
+        fields: Rc<[Symbol]>,
+    },
+    Record(Rc<RefCell<Record>>),
+    Instance(Rc<RefCell<Instance>>),
+    Tuple(Vec<Value>),
+    /// Shared and mutable in place, so copies of an array value all see the same elements
+    Array(Rc<RefCell<Vec<Value>>>),
+    /// Shared like arrays, and keyed by the literal values. Entries stay in the order their keys
+    /// were first inserted.
+    Map(Rc<RefCell<IndexMap<Literal, Value>>>),
 }
 impl Value {
     pub fn is_truthy(&self) -> bool {
         match self {
             Value::Literal(lit) => lit.is_truthy(),
             Value::Function(_) => false,
+            Value::Enum { .. }
+            | Value::EnumVariant { .. }
+            | Value::Struct { .. }
+            | Value::Record(_)
+            | Value::Instance(_)
+            | Value::Tuple(_)
+            | Value::Array(_)
+            | Value::Map(_) => true,
         }
     }
 
+    /// Whether this value is of the type named on the right of an `is` expression. Records,
+    /// instances and enum variants are typed by their declaration, so they match none of the
+    /// built-in types.
+    pub fn is_type(&self, type_name: TypeName) -> bool {
+        matches!(
+            (self, type_name),
+            (Value::Literal(Literal::Bool(_)), TypeName::Bool)
+                | (Value::Literal(Literal::Null), TypeName::Null)
+                | (Value::Literal(Literal::Int(_)), TypeName::Int)
+                | (
+                    Value::Literal(Literal::Int(_) | Literal::Number(_)),
+                    TypeName::Number
+                )
+                | (Value::Literal(Literal::String(_)), TypeName::String)
+                | (Value::Function(_), TypeName::Function)
+                | (Value::Enum { .. }, TypeName::Enum)
+                | (Value::Struct { .. }, TypeName::Struct)
+                | (Value::Tuple(_), TypeName::Tuple)
+                | (Value::Array(_), TypeName::Array)
+                | (Value::Map(_), TypeName::Map)
+        )
+    }
+
+    /// The name of this value's type, as `typeof` gives it. Instances, records and enum
+    /// variants are named after their class, struct or enum.
+    pub fn type_name(&self) -> String {
+        let name = match self {
+            Value::Literal(Literal::String(_)) => "String",
+            Value::Literal(Literal::Int(_)) => "Int",
+            Value::Literal(Literal::Number(_)) => "Number",
+            Value::Literal(Literal::Bool(_)) => "Bool",
+            Value::Literal(Literal::Null) => "Null",
+            Value::Function(func) if func.is_native() => "NativeFunction",
+            Value::Function(_) => "Function",
+            Value::Enum { .. } => "Enum",
+            Value::EnumVariant { enum_name, .. } => return enum_name.to_string(),
+            Value::Struct { .. } => "Struct",
+            Value::Tuple(_) => "Tuple",
+            Value::Array(_) => "Array",
+            Value::Map(_) => "Map",
+            Value::Instance(instance) => return instance.borrow().class.name.to_string(),
+            Value::Record(record) => return record.borrow().name.to_string(),
+        };
+        name.to_string()
+    }
+
+    /// The value as `print` shows it. A string is its raw text, while strings inside
+    /// collections, records and map keys are shown by [`Value::repr`].
     pub fn as_str(&self) -> String {
+        self.as_str_within(&mut Vec::new())
+    }
+
+    /// [`Value::as_str`] for a value inside the arrays, maps and records in `enclosing`, those
+    /// already being printed. Meeting one of them again shows `[...]`, `{...}` or `Name {...}`
+    /// instead, so a collection that contains itself still prints.
+    pub(crate) fn as_str_within(&self, enclosing: &mut Vec<*const ()>) -> String {
+        let pointer = match self {
+            Value::Array(elements) => Rc::as_ptr(elements) as *const (),
+            Value::Map(entries) => Rc::as_ptr(entries) as *const (),
+            Value::Record(record) => Rc::as_ptr(record) as *const (),
+            _ => return self.as_str_unenclosed(enclosing),
+        };
+        if enclosing.contains(&pointer) {
+            return match self {
+                Value::Array(_) => "[...]".to_string(),
+                Value::Record(record) => format!("{} {{...}}", record.borrow().name),
+                _ => "{...}".to_string(),
+            };
+        }
+        enclosing.push(pointer);
+        let str = self.as_str_unenclosed(enclosing);
+        enclosing.pop();
+        str
+    }
+
+    fn as_str_unenclosed(&self, enclosing: &mut Vec<*const ()>) -> String {
         match self {
             Value::Literal(lit) => lit.as_str(),
             Value::Function(func) => func.as_str(),
+            Value::Enum { name, .. } => format!("<enum {}>", name),
+            Value::EnumVariant {
+                enum_name, variant, ..
+            } => format!("{}.{}", enum_name, variant),
+            Value::Struct { name, .. } => format!("<struct {}>", name),
+            Value::Record(record) => record.borrow().as_str_within(enclosing),
+            Value::Instance(instance) => instance.borrow().as_str(),
+            Value::Tuple(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| element.repr_within(enclosing))
+                    .collect();
+                match elements.as_slice() {
+                    [element] => format!("({},)", element),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            Value::Array(elements) => {
+                let elements: Vec<String> = elements
+                    .borrow()
+                    .iter()
+                    .map(|element| element.repr_within(enclosing))
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Value::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.repr(), value.repr_within(enclosing)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+        }
+    }
+
+    /// The value as it appears inside a collection, with strings quoted and escaped so that
+    /// `["a, b"]` and `["a", "b"]` print differently. Everything else shows as by
+    /// [`Value::as_str`].
+    pub fn repr(&self) -> String {
+        self.repr_within(&mut Vec::new())
+    }
+
+    pub(crate) fn repr_within(&self, enclosing: &mut Vec<*const ()>) -> String {
+        match self {
+            Value::Literal(lit) => lit.repr(),
+            _ => self.as_str_within(enclosing),
+        }
+    }
+}
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Literal(left), Value::Literal(right)) => left == right,
+            (
+                Value::Enum { variants, .. },
+                Value::Enum {
+                    variants: other, ..
+                },
+            ) => Rc::ptr_eq(variants, other),
+            (
+                Value::EnumVariant {
+                    variants, ordinal, ..
+                },
+                Value::EnumVariant {
+                    variants: other,
+                    ordinal: other_ordinal,
+                    ..
+                },
+            ) => Rc::ptr_eq(variants, other) && ordinal == other_ordinal,
+            (Value::Struct { fields, .. }, Value::Struct { fields: other, .. }) => {
+                Rc::ptr_eq(fields, other)
+            }
+            (Value::Function(func), Value::Function(other)) => match (func.id(), other.id()) {
+                (Some(id), Some(other_id)) => id == other_id,
+                (None, None) => func.as_str() == other.as_str(),
+                _ => false,
+            },
+            (Value::Record(record), Value::Record(other)) => Rc::ptr_eq(record, other),
+            (Value::Instance(instance), Value::Instance(other)) => Rc::ptr_eq(instance, other),
+            (Value::Tuple(elements), Value::Tuple(other)) => elements == other,
+            (Value::Array(elements), Value::Array(other)) => Rc::ptr_eq(elements, other),
+            (Value::Map(entries), Value::Map(other)) => Rc::ptr_eq(entries, other),
+            _ => false,
+        }
+    }
+}
+impl Eq for Value {}
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Literal(lit) => lit.hash(state),
+            Value::Function(func) => match func.id() {
+                Some(id) => id.hash(state),
+                None => func.as_str().hash(state),
+            },
+            Value::Enum { variants, .. } => Rc::as_ptr(variants).hash(state),
+            Value::EnumVariant {
+                variants, ordinal, ..
+            } => (Rc::as_ptr(variants), ordinal).hash(state),
+            Value::Struct { fields, .. } => Rc::as_ptr(fields).hash(state),
+            // By identity, like equality, so mutating a record, instance, array or map never
+            // changes its hash
+            Value::Record(record) => Rc::as_ptr(record).hash(state),
+            Value::Instance(instance) => Rc::as_ptr(instance).hash(state),
+            Value::Tuple(elements) => elements.hash(state),
+            Value::Array(elements) => Rc::as_ptr(elements).hash(state),
+            Value::Map(entries) => Rc::as_ptr(entries).hash(state),
         }
     }
 }
@@ -44,6 +270,13 @@ impl From<Function> for Value {
 pub enum Throw {
     Return(Value),
     Error(SpannedError),
+    /// Unwinds to the innermost loop, ending it
+    Break,
+    /// Unwinds to the innermost loop, moving on to its next iteration
+    Continue,
+    /// (`function`, `span`, `arguments`), unwinds out of a function returning a call so that
+    /// its caller makes the call instead, keeping the stack from growing with each one
+    TailCall(Box<dyn for<'a> Callable<'a>>, Span, Vec<Value>),
 }
 impl From<Literal> for Throw {
     fn from(value: Literal) -> Throw {
@@ -60,6 +293,11 @@ impl From<SpannedError> for Throw {
         Throw::Error(value)
     }
 }
+impl From<RuntimeError> for Throw {
+    fn from(value: RuntimeError) -> Self {
+        Throw::Error(value.into())
+    }
+}
 impl From<(Span, &str)> for Throw {
     fn from(value: (Span, &str)) -> Self {
         Throw::Error(SpannedError::from(value))
@@ -72,38 +310,83 @@ impl From<(Span, String)> for Throw {
 }
 
 pub trait Callable<'a>: DynClone + Debug {
-    fn call(&mut self, interpreter: &'a mut Interpreter, arguments: &[Value]) -> Throw;
+    /// Calls with `arguments`, where `span` covers the call expression so that errors can point
+    /// at it. Hosts calling through [`Interpreter::call_value`] have no call site to give.
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw;
     fn arity(&self) -> usize;
     fn as_str(&self) -> String;
+
+    /// Identifies a function instance, shared by its clones, for equality and hashing. Native
+    /// functions are stateless, so by default they have none and are told apart by name.
+    fn id(&self) -> Option<usize> {
+        None
+    }
+
+    /// The class this callable constructs, if it is one.
+    fn as_class(&self) -> Option<&LcClass> {
+        None
+    }
+
+    /// Whether this is a builtin rather than a function or class declared by the script.
+    fn is_native(&self) -> bool {
+        true
+    }
 }
 dyn_clone::clone_trait_object!(for<'a> Callable<'a>);
 
+/// The standard error for a call with the wrong number of arguments. Every fixed-arity
+/// callable starts its `call` with this; variadic builtins skip it and check for themselves.
+pub fn check_arity(span: Span, expected: usize, arguments: &[Value]) -> Result<(), Throw> {
+    if arguments.len() == expected {
+        return Ok(());
+    }
+    Err((
+        span,
+        format!(
+            "Function expected {} arguments but was given {}",
+            expected,
+            arguments.len()
+        ),
+    )
+        .into())
+}
+
+/// The name given to lambdas declared without one.
+const ANONYMOUS: &str = "<anonymous>";
+static FUNCTION_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh [`Callable::id`], for callables that carry state of their own.
+pub(crate) fn next_function_id() -> usize {
+    FUNCTION_ID.fetch_add(1, Ordering::SeqCst)
+}
+
 #[derive(Clone, Debug)]
 pub struct Function {
+    id: usize,
     name: Ident,
-    params: Vec<Ident>,
-    body: Vec<Stmt>,
+    params: Rc<[Ident]>,
+    body: Rc<[Stmt]>,
     closure: Environment,
+    binds_name: bool,
 }
 impl<'a> Callable<'a> for Function {
-    fn call(&mut self, interpreter: &'a mut Interpreter, arguments: &[Value]) -> Throw {
-        if arguments.len() != self.params.len() {
-            return (
-                self.name.span,
-                format!(
-                    "Function expected {} arguments but was given {}",
-                    self.params.len(),
-                    arguments.len()
-                ),
-            )
-                .into();
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let environment = Environment::enclosed(&self.closure);
+        if self.binds_name {
+            let function = Value::Function(Box::new(self.clone()));
+            environment.define(self.name.symbol, function);
         }
         for (i, arg) in arguments.iter().enumerate().take(self.params.len()) {
-            self.closure.define(self.params[i].symbol, arg.to_owned())
+            environment.define(self.params[i].symbol, arg.to_owned())
         }
 
-        match interpreter.execute_block(&self.body, &self.closure) {
-            Ok(_) => Literal::Null.into(),
+        match interpreter.execute_block(&self.body, &environment) {
+            // The resolver rejects loop control outside a loop, and a loop around the call
+            // isn't one, so `break` and `continue` never unwind past the function
+            Ok(_) | Err(Throw::Break | Throw::Continue) => Literal::Null.into(),
             Err(throw) => throw,
         }
     }
@@ -113,30 +396,94 @@ impl<'a> Callable<'a> for Function {
     }
 
     fn as_str(&self) -> String {
-        format!("<fn {}>", self.name.symbol)
+        if self.name.symbol.to_string() == ANONYMOUS {
+            format!("<lambda/{}>", self.arity())
+        } else {
+            format!("<fn {}/{}>", self.name.symbol, self.arity())
+        }
+    }
+
+    fn id(&self) -> Option<usize> {
+        Some(self.id)
+    }
+
+    fn is_native(&self) -> bool {
+        false
     }
 }
 impl Function {
-    pub fn new(name: &Ident, params: &Vec<Ident>, body: &Vec<Stmt>, closure: &Environment) -> Self {
+    pub fn new(
+        name: &Ident,
+        params: &Rc<[Ident]>,
+        body: &Rc<[Stmt]>,
+        closure: &Environment,
+    ) -> Self {
         Self {
+            id: next_function_id(),
             name: name.to_owned(),
-            params: params.to_owned(),
-            body: body.to_owned(),
+            params: Rc::clone(params),
+            body: Rc::clone(body),
             closure: closure.to_owned(),
+            binds_name: false,
+        }
+    }
+
+    pub fn lambda(
+        name: &Option<Ident>,
+        params: &Rc<[Ident]>,
+        body: &Rc<[Stmt]>,
+        closure: &Environment,
+        span: Span,
+    ) -> Self {
+        match name {
+            Some(name) => Self {
+                binds_name: true,
+                ..Self::new(name, params, body, closure)
+            },
+            None => Self::new(&Ident::new(ANONYMOUS.into(), span), params, body, closure),
+        }
+    }
+
+    /// A copy of this method closing over a new scope with `this` bound to `instance`. The
+    /// copy keeps the method's identity, so a method read twice from the same instance compares
+    /// equal.
+    pub fn bind(&self, instance: Value) -> Self {
+        let closure = Environment::enclosed(&self.closure);
+        closure.define(Symbol::ident_str(THIS), instance);
+        Self {
+            closure,
+            ..self.to_owned()
         }
     }
 }
 
 pub fn define_builtins(environment: &mut Environment) {
     environment.define_builtin::<LcClock>("clock");
+    environment.define_builtin::<LcClockMillis>("clockMillis");
+    environment.define_builtin::<LcFormatTime>("formatTime");
     environment.define_builtin::<LcTypeof>("typeof");
     environment.define_builtin::<LcSleep>("sleep");
+    environment.define_builtin::<LcDefined>("defined");
+    environment.define_builtin::<LcLen>("len");
+    environment.define_builtin::<LcRandom>("random");
+    environment.define_builtin::<LcInput>("input");
+    environment.define_builtin::<LcWrite>("write");
+    environment.define_builtin::<LcStr>("str");
+    environment.define_builtin::<LcNum>("num");
+    environment.define_builtin::<LcAssert>("assert");
+    define_math(environment);
+    define_strings(environment);
+    define_arrays(environment);
+    define_maps(environment);
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct LcClock;
 impl<'a> Callable<'a> for LcClock {
-    fn call(&mut self, _: &'a mut Interpreter, _: &[Value]) -> Throw {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
         Literal::Number(
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -151,35 +498,84 @@ impl<'a> Callable<'a> for LcClock {
     }
 
     fn as_str(&self) -> String {
-        "<fn clock>".to_string()
+        format!("<native fn clock/{}>", self.arity())
+    }
+}
+
+/// Whole milliseconds since the Unix epoch, as an int.
+#[derive(Clone, Debug, Default)]
+pub struct LcClockMillis;
+impl<'a> Callable<'a> for LcClockMillis {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        Literal::Int(millis as i64).into()
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn clockMillis/{}>", self.arity())
+    }
+}
+
+/// Formats a Unix timestamp in seconds, as `clock` gives, in UTC with `strftime` specifiers
+/// like `%Y-%m-%d %H:%M:%S`.
+#[derive(Clone, Debug, Default)]
+pub struct LcFormatTime;
+impl<'a> Callable<'a> for LcFormatTime {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let (Some(timestamp), Value::Literal(Literal::String(format))) =
+            (Interpreter::number_value(&arguments[0]), &arguments[1])
+        else {
+            return (span, "formatTime expects a timestamp and a format string").into();
+        };
+        let format = format.resolve();
+        let items: Vec<Item> = StrftimeItems::new(&format).collect();
+        if items.contains(&Item::Error) {
+            return (span, format!("Invalid time format '{}'", format)).into();
+        }
+        let seconds = timestamp.floor();
+        let nanos = ((timestamp - seconds) * 1e9) as u32;
+        let time = if seconds.is_finite() && seconds.abs() < i64::MAX as f64 {
+            DateTime::from_timestamp(seconds as i64, nanos)
+        } else {
+            None
+        };
+        let Some(time) = time else {
+            return (span, "Timestamp out of range").into();
+        };
+        let formatted = time.format_with_items(items.into_iter()).to_string();
+        Literal::String(Symbol::string(formatted)).into()
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn formatTime/{}>", self.arity())
     }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct LcTypeof;
 impl<'a> Callable<'a> for LcTypeof {
-    fn call(&mut self, _: &mut Interpreter, arguments: &[Value]) -> Throw {
-        if arguments.len() != self.arity() {
-            return (
-                Span::default(),
-                format!(
-                    "Function expected {} arguments but was given {}",
-                    self.arity(),
-                    arguments.len()
-                ),
-            )
-                .into();
+    fn call(&mut self, _: &mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
         }
-        let res = match &arguments[0] {
-            Value::Literal(lit) => match lit {
-                Literal::String(_) => "String",
-                Literal::Number(_) => "Number",
-                Literal::Bool(_) => "Bool",
-                Literal::Null => "Null",
-            },
-            Value::Function(_) => "Function",
-        };
-        Literal::String(Symbol::string(res.to_string())).into()
+        Literal::String(Symbol::string(arguments[0].type_name())).into()
     }
 
     fn arity(&self) -> usize {
@@ -187,53 +583,260 @@ impl<'a> Callable<'a> for LcTypeof {
     }
 
     fn as_str(&self) -> String {
-        "<fn typeof>".to_string()
+        format!("<native fn typeof/{}>", self.arity())
     }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct LcSleep;
 impl<'a> Callable<'a> for LcSleep {
-    fn call(&mut self, _: &'a mut Interpreter, arguments: &[Value]) -> Throw {
-        if arguments.len() != self.arity() {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let Some(num) = Interpreter::number_value(&arguments[0]) else {
             return (
-                Span::default(),
-                format!(
-                    "Function expected {} arguments but was given {}",
-                    self.arity(),
-                    arguments.len()
-                ),
+                span,
+                "sleep duration must be a number in representing milliseconds",
             )
                 .into();
+        };
+        let duration = Duration::from_secs_f64(num / 1000.0);
+        thread::sleep(duration);
+        Literal::Null.into()
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn sleep/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcDefined;
+impl<'a> Callable<'a> for LcDefined {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let Value::Literal(Literal::String(name)) = &arguments[0] else {
+            return (span, "defined expects the variable name as a string").into();
+        };
+        let name = Ident::new(name.to_string(), span);
+        Literal::Bool(interpreter.environment.is_defined(&name)).into()
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn defined/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcLen;
+impl<'a> Callable<'a> for LcLen {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let len = match &arguments[0] {
+            // In characters rather than bytes
+            Value::Literal(Literal::String(string)) => string.resolve().chars().count(),
+            Value::Array(elements) => elements.borrow().len(),
+            Value::Map(entries) => entries.borrow().len(),
+            _ => {
+                return (span, "len expects a string, array or map").into();
+            }
+        };
+        Literal::Int(len as i64).into()
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn len/{}>", self.arity())
+    }
+}
+
+/// Draws from the interpreter's own generator, see [`Interpreter::with_seed`].
+#[derive(Clone, Debug, Default)]
+pub struct LcRandom;
+impl<'a> Callable<'a> for LcRandom {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
         }
-        let duration = match &arguments[0] {
-            Value::Literal(lit) => match lit {
-                Literal::Number(num) => Duration::from_secs_f64(num / 1000.0),
-                _ => {
-                    return (
-                        Span::default(),
-                        "sleep duration must be a number in representing milliseconds",
-                    )
-                        .into()
+        Literal::Number(interpreter.random()).into()
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn random/{}>", self.arity())
+    }
+}
+
+/// `input()` or `input(prompt)`, the next line of input or null once it runs out.
+#[derive(Clone, Debug, Default)]
+pub struct LcInput;
+impl<'a> Callable<'a> for LcInput {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let prompt = match arguments {
+            [] => String::new(),
+            [Value::Literal(Literal::String(prompt))] => prompt.resolve(),
+            [_] => return (span, "input expects the prompt as a string").into(),
+            _ => {
+                return (
+                    span,
+                    format!(
+                        "Function expected at most 1 arguments but was given {}",
+                        arguments.len()
+                    ),
+                )
+                    .into()
+            }
+        };
+        match interpreter.read_line(&prompt) {
+            Ok(Some(line)) => Literal::String(Symbol::string(line)).into(),
+            Ok(None) => Literal::Null.into(),
+            Err(err) => (span, format!("Failed to read input: {}", err)).into(),
+        }
+    }
+
+    /// The prompt is optional, so this is the fewest arguments it takes.
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn input/{}>", self.arity())
+    }
+}
+
+/// `write(value)`, which outputs the value as `print` would but without ending the line.
+#[derive(Clone, Debug, Default)]
+pub struct LcWrite;
+impl<'a> Callable<'a> for LcWrite {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        match interpreter.write(&arguments[0].as_str()) {
+            Ok(()) => Literal::Null.into(),
+            Err(err) => (span, format!("Failed to write output: {}", err)).into(),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn write/{}>", self.arity())
+    }
+}
+
+/// Any value as the text `print` would show for it.
+#[derive(Clone, Debug, Default)]
+pub struct LcStr;
+impl<'a> Callable<'a> for LcStr {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        Literal::String(Symbol::string(arguments[0].as_str())).into()
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn str/{}>", self.arity())
+    }
+}
+
+/// Parses a string as a number, ignoring surrounding whitespace. Text that isn't a finite
+/// number gives null, and numbers are returned unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct LcNum;
+impl<'a> Callable<'a> for LcNum {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        match &arguments[0] {
+            Value::Literal(Literal::String(string)) => {
+                let string = string.resolve();
+                let string = string.trim();
+                if let Ok(num) = string.parse::<i64>() {
+                    return Literal::Int(num).into();
                 }
-            },
-            Value::Function(_) => {
+                match string.parse::<f64>() {
+                    Ok(num) if num.is_finite() => Literal::Number(num).into(),
+                    _ => Literal::Null.into(),
+                }
+            }
+            Value::Literal(num @ (Literal::Int(_) | Literal::Number(_))) => (*num).into(),
+            _ => (span, "num expects a string").into(),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn num/{}>", self.arity())
+    }
+}
+
+/// `assert(condition)` or `assert(condition, message)`, an error unless the condition is
+/// truthy.
+#[derive(Clone, Debug, Default)]
+pub struct LcAssert;
+impl<'a> Callable<'a> for LcAssert {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let (condition, message) = match arguments {
+            [condition] => (condition, None),
+            [condition, Value::Literal(Literal::String(message))] => {
+                (condition, Some(message.resolve()))
+            }
+            [_, _] => return (span, "assert expects the message as a string").into(),
+            _ => {
                 return (
-                    Span::default(),
-                    "sleep duration must be a number in representing milliseconds",
+                    span,
+                    format!(
+                        "Function expected 1 or 2 arguments but was given {}",
+                        arguments.len()
+                    ),
                 )
                     .into()
             }
         };
-        thread::sleep(duration);
-        Literal::Null.into()
+        match message {
+            _ if condition.is_truthy() => Literal::Null.into(),
+            Some(message) => (span, format!("assertion failed: {}", message)).into(),
+            None => (span, "assertion failed").into(),
+        }
     }
 
+    /// The message is optional, so this is the fewest arguments it takes.
     fn arity(&self) -> usize {
         1
     }
 
     fn as_str(&self) -> String {
-        "<fn sleep>".to_string()
+        format!("<native fn assert/{}>", self.arity())
     }
 }
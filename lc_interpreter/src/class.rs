@@ -0,0 +1,98 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::*;
+use lc_core::*;
+
+/// The name a method's instance is bound to.
+pub(crate) const THIS: &str = "this";
+/// The name a subclass method's superclass is bound to.
+pub(crate) const SUPER: &str = "super";
+
+/// A `class` declaration. Calling it constructs a new [`Instance`].
+#[derive(Clone, Debug)]
+pub struct LcClass {
+    id: usize,
+    pub name: Symbol,
+    superclass: Option<Box<LcClass>>,
+    methods: Rc<HashMap<Symbol, Function>>,
+}
+impl<'a> Callable<'a> for LcClass {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        let instance = Instance::new(self.to_owned());
+        Value::Instance(Rc::new(RefCell::new(instance))).into()
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn as_str(&self) -> String {
+        format!("<class {}>", self.name)
+    }
+
+    fn id(&self) -> Option<usize> {
+        Some(self.id)
+    }
+
+    fn as_class(&self) -> Option<&LcClass> {
+        Some(self)
+    }
+
+    fn is_native(&self) -> bool {
+        false
+    }
+}
+impl LcClass {
+    pub fn new(
+        name: Symbol,
+        superclass: Option<LcClass>,
+        methods: impl IntoIterator<Item = (Symbol, Function)>,
+    ) -> Self {
+        Self {
+            id: next_function_id(),
+            name,
+            superclass: superclass.map(Box::new),
+            methods: Rc::new(methods.into_iter().collect()),
+        }
+    }
+
+    /// Looks for the method on this class first, then up the chain of superclasses.
+    pub fn find_method(&self, name: Symbol) -> Option<&Function> {
+        self.methods.get(&name).or_else(|| {
+            self.superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name))
+        })
+    }
+}
+
+/// An object constructed from a class. Unlike a record, any field can be added by assigning
+/// to it. Fields shadow methods of the same name.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub class: LcClass,
+    fields: HashMap<Symbol, Value>,
+}
+impl Instance {
+    pub fn new(class: LcClass) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, field: Symbol) -> Option<&Value> {
+        self.fields.get(&field)
+    }
+
+    pub fn set(&mut self, field: Symbol, value: Value) {
+        self.fields.insert(field, value);
+    }
+
+    pub fn as_str(&self) -> String {
+        format!("<{} instance>", self.class.name)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::*;
+use lc_core::*;
+
+type CompileResult = Result<(), SpannedError>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OpCode {
+    /// Push a constant onto the stack
+    Constant(Literal),
+    /// Discard the top of the stack
+    Pop,
+    DefineGlobal(Symbol),
+    GetGlobal(Symbol),
+    SetGlobal(Symbol),
+    /// Move the top of the stack into a new local slot
+    DefineLocal,
+    GetLocal(usize),
+    SetLocal(usize),
+    /// Discard the `n` most recently defined local slots
+    PopLocals(usize),
+    Binary(BinaryOp),
+    Unary(UnaryOp),
+    /// Print the `n` values on top of the stack, separated by spaces
+    Print(usize),
+    /// Replace the `n` values on top of the stack with the string joining them
+    Interpolate(usize),
+    /// Replace the top of the stack with whether it has the given type
+    Is(TypeName),
+    /// Unconditionally continue execution at the target instruction
+    Jump(usize),
+    /// Jump to the target if the top of the stack is falsy, without popping it
+    JumpIfFalse(usize),
+    /// Jump to the target if the top of the stack is truthy, without popping it
+    JumpIfTrue(usize),
+}
+
+/// A flat sequence of instructions, with the source span each instruction was lowered from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub spans: Vec<Span>,
+}
+impl From<Vec<OpCode>> for Chunk {
+    fn from(code: Vec<OpCode>) -> Self {
+        let spans = vec![Span::default(); code.len()];
+        Self { code, spans }
+    }
+}
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    fn emit(&mut self, op: OpCode, span: Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    fn patch(&mut self, index: usize, target: usize) {
+        match &mut self.code[index] {
+            OpCode::Jump(to) | OpCode::JumpIfFalse(to) | OpCode::JumpIfTrue(to) => *to = target,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Lowers a resolved program into a [`Chunk`] for the [`Vm`](crate::Vm).
+///
+/// Only a subset of the language is supported: literals, arithmetic, variables, blocks,
+/// `print`, `if` and `while`. Anything else is reported as an error.
+#[derive(Debug, Default)]
+pub struct Compiler {
+    chunk: Chunk,
+    locals: Vec<(Symbol, usize)>,
+    scope_depth: usize,
+}
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compile(mut self, statements: &[Stmt]) -> Result<Chunk, SpannedError> {
+        for statement in statements {
+            self.statement(statement)?;
+        }
+        Ok(optimize(self.chunk))
+    }
+
+    fn statement(&mut self, stmt: &Stmt) -> CompileResult {
+        match stmt {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                self.end_scope(Span::default());
+                Ok(())
+            }
+            Stmt::Expression(ex) => {
+                self.expression(ex)?;
+                self.chunk.emit(OpCode::Pop, ex.span);
+                Ok(())
+            }
+            Stmt::If(condition, st_then, st_else) => {
+                self.expression(condition)?;
+                let then_jump = self.chunk.emit(OpCode::JumpIfFalse(0), condition.span);
+                self.chunk.emit(OpCode::Pop, condition.span);
+                self.statement(st_then)?;
+                let else_jump = self.chunk.emit(OpCode::Jump(0), condition.span);
+                self.chunk.patch(then_jump, self.chunk.len());
+                self.chunk.emit(OpCode::Pop, condition.span);
+                if let Some(st_else) = st_else {
+                    self.statement(st_else)?;
+                }
+                self.chunk.patch(else_jump, self.chunk.len());
+                Ok(())
+            }
+            Stmt::Print(expressions) => {
+                for ex in expressions {
+                    self.expression(ex)?;
+                }
+                let span = expressions[0]
+                    .span
+                    .to(expressions[expressions.len() - 1].span);
+                self.chunk.emit(OpCode::Print(expressions.len()), span);
+                Ok(())
+            }
+            Stmt::Const(id, initializer) | Stmt::Let(id, initializer) => {
+                self.expression(initializer)?;
+                if self.scope_depth == 0 {
+                    self.chunk.emit(OpCode::DefineGlobal(id.symbol), id.span);
+                } else {
+                    self.chunk.emit(OpCode::DefineLocal, id.span);
+                    self.locals.push((id.symbol, self.scope_depth));
+                }
+                Ok(())
+            }
+            Stmt::While(condition, body, increment) => {
+                let start = self.chunk.len();
+                self.expression(condition)?;
+                let exit_jump = self.chunk.emit(OpCode::JumpIfFalse(0), condition.span);
+                self.chunk.emit(OpCode::Pop, condition.span);
+                self.statement(body)?;
+                if let Some(increment) = increment {
+                    self.expression(increment)?;
+                    self.chunk.emit(OpCode::Pop, increment.span);
+                }
+                self.chunk.emit(OpCode::Jump(start), condition.span);
+                self.chunk.patch(exit_jump, self.chunk.len());
+                self.chunk.emit(OpCode::Pop, condition.span);
+                Ok(())
+            }
+            Stmt::DoWhile(body, condition) => {
+                let start = self.chunk.len();
+                self.statement(body)?;
+                self.expression(condition)?;
+                let exit_jump = self.chunk.emit(OpCode::JumpIfFalse(0), condition.span);
+                self.chunk.emit(OpCode::Pop, condition.span);
+                self.chunk.emit(OpCode::Jump(start), condition.span);
+                self.chunk.patch(exit_jump, self.chunk.len());
+                self.chunk.emit(OpCode::Pop, condition.span);
+                Ok(())
+            }
+            Stmt::Break(span) | Stmt::Continue(span) => {
+                Err(Self::unsupported("Loop control statements", *span))
+            }
+            Stmt::Class(id, _, _) => Err(Self::unsupported("Classes", id.span)),
+            Stmt::Enum(id, _) => Err(Self::unsupported("Enums", id.span)),
+            Stmt::ForEach(_, iterable, _) => Err(Self::unsupported("For-in loops", iterable.span)),
+            Stmt::Function(id, _, _) => Err(Self::unsupported("Functions", id.span)),
+            Stmt::LetTuple(ids, _) => Err(Self::unsupported("Tuples", ids[0].span)),
+            Stmt::Struct(id, _) => Err(Self::unsupported("Structs", id.span)),
+            Stmt::Return(ex) => Err(Self::unsupported("Return statements", ex.span)),
+        }
+    }
+
+    fn expression(&mut self, ex: &Expr) -> CompileResult {
+        match &ex.kind {
+            ExprKind::Assign(id, right) => {
+                self.expression(right)?;
+                match self.resolve_local(id) {
+                    Some(slot) => self.chunk.emit(OpCode::SetLocal(slot), ex.span),
+                    None => self.chunk.emit(OpCode::SetGlobal(id.symbol), ex.span),
+                };
+            }
+            ExprKind::Binary(left, op, right) => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.chunk.emit(OpCode::Binary(*op), ex.span);
+            }
+            ExprKind::Block(statements, value) => {
+                self.begin_scope();
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                match value {
+                    Some(value) => self.expression(value)?,
+                    None => {
+                        self.chunk.emit(OpCode::Constant(Literal::Null), ex.span);
+                    }
+                }
+                self.end_scope(ex.span);
+            }
+            ExprKind::Grouping(inner) => self.expression(inner)?,
+            ExprKind::Interpolation(parts) => {
+                for part in parts {
+                    self.expression(part)?;
+                }
+                self.chunk.emit(OpCode::Interpolate(parts.len()), ex.span);
+            }
+            ExprKind::Is(inner, type_name) => {
+                self.expression(inner)?;
+                self.chunk.emit(OpCode::Is(*type_name), ex.span);
+            }
+            ExprKind::If(condition, ex_then, ex_else)
+            | ExprKind::Ternary(condition, ex_then, ex_else) => {
+                self.expression(condition)?;
+                let then_jump = self.chunk.emit(OpCode::JumpIfFalse(0), ex.span);
+                self.chunk.emit(OpCode::Pop, ex.span);
+                self.expression(ex_then)?;
+                let else_jump = self.chunk.emit(OpCode::Jump(0), ex.span);
+                self.chunk.patch(then_jump, self.chunk.len());
+                self.chunk.emit(OpCode::Pop, ex.span);
+                self.expression(ex_else)?;
+                self.chunk.patch(else_jump, self.chunk.len());
+            }
+            ExprKind::Literal(lit) => {
+                self.chunk.emit(OpCode::Constant(*lit), ex.span);
+            }
+            ExprKind::Logical(left, op, right) => {
+                self.expression(left)?;
+                let jump = match op {
+                    LogicOp::And => OpCode::JumpIfFalse(0),
+                    LogicOp::Or => OpCode::JumpIfTrue(0),
+                };
+                let end_jump = self.chunk.emit(jump, ex.span);
+                self.chunk.emit(OpCode::Pop, ex.span);
+                self.expression(right)?;
+                self.chunk.patch(end_jump, self.chunk.len());
+            }
+            ExprKind::Unary(op, right) => {
+                self.expression(right)?;
+                self.chunk.emit(OpCode::Unary(*op), ex.span);
+            }
+            ExprKind::Variable(id) => {
+                match self.resolve_local(id) {
+                    Some(slot) => self.chunk.emit(OpCode::GetLocal(slot), ex.span),
+                    None => self.chunk.emit(OpCode::GetGlobal(id.symbol), ex.span),
+                };
+            }
+            ExprKind::Call(_, span, _) => return Err(Self::unsupported("Function calls", *span)),
+            ExprKind::Get(..) => return Err(Self::unsupported("Property accesses", ex.span)),
+            ExprKind::Lambda(..) => return Err(Self::unsupported("Functions", ex.span)),
+            ExprKind::Record(..) => return Err(Self::unsupported("Records", ex.span)),
+            ExprKind::Array(..) => return Err(Self::unsupported("Arrays", ex.span)),
+            ExprKind::Map(..) => return Err(Self::unsupported("Maps", ex.span)),
+            ExprKind::Index(..) | ExprKind::SetIndex(..) => {
+                return Err(Self::unsupported("Index expressions", ex.span))
+            }
+            ExprKind::Tuple(..) => return Err(Self::unsupported("Tuples", ex.span)),
+            ExprKind::Set(..) => return Err(Self::unsupported("Property assignments", ex.span)),
+            ExprKind::Super(..) | ExprKind::This(_) => {
+                return Err(Self::unsupported("Classes", ex.span))
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_local(&self, id: &Ident) -> Option<usize> {
+        self.locals
+            .iter()
+            .rposition(|(symbol, _)| *symbol == id.symbol)
+    }
+
+    fn begin_scope(&mut self) {
+        self.scope_depth += 1;
+    }
+
+    fn end_scope(&mut self, span: Span) {
+        let count = self
+            .locals
+            .iter()
+            .rev()
+            .take_while(|(_, depth)| *depth == self.scope_depth)
+            .count();
+        if count > 0 {
+            self.locals.truncate(self.locals.len() - count);
+            self.chunk.emit(OpCode::PopLocals(count), span);
+        }
+        self.scope_depth -= 1;
+    }
+
+    fn unsupported(feature: &str, span: Span) -> SpannedError {
+        (
+            span,
+            format!("{} are not supported by the bytecode compiler.", feature),
+        )
+            .into()
+    }
+}
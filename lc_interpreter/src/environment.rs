@@ -1,25 +1,37 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
 use crate::*;
 use lc_core::*;
 
+/// The environments code runs in. The innermost is on top, and each one reaches the globals
+/// through its chain of enclosing scopes. Calling a function pushes an environment enclosing
+/// the function's closure rather than the caller's, and pops it on return.
 #[derive(Clone, Debug)]
 pub struct EnvironmentStack {
     stack: Vec<Environment>,
+    scopes_created: usize,
 }
 impl EnvironmentStack {
     pub fn new(globals: Environment) -> Self {
         Self {
             stack: vec![globals],
+            scopes_created: 0,
         }
     }
 
+    /// The number of scopes pushed over the lifetime of the stack.
+    pub fn scopes_created(&self) -> usize {
+        self.scopes_created
+    }
+
+    /// The innermost environment, shared rather than copied, so closures see later changes.
     pub fn top(&self) -> Environment {
         self.stack.last().unwrap().clone()
     }
 
     pub fn begin_scope(&mut self, environment: Environment) {
         self.stack.push(environment);
+        self.scopes_created += 1;
     }
 
     pub fn end_scope(&mut self) {
@@ -27,36 +39,54 @@ impl EnvironmentStack {
     }
 
     pub fn define(&mut self, name: &Ident, value: Value) {
-        self.stack.last_mut().unwrap().define(name.symbol, value);
+        self.stack.last().unwrap().define(name.symbol, value);
     }
 
+    /// Looks `name` up through every scope enclosing the innermost one.
     pub fn get(&self, name: &Ident) -> Result<Value, SpannedError> {
-        for env in self.stack.iter().rev() {
-            if let Ok(value) = env.get(name) {
-                return Ok(value);
-            }
+        match self.top().find(name) {
+            Some(environment) => environment.get(name),
+            None => Err(self.undefined(name)),
         }
-        Err((name.span, format!("Undefined variable '{}'", name.symbol)).into())
     }
 
+    /// An "Undefined variable" error suggesting the closest name bound in any enclosing scope.
+    pub fn undefined(&self, name: &Ident) -> SpannedError {
+        let names: Vec<String> = self
+            .top()
+            .chain()
+            .flat_map(|env| env.bindings().into_iter())
+            .map(|(symbol, _)| symbol.to_string())
+            .collect();
+        let message =
+            undefined_variable(&name.symbol.to_string(), names.iter().map(String::as_str));
+        (name.span, message).into()
+    }
+
+    /// Whether `name` is bound in any scope enclosing the innermost one.
+    pub fn is_defined(&self, name: &Ident) -> bool {
+        self.top().find(name).is_some()
+    }
+
+    /// Looks `name` up exactly `depth` scopes out from the innermost one, as the resolver
+    /// measured it.
     pub fn get_at(&self, name: &Ident, depth: usize) -> Result<Value, SpannedError> {
-        self.stack
-            .get(self.stack.len() - 1 - depth)
-            .unwrap()
-            .get(name)
+        self.top().ancestor(depth).get(name)
+    }
+
+    pub fn globals(&self) -> &Environment {
+        self.stack.first().unwrap()
     }
 
     pub fn global_get(&self, name: &Ident) -> Result<Value, SpannedError> {
-        self.stack.first().unwrap().get(name)
+        self.globals().get(name).map_err(|_| self.undefined(name))
     }
 
     pub fn assign(&mut self, name: &Ident, value: Value) -> Result<(), SpannedError> {
-        for env in self.stack.iter_mut().rev() {
-            if env.contains(name) && env.assign(name, value.to_owned()).is_ok() {
-                return Ok(());
-            }
+        match self.top().find(name) {
+            Some(environment) => environment.assign(name, value),
+            None => Err(self.undefined(name)),
         }
-        Err((name.span, format!("Undefined variable '{}'", name.symbol)).into())
     }
 
     pub fn assign_at(
@@ -65,31 +95,59 @@ impl EnvironmentStack {
         value: Value,
         depth: usize,
     ) -> Result<(), SpannedError> {
-        let index = self.stack.len() - 1 - depth;
-        self.stack.get_mut(index).unwrap().assign(name, value)
+        self.top().ancestor(depth).assign(name, value)
     }
 
     pub fn global_assign(&mut self, name: &Ident, value: Value) -> Result<(), SpannedError> {
-        self.stack.first_mut().unwrap().assign(name, value)
+        if !self.globals().contains(name) {
+            return Err(self.undefined(name));
+        }
+        self.globals().assign(name, value)
     }
 }
 
-#[derive(Clone, Default, Debug)]
-pub struct Environment {
+#[derive(Default)]
+struct Scope {
     values: HashMap<Symbol, Value>,
+    enclosing: Option<Environment>,
+}
+
+/// A scope of bindings, shared by reference: every clone of an environment sees the same
+/// values, so a closure observes assignments made after it was created.
+#[derive(Clone, Default)]
+pub struct Environment {
+    scope: Rc<RefCell<Scope>>,
+}
+impl fmt::Debug for Environment {
+    /// Only the names, since a function bound here holds this environment as its closure.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.scope.borrow().values.keys())
+            .finish()
+    }
 }
 impl Environment {
+    /// A scope enclosed by nothing, for the globals.
     pub fn new() -> Self {
-        Self {
+        Self::default()
+    }
+
+    /// A new, empty scope nested inside `enclosing`.
+    pub fn enclosed(enclosing: &Environment) -> Self {
+        let scope = Scope {
             values: HashMap::new(),
+            enclosing: Some(enclosing.clone()),
+        };
+        Self {
+            scope: Rc::new(RefCell::new(scope)),
         }
     }
 
-    pub fn define(&mut self, name: Symbol, value: Value) {
-        self.values.insert(name, value);
+    pub fn define(&self, name: Symbol, value: Value) {
+        self.scope.borrow_mut().values.insert(name, value);
     }
 
-    pub fn define_builtin<T>(&mut self, name: &str)
+    pub fn define_builtin<T>(&self, name: &str)
     where
         T: for<'b> Callable<'b> + Default + 'static,
     {
@@ -99,17 +157,19 @@ impl Environment {
         );
     }
 
+    /// Looks `name` up in this scope only.
     pub fn get(&self, name: &Ident) -> Result<Value, SpannedError> {
-        if let Some(value) = self.values.get(&name.symbol) {
+        if let Some(value) = self.scope.borrow().values.get(&name.symbol) {
             Ok(value.clone())
         } else {
             Err((name.span, format!("Undefined variable '{}'", name.symbol)).into())
         }
     }
 
-    pub fn assign(&mut self, name: &Ident, value: Value) -> Result<(), SpannedError> {
-        if self.values.contains_key(&name.symbol) {
-            self.values.insert(name.symbol, value);
+    /// Assigns to `name` in this scope only.
+    pub fn assign(&self, name: &Ident, value: Value) -> Result<(), SpannedError> {
+        if let Some(slot) = self.scope.borrow_mut().values.get_mut(&name.symbol) {
+            *slot = value;
             Ok(())
         } else {
             Err((name.span, format!("Undefined variable '{}'", name.symbol)).into())
@@ -117,6 +177,48 @@ impl Environment {
     }
 
     pub fn contains(&self, name: &Ident) -> bool {
-        self.values.contains_key(&name.symbol)
+        self.scope.borrow().values.contains_key(&name.symbol)
+    }
+
+    /// The bindings of this scope only, in no particular order.
+    pub fn bindings(&self) -> Vec<(Symbol, Value)> {
+        let scope = self.scope.borrow();
+        scope
+            .values
+            .iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect()
+    }
+
+    pub fn enclosing(&self) -> Option<Environment> {
+        self.scope.borrow().enclosing.clone()
+    }
+
+    /// This scope, then each enclosing one out to the globals.
+    fn chain(&self) -> impl Iterator<Item = Environment> {
+        std::iter::successors(Some(self.clone()), Environment::enclosing)
+    }
+
+    /// The scope `depth` steps out from this one.
+    fn ancestor(&self, depth: usize) -> Environment {
+        self.chain()
+            .nth(depth)
+            .expect("resolved depth within the scope chain")
+    }
+
+    /// The innermost scope binding `name`.
+    fn find(&self, name: &Ident) -> Option<Environment> {
+        self.chain().find(|env| env.contains(name))
+    }
+}
+
+/// "Undefined variable 'x'", with a "Did you mean" hint when one of `candidates` is close.
+pub fn undefined_variable<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    match closest_match(name, candidates) {
+        Some(suggestion) => format!(
+            "Undefined variable '{}'. Did you mean '{}'?",
+            name, suggestion
+        ),
+        None => format!("Undefined variable '{}'", name),
     }
 }
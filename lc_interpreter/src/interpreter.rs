@@ -1,16 +1,66 @@
 use core::fmt;
-use std::{collections::HashMap, io};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    io::{self, BufRead},
+    panic,
+    rc::Rc,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use indexmap::IndexMap;
 
 use crate::*;
 use lc_core::*;
 
+/// How deeply calls may nest unless [`Interpreter::set_max_depth`] says otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// A native stack that [`DEFAULT_MAX_DEPTH`] nested calls fit in with room to spare, even in
+/// unoptimized builds, where each call takes around 20 KiB.
+pub const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Runs `f` on a thread with a [`STACK_SIZE`] stack. Hosts run the interpreter this way so that
+/// deep recursion reaches the call depth limit and raises an error, where a main thread's stack
+/// could overflow first and abort the process.
+pub fn with_interpreter_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    thread::scope(|scope| {
+        let thread = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("failed to spawn the interpreter thread");
+        match thread.join() {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    })
+}
+
 type ExprResult = Result<Value, Throw>;
+/// A function with the call site and arguments it is about to be called with
+type PendingCall = (Box<dyn for<'b> Callable<'b>>, Span, Vec<Value>);
 type StmtResult = Result<(), Throw>;
 
 pub struct Interpreter<'a> {
     pub environment: EnvironmentStack,
-    locals: HashMap<Expr, usize>,
+    /// Scope depths of resolved local variables, keyed by [`Expr::id`]
+    locals: HashMap<usize, usize>,
+    /// Top-level names declared with `const`, kept so that later inputs resolved against the
+    /// same globals can't assign to them either
+    global_constants: HashSet<Symbol>,
     output: &'a mut dyn io::Write,
+    /// Where `input()` reads lines from, stdin unless one is given
+    input: Option<&'a mut dyn io::BufRead>,
+    /// Called with each statement's span just before it executes
+    trace: Option<Box<dyn FnMut(Span)>>,
+    /// State of the generator behind `random()`
+    seed: u64,
+    /// Calls currently being evaluated, innermost last, for backtraces and the depth limit
+    call_stack: Vec<Frame>,
+    /// Deepest the calls may nest before raising a stack overflow error
+    max_depth: usize,
 }
 impl<'a> fmt::Debug for Interpreter<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -21,54 +71,211 @@ impl<'a> fmt::Debug for Interpreter<'a> {
     }
 }
 impl<'a> Interpreter<'a> {
+    /// An interpreter whose `random()` is seeded from the clock.
     pub fn new(output: &'a mut dyn io::Write) -> Self {
-        let mut globals = Environment::new();
-        define_builtins(&mut globals);
-        let environment = EnvironmentStack::new(globals);
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self::with_seed(output, seed)
+    }
+
+    /// An interpreter whose `random()` starts from `seed`. Two interpreters given the same seed
+    /// produce the same sequence of random numbers.
+    pub fn with_seed(output: &'a mut dyn io::Write, seed: u64) -> Self {
         Self {
-            environment,
+            environment: Self::initial_environment(),
             locals: HashMap::new(),
+            global_constants: HashSet::new(),
             output,
+            input: None,
+            trace: None,
+            seed,
+            call_stack: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
         }
     }
 
+    /// The next number in `[0, 1)` from the interpreter's generator, using splitmix64.
+    pub fn random(&mut self) -> f64 {
+        self.seed = self.seed.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        // The top 53 bits fill an f64's mantissa exactly
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Makes `input()` read from `input` instead of stdin.
+    pub fn set_input(&mut self, input: &'a mut dyn io::BufRead) {
+        self.input = Some(input);
+    }
+
+    /// Writes `prompt` to the output, then reads one line of input without its line ending.
+    /// Returns `None` at the end of the input.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{}", prompt)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line)?,
+            None => io::stdin().lock().read_line(&mut line)?,
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line).to_string()))
+    }
+
+    /// Writes `text` to the output as it is, flushing so that a partial line shows up at once.
+    pub fn write(&mut self, text: &str) -> io::Result<()> {
+        write!(self.output, "{}", text)?;
+        self.output.flush()
+    }
+
+    /// Installs a callback run before every statement with its [`Stmt::span`], for hosts
+    /// implementing tracing or breakpoints.
+    pub fn set_trace(&mut self, trace: Box<dyn FnMut(Span)>) {
+        self.trace = Some(trace);
+    }
+
+    /// Sets how deeply calls may nest, [`DEFAULT_MAX_DEPTH`] unless changed. Going deeper raises
+    /// a stack overflow error at the call rather than overflowing the native stack.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Discards every definition made so far, leaving only the builtins.
+    pub fn reset(&mut self) {
+        self.environment = Self::initial_environment();
+        self.locals.clear();
+        self.global_constants.clear();
+    }
+
+    fn initial_environment() -> EnvironmentStack {
+        let mut globals = Environment::new();
+        define_builtins(&mut globals);
+        EnvironmentStack::new(globals)
+    }
+
+    /// Scans, parses, resolves and runs `source`, returning the value of its final statement
+    /// as [`Interpreter::interpret_value`] does. Translation errors come back as
+    /// [`TranslationErrors`] and failures while running as a [`RuntimeError`].
+    pub fn eval(&mut self, source: &str) -> anyhow::Result<Value> {
+        let mut issues = TranslationErrors::new();
+        let (tokens, mut errs) = Scanner::new(source.to_string()).scan_tokens();
+        issues.merge(&mut errs);
+        let (statements, mut errs) = Parser::new(tokens).parse();
+        issues.merge(&mut errs);
+        let (_, mut errs) = Resolver::new(self).resolve(&statements);
+        issues.merge(&mut errs);
+        issues.check()?;
+        Ok(self.interpret_value(statements)?)
+    }
+
     pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
-        for statement in &statements {
-            if let Err(e) = self.execute(statement) {
-                if let Throw::Error(e) = e {
-                    return Err(e.into());
+        self.interpret_value(statements).map(|_| ())
+    }
+
+    /// Like [`Interpreter::interpret`], but returns the value of the final statement if it is
+    /// an expression statement, and `null` otherwise.
+    pub fn interpret_value(&mut self, statements: Vec<Stmt>) -> Result<Value, RuntimeError> {
+        let Some((last, rest)) = statements.split_last() else {
+            return Ok(Literal::Null.into());
+        };
+        let result = rest
+            .iter()
+            .try_for_each(|statement| self.execute(statement))
+            .and_then(|_| match last {
+                Stmt::Expression(ex) => {
+                    self.trace(last);
+                    self.evaluate(ex)
                 }
-                break;
+                _ => self.execute(last).map(|_| Literal::Null.into()),
+            });
+        match result {
+            Ok(value) => Ok(value),
+            Err(Throw::Error(e)) => Err(e.into()),
+            Err(Throw::Return(_) | Throw::Break | Throw::Continue | Throw::TailCall(..)) => {
+                Ok(Literal::Null.into())
             }
         }
-        Ok(())
+    }
+
+    /// Calls a script or native function, as the script `callee(arguments)` would. This is how
+    /// builtins and hosts call back into script code.
+    pub fn call_value(
+        &mut self,
+        callee: &Value,
+        arguments: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        let Value::Function(func) = callee else {
+            return Err(RuntimeError::new(format!(
+                "Can only call functions, not '{}'.",
+                callee.as_str()
+            )));
+        };
+        match self.call_function(func.clone(), Span::default(), arguments.to_vec()) {
+            Ok(value) => Ok(value),
+            Err(Throw::Error(err)) => Err(err.into()),
+            Err(_) => Ok(Literal::Null.into()),
+        }
+    }
+
+    /// Calls a function value for a builtin such as `map`. Errors about the call itself, like
+    /// a wrong number of arguments, point at `span`, the builtin's own call site.
+    pub(crate) fn call_back(
+        &mut self,
+        callee: &Value,
+        span: Span,
+        arguments: Vec<Value>,
+    ) -> ExprResult {
+        let Value::Function(func) = callee else {
+            return Err((span, "Not a valid function call.").into());
+        };
+        self.call_function(func.clone(), span, arguments)
     }
 
     fn execute(&mut self, stmt: &Stmt) -> StmtResult {
         self.visit_stmt(stmt)
     }
 
+    fn trace(&mut self, stmt: &Stmt) {
+        if let Some(trace) = &mut self.trace {
+            trace(stmt.span());
+        }
+    }
+
     fn visit_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+        self.trace(stmt);
         match stmt {
             Stmt::Block(statements) => self.visit_block_stmt(statements),
-            Stmt::Class(id, methods) => self.visit_class_stmt(id, methods),
+            Stmt::Break(_) => Err(Throw::Break),
+            Stmt::Continue(_) => Err(Throw::Continue),
+            Stmt::Class(id, superclass, methods) => self.visit_class_stmt(id, superclass, methods),
+            Stmt::Enum(id, variants) => self.visit_enum_stmt(id, variants),
             Stmt::Expression(ex) => self.visit_expr_stmt(ex),
             Stmt::Function(name, params, body) => self.visit_fn_stmt(name, params, body),
             Stmt::If(condition, st_then, st_else) => {
                 self.visit_if_stmt(condition, st_then, st_else)
             }
-            Stmt::Print(ex) => self.visit_print_stmt(ex),
+            Stmt::Print(expressions) => self.visit_print_stmt(expressions),
             Stmt::Return(ex) => self.visit_return_stmt(ex),
-            Stmt::Let(id, initializer) => self.visit_let_stmt(id, initializer),
-            Stmt::While(condition, body) => self.visit_while_stmt(condition, body),
+            Stmt::Const(id, initializer) | Stmt::Let(id, initializer) => {
+                self.visit_let_stmt(id, initializer)
+            }
+            Stmt::LetTuple(ids, initializer) => self.visit_let_tuple_stmt(ids, initializer),
+            Stmt::Struct(id, fields) => self.visit_struct_stmt(id, fields),
+            Stmt::While(condition, body, increment) => {
+                self.visit_while_stmt(condition, body, increment)
+            }
+            Stmt::DoWhile(body, condition) => self.visit_do_while_stmt(body, condition),
+            Stmt::ForEach(id, iterable, body) => self.visit_foreach_stmt(id, iterable, body),
         }
     }
 
-    pub fn execute_block(
-        &mut self,
-        statements: &Vec<Stmt>,
-        environment: &Environment,
-    ) -> StmtResult {
+    pub fn execute_block(&mut self, statements: &[Stmt], environment: &Environment) -> StmtResult {
         self.environment.begin_scope(environment.to_owned());
         for statement in statements {
             if let Err(e) = self.execute(statement) {
@@ -80,13 +287,58 @@ impl<'a> Interpreter<'a> {
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> StmtResult {
-        self.execute_block(statements, &Environment::new())
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> StmtResult {
+        if !Stmt::declares_bindings(statements) {
+            return statements
+                .iter()
+                .try_for_each(|statement| self.execute(statement));
+        }
+        self.execute_block(statements, &Environment::enclosed(&self.environment.top()))
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        id: &Ident,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+    ) -> StmtResult {
+        let superclass = match superclass {
+            Some(superclass) => match self.evaluate(superclass)? {
+                Value::Function(class) if class.as_class().is_some() => class.as_class().cloned(),
+                value => {
+                    let message = format!("Superclass must be a class, not {}.", value.type_name());
+                    return Err((superclass.span, message).into());
+                }
+            },
+            None => None,
+        };
+        let mut closure = self.environment.top();
+        // Methods of a subclass close over a scope binding `super`
+        if let Some(superclass) = &superclass {
+            closure = Environment::enclosed(&closure);
+            closure.define(
+                Symbol::ident_str(SUPER),
+                Value::Function(Box::new(superclass.to_owned())),
+            );
+        }
+        let methods = methods.iter().filter_map(|method| match method {
+            Stmt::Function(name, params, body) => {
+                Some((name.symbol, Function::new(name, params, body, &closure)))
+            }
+            _ => None,
+        });
+        let class = LcClass::new(id.symbol, superclass, methods);
+        self.environment
+            .define(id, Value::Function(Box::new(class)));
+        Ok(())
     }
 
-    fn visit_class_stmt(&mut self, id: &Ident, _methods: &Vec<Stmt>) -> StmtResult {
-        self.environment.define(id, Value::Literal(Literal::Null));
-        //let class = Stmt::Class((), ())
+    fn visit_enum_stmt(&mut self, id: &Ident, variants: &[Ident]) -> StmtResult {
+        let value = Value::Enum {
+            name: id.symbol,
+            variants: variants.iter().map(|variant| variant.symbol).collect(),
+        };
+        self.environment.define(id, value);
         Ok(())
     }
 
@@ -97,7 +349,12 @@ impl<'a> Interpreter<'a> {
         }
     }
 
-    fn visit_fn_stmt(&mut self, name: &Ident, params: &Vec<Ident>, body: &Vec<Stmt>) -> StmtResult {
+    fn visit_fn_stmt(
+        &mut self,
+        name: &Ident,
+        params: &Rc<[Ident]>,
+        body: &Rc<[Stmt]>,
+    ) -> StmtResult {
         let function = Function::new(name, params, body, &self.environment.top());
         self.environment.define(name, function.into());
         Ok(())
@@ -117,17 +374,22 @@ impl<'a> Interpreter<'a> {
         Ok(())
     }
 
-    fn visit_print_stmt(&mut self, ex: &Expr) -> StmtResult {
-        match self.evaluate(ex) {
-            Ok(lit) => {
-                writeln!(self.output, "{}", lit.as_str()).unwrap();
-                Ok(())
-            }
-            Err(err) => Err(err),
-        }
+    fn visit_print_stmt(&mut self, expressions: &[Expr]) -> StmtResult {
+        let values = expressions
+            .iter()
+            .map(|ex| self.evaluate(ex).map(|value| value.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+        writeln!(self.output, "{}", values.join(" ")).unwrap();
+        Ok(())
     }
 
     fn visit_return_stmt(&mut self, ex: &Expr) -> StmtResult {
+        // A call in tail position is left for the caller to make once this function has
+        // returned, so that recursion through tail calls runs in constant stack
+        if let ExprKind::Call(callee, span, args) = &ex.kind {
+            let (func, call_span, arguments) = self.evaluate_call(callee, span, args)?;
+            return Err(Throw::TailCall(func, call_span, arguments));
+        }
         let value = self.evaluate(ex)?;
         Err(value.into())
     }
@@ -138,9 +400,101 @@ impl<'a> Interpreter<'a> {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> StmtResult {
+    fn visit_let_tuple_stmt(&mut self, ids: &[Ident], initializer: &Expr) -> StmtResult {
+        let Value::Tuple(elements) = self.evaluate(initializer)? else {
+            return Err((initializer.span, "Can only destructure a tuple.").into());
+        };
+        if elements.len() != ids.len() {
+            return Err((
+                initializer.span,
+                format!(
+                    "Expected a tuple of {} elements but was given {}.",
+                    ids.len(),
+                    elements.len()
+                ),
+            )
+                .into());
+        }
+        for (id, value) in ids.iter().zip(elements) {
+            self.environment.define(id, value);
+        }
+        Ok(())
+    }
+
+    fn visit_struct_stmt(&mut self, id: &Ident, fields: &[Ident]) -> StmtResult {
+        let value = Value::Struct {
+            name: id.symbol,
+            fields: fields.iter().map(|field| field.symbol).collect(),
+        };
+        self.environment.define(id, value);
+        Ok(())
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> StmtResult {
         while self.evaluate(condition)?.is_truthy() {
-            self.execute(body)?;
+            match self.execute(body) {
+                Ok(()) | Err(Throw::Continue) => (),
+                Err(Throw::Break) => break,
+                Err(throw) => return Err(throw),
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_do_while_stmt(&mut self, body: &Stmt, condition: &Expr) -> StmtResult {
+        loop {
+            match self.execute(body) {
+                Ok(()) | Err(Throw::Continue) => (),
+                Err(Throw::Break) => break,
+                Err(throw) => return Err(throw),
+            }
+            if !self.evaluate(condition)?.is_truthy() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loops over a snapshot of the array, so changing the array in the body doesn't change
+    /// which elements are visited. Each iteration binds the variable in a scope of its own.
+    fn visit_foreach_stmt(&mut self, id: &Ident, iterable: &Expr, body: &Stmt) -> StmtResult {
+        let elements: Vec<Value> = match self.evaluate(iterable)? {
+            Value::Array(elements) => elements.borrow().clone(),
+            Value::Literal(Literal::String(string)) => string
+                .resolve()
+                .chars()
+                .map(|c| Literal::String(Symbol::string(c.to_string())).into())
+                .collect(),
+            value => {
+                return Err((
+                    iterable.span,
+                    format!(
+                        "Can only loop over arrays and strings, not '{}'.",
+                        value.as_str()
+                    ),
+                )
+                    .into())
+            }
+        };
+        for element in elements {
+            let environment = Environment::enclosed(&self.environment.top());
+            environment.define(id.symbol, element);
+            self.environment.begin_scope(environment);
+            let result = self.execute(body);
+            self.environment.end_scope();
+            match result {
+                Ok(()) | Err(Throw::Continue) => (),
+                Err(Throw::Break) => break,
+                Err(throw) => return Err(throw),
+            }
         }
         Ok(())
     }
@@ -151,20 +505,63 @@ impl<'a> Interpreter<'a> {
 
     fn visit_expr(&mut self, expr: &Expr) -> ExprResult {
         match &expr.kind {
+            ExprKind::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<Result<_, _>>()?;
+                Ok(Value::Array(Rc::new(RefCell::new(elements))))
+            }
             ExprKind::Assign(id, right) => self.visit_assign_expr(expr, id, right),
             ExprKind::Binary(left, op, right) => self.visit_binary_expr(left, op, right),
+            ExprKind::Block(statements, value) => self.visit_block_expr(statements, value),
             ExprKind::Call(callee, span, args) => self.visit_call_expr(callee, span, args),
+            ExprKind::Get(object, property) => self.visit_get_expr(object, property),
             ExprKind::Grouping(ex) => self.evaluate(ex),
+            ExprKind::Index(object, index) => self.visit_index_expr(object, index),
+            ExprKind::Interpolation(parts) => self.visit_interpolation_expr(parts),
+            ExprKind::Map(entries) => {
+                // String keys hash by their interned id, not the shared table it points into
+                #[allow(clippy::mutable_key_type)]
+                let mut map = IndexMap::new();
+                for (key, value) in entries {
+                    let key_value = self.evaluate(key)?;
+                    map.insert(Self::map_key(key_value, key.span)?, self.evaluate(value)?);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            ExprKind::Is(ex, type_name) => {
+                Ok(Literal::Bool(self.evaluate(ex)?.is_type(*type_name)).into())
+            }
+            ExprKind::If(condition, ex_then, ex_else)
+            | ExprKind::Ternary(condition, ex_then, ex_else) => {
+                self.visit_if_expr(condition, ex_then, ex_else)
+            }
+            ExprKind::Lambda(name, params, body) => {
+                self.visit_lambda_expr(expr, name, params, body)
+            }
             ExprKind::Literal(lit) => Ok(lit.to_owned().into()),
             ExprKind::Logical(left, op, right) => self.visit_logical_expr(left, op, right),
+            ExprKind::Record(name, fields) => self.visit_record_expr(expr, name, fields),
+            ExprKind::Set(object, property, value) => self.visit_set_expr(object, property, value),
+            ExprKind::SetIndex(object, index, value) => {
+                self.visit_set_index_expr(object, index, value)
+            }
+            ExprKind::Tuple(elements) => elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect::<Result<_, _>>()
+                .map(Value::Tuple),
             ExprKind::Unary(op, right) => self.visit_unary_expr(expr, op, right),
+            ExprKind::Super(keyword, method) => self.visit_super_expr(expr, keyword, method),
+            ExprKind::This(keyword) => self.look_up_variable(expr, keyword),
             ExprKind::Variable(id) => self.visit_var_expr(expr, id),
         }
     }
 
     fn visit_assign_expr(&mut self, ex: &Expr, id: &Ident, right: &Expr) -> ExprResult {
         let value = self.evaluate(right)?;
-        if let Some(distance) = self.locals.get(ex) {
+        if let Some(distance) = self.locals.get(&ex.id()) {
             self.environment
                 .assign_at(id, value.to_owned(), *distance)?;
         } else {
@@ -174,15 +571,23 @@ impl<'a> Interpreter<'a> {
     }
 
     fn visit_binary_expr(&mut self, left: &Expr, op: &BinaryOp, right: &Expr) -> ExprResult {
-        let span = left.span.to(right.span);
-        let Value::Literal(left) = self.evaluate(left)? else {
+        let (left_span, right_span) = (left.span, right.span);
+        let span = left_span.to(right_span);
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        match op {
+            BinaryOp::Equal => return Ok(Literal::Bool(left == right).into()),
+            BinaryOp::NotEqual => return Ok(Literal::Bool(left != right).into()),
+            _ => (),
+        }
+        let Value::Literal(left) = left else {
             return Err((
                 span,
                 "Operands must be two numbers or two strings. Did you forget to call the function?",
             )
                 .into());
         };
-        let Value::Literal(right) = self.evaluate(right)? else {
+        let Value::Literal(right) = right else {
             return Err((
                 span,
                 "Operands must be two numbers or two strings. Did you forget to call the function?",
@@ -190,82 +595,213 @@ impl<'a> Interpreter<'a> {
                 .into());
         };
         match op {
-            BinaryOp::Minus => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Number(left - right).into())
-            }
-            BinaryOp::Divide => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Number(left / right).into())
-            }
-            BinaryOp::Multiply => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Number(left * right).into())
+            BinaryOp::Minus
+            | BinaryOp::Divide
+            | BinaryOp::FloorDivide
+            | BinaryOp::Modulo
+            | BinaryOp::Multiply
+            | BinaryOp::Power
+            | BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::ShiftLeft
+            | BinaryOp::ShiftRight => {
+                let operands = self.get_number_ops(&left, left_span, &right, right_span)?;
+                match operands.apply(*op) {
+                    Ok(result) => Ok(result.into()),
+                    Err(message) => Err((span, message).into()),
+                }
             }
             BinaryOp::Plus => match left {
-                Literal::Number(_) => {
-                    let (left, right) = self.get_number_ops(&left, span, &right)?;
-                    Ok(Literal::Number(left + right).into())
+                Literal::Int(_) | Literal::Number(_) => {
+                    let operands = self.get_number_ops(&left, left_span, &right, right_span)?;
+                    match operands.apply(*op) {
+                        Ok(result) => Ok(result.into()),
+                        Err(message) => Err((span, message).into()),
+                    }
                 }
+                // A string on the left turns any literal on the right into text
                 Literal::String(str) => {
-                    let Literal::String(right) = right else {
-                        return Err((span, "Cannot concatenate non-string value.").into());
-                    };
-                    Ok(Literal::String(str + right).into())
+                    Ok(Literal::String(str + Symbol::string(right.as_str())).into())
                 }
                 _ => Err((span, "Operands must be two numbers or two strings.").into()),
             },
             BinaryOp::Greater => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Bool(left > right).into())
+                let ordering = self.compare_ops(&left, left_span, &right, right_span, span)?;
+                Ok(Literal::Bool(matches!(ordering, Some(Ordering::Greater))).into())
             }
             BinaryOp::GreaterEqual => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Bool(left >= right).into())
+                let ordering = self.compare_ops(&left, left_span, &right, right_span, span)?;
+                Ok(Literal::Bool(matches!(
+                    ordering,
+                    Some(Ordering::Greater | Ordering::Equal)
+                ))
+                .into())
             }
             BinaryOp::Less => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Bool(left < right).into())
+                let ordering = self.compare_ops(&left, left_span, &right, right_span, span)?;
+                Ok(Literal::Bool(matches!(ordering, Some(Ordering::Less))).into())
             }
             BinaryOp::LessEqual => {
-                let (left, right) = self.get_number_ops(&left, span, &right)?;
-                Ok(Literal::Bool(left <= right).into())
+                let ordering = self.compare_ops(&left, left_span, &right, right_span, span)?;
+                Ok(
+                    Literal::Bool(matches!(ordering, Some(Ordering::Less | Ordering::Equal)))
+                        .into(),
+                )
             }
-            BinaryOp::NotEqual => Ok(Literal::Bool(left != right).into()),
-            BinaryOp::Equal => Ok(Literal::Bool(left == right).into()),
+            BinaryOp::Equal | BinaryOp::NotEqual => unreachable!(),
         }
     }
 
-    fn visit_call_expr(&mut self, callee: &Expr, span: &Span, args: &Vec<Expr>) -> ExprResult {
-        let ExprKind::Variable(identifier) = &callee.kind else {
-            return Err((*span, "Not a valid function call.").into());
-        };
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: &Option<Box<Expr>>) -> ExprResult {
+        let scoped = Stmt::declares_bindings(statements);
+        if scoped {
+            self.environment
+                .begin_scope(Environment::enclosed(&self.environment.top()));
+        }
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement))
+            .and_then(|_| match value {
+                Some(value) => self.evaluate(value),
+                None => Ok(Literal::Null.into()),
+            });
+        if scoped {
+            self.environment.end_scope();
+        }
+        result
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, span: &Span, args: &[Expr]) -> ExprResult {
+        let (func, call_span, arguments) = self.evaluate_call(callee, span, args)?;
+        self.call_function(func, call_span, arguments)
+    }
+
+    /// Evaluates the function and arguments of a call without making it.
+    fn evaluate_call(
+        &mut self,
+        callee: &Expr,
+        span: &Span,
+        args: &[Expr],
+    ) -> Result<PendingCall, Throw> {
+        let value = self.evaluate(callee)?;
         let mut arguments = Vec::new();
         for arg in args {
             arguments.push(self.evaluate(arg)?);
         }
-        let value = self.environment.get(identifier)?;
-        match value {
-            Value::Literal(_) => Err((identifier.span, "Not a valid function call.").into()),
-            Value::Function(mut func) => match func.call(self, &arguments) {
-                Throw::Return(value) => Ok(value),
-                Throw::Error(err) => Err(err.into()), // only keep propagating up call stack if it was an *actual* error
-            },
+        let call_span = callee.span.to(*span);
+        let Value::Function(func) = value else {
+            return Err((call_span, "Not a valid function call.").into());
+        };
+        Ok((func, call_span, arguments))
+    }
+
+    /// Calls `func`, then in turn each tail call it returns, all from this one frame. Tail
+    /// calls replace the frame rather than adding to the call depth. An error leaving the call
+    /// carries a backtrace from the point it was raised.
+    fn call_function(
+        &mut self,
+        mut func: Box<dyn for<'b> Callable<'b>>,
+        mut span: Span,
+        mut arguments: Vec<Value>,
+    ) -> ExprResult {
+        if self.call_stack.len() >= self.max_depth {
+            let message = format!(
+                "stack overflow: maximum call depth {} exceeded",
+                self.max_depth
+            );
+            return Err((span, message).into());
+        }
+        self.call_stack.push(Frame::new(func.as_str(), span));
+        let mut result = loop {
+            match func.call(self, span, &arguments) {
+                Throw::TailCall(next, next_span, next_arguments) => {
+                    (func, span, arguments) = (next, next_span, next_arguments);
+                    *self.call_stack.last_mut().unwrap() = Frame::new(func.as_str(), span);
+                }
+                result => break result,
+            }
+        };
+        if let Throw::Error(err) = &mut result {
+            self.record_backtrace(err);
+        }
+        self.call_stack.pop();
+        match result {
+            Throw::Return(value) => Ok(value),
+            Throw::Error(err) => Err(err.into()), // only keep propagating up call stack if it was an *actual* error
+            Throw::Break | Throw::Continue | Throw::TailCall(..) => Ok(Literal::Null.into()),
+        }
+    }
+
+    /// Gives `err` the current call stack, unless a call it unwound through already did.
+    fn record_backtrace(&self, err: &mut SpannedError) {
+        if err.backtrace.is_empty() {
+            err.backtrace = self.call_stack.iter().rev().cloned().collect();
         }
     }
 
+    fn visit_if_expr(&mut self, condition: &Expr, ex_then: &Expr, ex_else: &Expr) -> ExprResult {
+        if self.evaluate(condition)?.is_truthy() {
+            self.evaluate(ex_then)
+        } else {
+            self.evaluate(ex_else)
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> ExprResult {
+        let mut string = String::new();
+        for part in parts {
+            string.push_str(&self.evaluate(part)?.as_str());
+        }
+        Ok(Literal::String(Symbol::string(string)).into())
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        ex: &Expr,
+        name: &Option<Ident>,
+        params: &Rc<[Ident]>,
+        body: &Rc<[Stmt]>,
+    ) -> ExprResult {
+        let function = Function::lambda(name, params, body, &self.environment.top(), ex.span);
+        Ok(function.into())
+    }
+
     fn visit_logical_expr(&mut self, left: &Expr, op: &LogicOp, right: &Expr) -> ExprResult {
         let left = self.evaluate(left)?;
-        if *op == LogicOp::Or && left.is_truthy() {
-            return Ok(left);
-        }
-        if !left.is_truthy() {
+        let short_circuit = match op {
+            LogicOp::And => !left.is_truthy(),
+            LogicOp::Or => left.is_truthy(),
+        };
+        if short_circuit {
             return Ok(left);
         }
-
         self.evaluate(right)
     }
 
+    fn visit_super_expr(&mut self, ex: &Expr, keyword: &Ident, method: &Ident) -> ExprResult {
+        let Value::Function(superclass) = self.look_up_variable(ex, keyword)? else {
+            unreachable!("'super' is only ever bound to a class")
+        };
+        let superclass = superclass.as_class().expect("'super' is bound to a class");
+        // A bound method's `this` scope sits just inside the scope binding `super`
+        let distance = self.locals[&ex.id()];
+        let this = self
+            .environment
+            .get_at(&Ident::new(THIS.into(), keyword.span), distance - 1)?;
+        match superclass.find_method(method.symbol) {
+            Some(found) => Ok(found.bind(this).into()),
+            None => Err((
+                method.span,
+                format!(
+                    "Undefined method '{}' on superclass '{}'.",
+                    method.symbol, superclass.name
+                ),
+            )
+                .into()),
+        }
+    }
+
     fn visit_unary_expr(&mut self, ex: &Expr, op: &UnaryOp, right: &Expr) -> ExprResult {
         let Value::Literal(right) = self.evaluate(right)? else {
             return Err((
@@ -276,40 +812,306 @@ impl<'a> Interpreter<'a> {
         };
         match op {
             UnaryOp::Negative => match right {
+                Literal::Int(num) => match num.checked_neg() {
+                    Some(num) => Ok(Literal::Int(num).into()),
+                    None => Err((ex.span, INTEGER_OVERFLOW).into()),
+                },
                 Literal::Number(num) => Ok(Literal::Number(-num).into()),
                 _ => Err((ex.span, "Unary operand must be numeric.").into()),
             },
             UnaryOp::Not => Ok(Literal::Bool(!right.is_truthy()).into()),
+            UnaryOp::BitNot => match right.bit_not() {
+                Ok(result) => Ok(result.into()),
+                Err(_) => Err((ex.span, "Unary operand must be numeric.").into()),
+            },
+        }
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, property: &Ident) -> ExprResult {
+        match self.evaluate(object)? {
+            Value::Enum { name, variants } => {
+                match variants
+                    .iter()
+                    .position(|variant| *variant == property.symbol)
+                {
+                    Some(ordinal) => Ok(Value::EnumVariant {
+                        enum_name: name,
+                        variants: Rc::clone(&variants),
+                        variant: property.symbol,
+                        ordinal,
+                    }),
+                    None => Err((
+                        property.span,
+                        format!(
+                            "Undefined variant '{}' on enum '{}'.",
+                            property.symbol, name
+                        ),
+                    )
+                        .into()),
+                }
+            }
+            Value::Record(record) => {
+                let record = record.borrow();
+                match record.get(property.symbol) {
+                    Some(value) => Ok(value.to_owned()),
+                    None => Err(Self::undefined_field(&record, property)),
+                }
+            }
+            Value::Instance(rc) => {
+                let instance = rc.borrow();
+                if let Some(value) = instance.get(property.symbol) {
+                    return Ok(value.to_owned());
+                }
+                match instance.class.find_method(property.symbol) {
+                    Some(method) => Ok(method.bind(Value::Instance(Rc::clone(&rc))).into()),
+                    None => Err((
+                        property.span,
+                        format!(
+                            "Undefined property '{}' on '{}'.",
+                            property.symbol,
+                            instance.as_str()
+                        ),
+                    )
+                        .into()),
+                }
+            }
+            _ => Err((
+                object.span,
+                "Only enums, records and instances have properties.",
+            )
+                .into()),
         }
     }
 
+    fn visit_record_expr(
+        &mut self,
+        ex: &Expr,
+        name: &Ident,
+        fields: &[(Ident, Expr)],
+    ) -> ExprResult {
+        let Value::Struct {
+            name: struct_name,
+            fields: declared,
+        } = self.look_up_variable(ex, name)?
+        else {
+            return Err((name.span, format!("'{}' is not a struct.", name.symbol)).into());
+        };
+        let mut values: Vec<Option<Value>> = vec![None; declared.len()];
+        for (field, initializer) in fields {
+            let Some(index) = declared.iter().position(|f| *f == field.symbol) else {
+                return Err((
+                    field.span,
+                    format!("Struct '{}' has no field '{}'.", struct_name, field.symbol),
+                )
+                    .into());
+            };
+            if values[index].is_some() {
+                return Err((
+                    field.span,
+                    format!("Field '{}' is initialized more than once.", field.symbol),
+                )
+                    .into());
+            }
+            values[index] = Some(self.evaluate(initializer)?);
+        }
+        let mut record = Vec::with_capacity(declared.len());
+        for (field, value) in declared.iter().zip(values) {
+            let Some(value) = value else {
+                return Err((
+                    ex.span,
+                    format!(
+                        "Missing field '{}' in construction of '{}'.",
+                        field, struct_name
+                    ),
+                )
+                    .into());
+            };
+            record.push((*field, value));
+        }
+        let record = Record::new(struct_name, record);
+        Ok(Value::Record(Rc::new(RefCell::new(record))))
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, property: &Ident, value: &Expr) -> ExprResult {
+        match self.evaluate(object)? {
+            Value::Record(record) => {
+                let value = self.evaluate(value)?;
+                let mut record = record.borrow_mut();
+                if !record.set(property.symbol, value.to_owned()) {
+                    return Err(Self::undefined_field(&record, property));
+                }
+                Ok(value)
+            }
+            Value::Instance(instance) => {
+                let value = self.evaluate(value)?;
+                instance.borrow_mut().set(property.symbol, value.to_owned());
+                Ok(value)
+            }
+            _ => Err((object.span, "Only records and instances have fields.").into()),
+        }
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, index: &Expr) -> ExprResult {
+        let collection = self.evaluate(object)?;
+        let position = self.evaluate(index)?;
+        match collection {
+            Value::Array(array) => {
+                let array = array.borrow();
+                let position = Self::array_index(&position, index.span, array.len())?;
+                Ok(array[position].to_owned())
+            }
+            // A missing key reads as null
+            Value::Map(map) => {
+                let key = Self::map_key(position, index.span)?;
+                Ok(map
+                    .borrow()
+                    .get(&key)
+                    .map_or(Literal::Null.into(), Value::to_owned))
+            }
+            _ => Err((object.span, "Only arrays and maps can be indexed.").into()),
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, object: &Expr, index: &Expr, value: &Expr) -> ExprResult {
+        let collection = self.evaluate(object)?;
+        let position = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+        match collection {
+            Value::Array(array) => {
+                let mut array = array.borrow_mut();
+                let position = Self::array_index(&position, index.span, array.len())?;
+                array[position] = value.to_owned();
+            }
+            Value::Map(map) => {
+                let key = Self::map_key(position, index.span)?;
+                map.borrow_mut().insert(key, value.to_owned());
+            }
+            _ => return Err((object.span, "Only arrays and maps can be indexed.").into()),
+        }
+        Ok(value)
+    }
+
+    /// Only literals can key a map. Errors point at the key expression.
+    fn map_key(key: Value, span: Span) -> Result<Literal, Throw> {
+        match key {
+            Value::Literal(key) => Ok(key),
+            _ => Err((
+                span,
+                format!(
+                    "Map keys must be strings, numbers, booleans or null, not '{}'.",
+                    key.as_str()
+                ),
+            )
+                .into()),
+        }
+    }
+
+    /// Errors point at the index expression.
+    fn array_index(position: &Value, span: Span, len: usize) -> Result<usize, Throw> {
+        let Some(position) = Self::number_value(position) else {
+            return Err((span, "Array index must be a number.").into());
+        };
+        if position.fract() != 0.0 {
+            return Err((span, "Array index must be a whole number.").into());
+        }
+        if position < 0.0 || position >= len as f64 {
+            return Err((
+                span,
+                format!(
+                    "Index {} is out of bounds for an array of length {}.",
+                    position, len
+                ),
+            )
+                .into());
+        }
+        Ok(position as usize)
+    }
+
+    /// The value of an int or float as a float.
+    pub(crate) fn number_value(value: &Value) -> Option<f64> {
+        match value {
+            Value::Literal(literal) => literal.as_number(),
+            _ => None,
+        }
+    }
+
+    fn undefined_field(record: &Record, property: &Ident) -> Throw {
+        (
+            property.span,
+            format!(
+                "Undefined field '{}' on '{}'.",
+                property.symbol, record.name
+            ),
+        )
+            .into()
+    }
+
     fn visit_var_expr(&mut self, ex: &Expr, id: &Ident) -> ExprResult {
         self.look_up_variable(ex, id)
     }
 
     pub fn resolve(&mut self, ex: &Expr, depth: usize) {
-        self.locals.insert(ex.to_owned(), depth);
+        self.locals.insert(ex.id(), depth);
+    }
+
+    /// Records whether the top-level name was last declared with `const`.
+    pub(crate) fn declare_global(&mut self, name: Symbol, constant: bool) {
+        if constant {
+            self.global_constants.insert(name);
+        } else {
+            self.global_constants.remove(&name);
+        }
+    }
+
+    pub(crate) fn is_global_constant(&self, name: Symbol) -> bool {
+        self.global_constants.contains(&name)
     }
 
     fn look_up_variable(&self, ex: &Expr, id: &Ident) -> ExprResult {
-        match self.locals.get(ex) {
+        match self.locals.get(&ex.id()) {
             Some(distance) => Ok(self.environment.get_at(id, *distance)?),
             None => Ok(self.environment.global_get(id)?),
         }
     }
 
+    /// Errors point at whichever operand isn't a number.
     fn get_number_ops(
         &self,
         left: &Literal,
-        span: Span,
+        left_span: Span,
         right: &Literal,
-    ) -> Result<(f64, f64), SpannedError> {
-        let Literal::Number(left) = *left else {
-            return Err((span, "Left operand must be a number.").into());
-        };
-        let Literal::Number(right) = *right else {
-            return Err((span, "Right operand must be a number.").into());
-        };
-        Ok((left, right))
+        right_span: Span,
+    ) -> Result<Operands, SpannedError> {
+        if left.as_number().is_none() {
+            return Err((left_span, "Left operand must be a number.").into());
+        }
+        match Operands::new(left, right) {
+            Some(operands) => Ok(operands),
+            None => Err((right_span, "Right operand must be a number.").into()),
+        }
+    }
+
+    /// Two strings compare by their text, anything else must be two numbers.
+    /// Returns `None` when a number is NaN, which makes every ordering false.
+    fn compare_ops(
+        &self,
+        left: &Literal,
+        left_span: Span,
+        right: &Literal,
+        right_span: Span,
+        span: Span,
+    ) -> Result<Option<Ordering>, SpannedError> {
+        match (left, right) {
+            (Literal::String(left), Literal::String(right)) => {
+                Ok(Some(left.resolve().cmp(&right.resolve())))
+            }
+            (Literal::String(_), _) | (_, Literal::String(_)) => {
+                Err((span, "Can only compare two numbers or two strings.").into())
+            }
+            _ => {
+                let operands = self.get_number_ops(left, left_span, right, right_span)?;
+                Ok(operands.compare())
+            }
+        }
     }
 }
@@ -1,9 +1,27 @@
+mod arrays;
 mod callable;
+mod class;
+mod compiler;
 mod environment;
 mod interpreter;
+mod maps;
+mod math;
+mod peephole;
+mod record;
 mod resolver;
+mod strings;
+mod vm;
 
+pub use crate::arrays::*;
 pub use crate::callable::*;
+pub use crate::class::*;
+pub use crate::compiler::*;
 pub use crate::environment::*;
 pub use crate::interpreter::*;
+pub use crate::maps::*;
+pub use crate::math::*;
+pub use crate::peephole::*;
+pub use crate::record::*;
 pub use crate::resolver::*;
+pub use crate::strings::*;
+pub use crate::vm::*;
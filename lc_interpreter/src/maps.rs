@@ -0,0 +1,70 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::*;
+use lc_core::*;
+
+pub fn define_maps(environment: &mut Environment) {
+    environment.define_builtin::<LcKeys>("keys");
+    environment.define_builtin::<LcValues>("values");
+}
+
+/// Checks the arity and that the argument is a map, then collects an array from its entries
+/// in insertion order.
+fn map_entries(
+    name: &str,
+    span: Span,
+    arity: usize,
+    arguments: &[Value],
+    entry: impl Fn(&Literal, &Value) -> Value,
+) -> Throw {
+    if let Err(throw) = check_arity(span, arity, arguments) {
+        return throw;
+    }
+    let Value::Map(entries) = &arguments[0] else {
+        return (span, format!("{} expects a map", name)).into();
+    };
+    let elements = entries
+        .borrow()
+        .iter()
+        .map(|(key, value)| entry(key, value))
+        .collect();
+    Value::Array(Rc::new(RefCell::new(elements))).into()
+}
+
+/// `keys(map)`, an array of the map's keys in the order they were first inserted.
+#[derive(Clone, Debug, Default)]
+pub struct LcKeys;
+impl<'a> Callable<'a> for LcKeys {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        map_entries("keys", span, self.arity(), arguments, |key, _| {
+            Value::Literal(key.to_owned())
+        })
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn keys/{}>", self.arity())
+    }
+}
+
+/// `values(map)`, an array of the map's values in the same order as [`LcKeys`] gives keys.
+#[derive(Clone, Debug, Default)]
+pub struct LcValues;
+impl<'a> Callable<'a> for LcValues {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        map_entries("values", span, self.arity(), arguments, |_, value| {
+            value.to_owned()
+        })
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn values/{}>", self.arity())
+    }
+}
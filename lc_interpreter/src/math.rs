@@ -0,0 +1,145 @@
+use crate::*;
+use lc_core::*;
+
+pub fn define_math(environment: &mut Environment) {
+    environment.define_builtin::<LcSqrt>("sqrt");
+    environment.define_builtin::<LcPow>("pow");
+    environment.define_builtin::<LcAbs>("abs");
+    environment.define_builtin::<LcFloor>("floor");
+    environment.define_builtin::<LcCeil>("ceil");
+    environment.define_builtin::<LcRound>("round");
+}
+
+/// Checks the arity and that every argument is a number, naming the builtin in the error.
+fn number_arguments<const N: usize>(
+    name: &str,
+    span: Span,
+    arguments: &[Value],
+) -> Result<[f64; N], Throw> {
+    check_arity(span, N, arguments)?;
+    let mut numbers = [0.0; N];
+    for (number, argument) in numbers.iter_mut().zip(arguments) {
+        let Some(value) = Interpreter::number_value(argument) else {
+            let expected = if N == 1 { "a number" } else { "numbers" };
+            return Err((span, format!("{} expects {}", name, expected)).into());
+        };
+        *number = value;
+    }
+    Ok(numbers)
+}
+
+/// The square root, NaN for negative numbers.
+#[derive(Clone, Debug, Default)]
+pub struct LcSqrt;
+impl<'a> Callable<'a> for LcSqrt {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<1>("sqrt", span, arguments) {
+            Ok([num]) => Literal::Number(num.sqrt()).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn sqrt/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcPow;
+impl<'a> Callable<'a> for LcPow {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<2>("pow", span, arguments) {
+            Ok([base, exponent]) => Literal::Number(base.powf(exponent)).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn pow/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcAbs;
+impl<'a> Callable<'a> for LcAbs {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<1>("abs", span, arguments) {
+            Ok([num]) => Literal::Number(num.abs()).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn abs/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcFloor;
+impl<'a> Callable<'a> for LcFloor {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<1>("floor", span, arguments) {
+            Ok([num]) => Literal::Number(num.floor()).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn floor/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcCeil;
+impl<'a> Callable<'a> for LcCeil {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<1>("ceil", span, arguments) {
+            Ok([num]) => Literal::Number(num.ceil()).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn ceil/{}>", self.arity())
+    }
+}
+
+/// Rounds half-way cases away from zero, so `round(-2.5)` is -3.
+#[derive(Clone, Debug, Default)]
+pub struct LcRound;
+impl<'a> Callable<'a> for LcRound {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match number_arguments::<1>("round", span, arguments) {
+            Ok([num]) => Literal::Number(num.round()).into(),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn round/{}>", self.arity())
+    }
+}
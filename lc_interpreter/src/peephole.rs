@@ -0,0 +1,101 @@
+use crate::*;
+
+/// Simplifies control flow in a compiled [`Chunk`] without changing its behaviour.
+///
+/// Jumps that land on an unconditional `Jump` are redirected to its final target, unreachable
+/// instructions following an unconditional `Jump` are dropped, and jumps to the very next
+/// instruction are removed. Passes repeat until the chunk stops changing.
+pub fn optimize(mut chunk: Chunk) -> Chunk {
+    loop {
+        thread_jumps(&mut chunk);
+        let removed = unreachable_code(&chunk);
+        if !removed.iter().any(|r| *r) {
+            return chunk;
+        }
+        chunk = remove_instructions(chunk, &removed);
+    }
+}
+
+fn jump_target(op: &OpCode) -> Option<usize> {
+    match op {
+        OpCode::Jump(to) | OpCode::JumpIfFalse(to) | OpCode::JumpIfTrue(to) => Some(*to),
+        _ => None,
+    }
+}
+
+fn set_jump_target(op: &mut OpCode, target: usize) {
+    if let OpCode::Jump(to) | OpCode::JumpIfFalse(to) | OpCode::JumpIfTrue(to) = op {
+        *to = target;
+    }
+}
+
+fn thread_jumps(chunk: &mut Chunk) {
+    for i in 0..chunk.code.len() {
+        let Some(mut target) = jump_target(&chunk.code[i]) else {
+            continue;
+        };
+        // Bound the walk so a cycle of jumps can't loop forever
+        for _ in 0..chunk.code.len() {
+            match chunk.code.get(target) {
+                Some(OpCode::Jump(next)) if *next != target => target = *next,
+                _ => break,
+            }
+        }
+        set_jump_target(&mut chunk.code[i], target);
+    }
+}
+
+fn unreachable_code(chunk: &Chunk) -> Vec<bool> {
+    let mut targets = vec![false; chunk.code.len() + 1];
+    for op in &chunk.code {
+        if let Some(target) = jump_target(op) {
+            targets[target] = true;
+        }
+    }
+
+    let mut removed = vec![false; chunk.code.len()];
+    let mut reachable = true;
+    for (i, op) in chunk.code.iter().enumerate() {
+        if targets[i] {
+            reachable = true;
+        }
+        if !reachable {
+            removed[i] = true;
+            continue;
+        }
+        if let OpCode::Jump(target) = op {
+            reachable = false;
+            // A jump to the following instruction does nothing
+            if *target == i + 1 {
+                removed[i] = true;
+            }
+        }
+    }
+    removed
+}
+
+fn remove_instructions(chunk: Chunk, removed: &[bool]) -> Chunk {
+    // Each old index maps to the next instruction that survives
+    let mut new_index = Vec::with_capacity(removed.len() + 1);
+    let mut kept = 0;
+    for removed in removed {
+        new_index.push(kept);
+        if !removed {
+            kept += 1;
+        }
+    }
+    new_index.push(kept);
+
+    let mut optimized = Chunk::new();
+    for (i, (mut op, span)) in chunk.code.into_iter().zip(chunk.spans).enumerate() {
+        if removed[i] {
+            continue;
+        }
+        if let Some(target) = jump_target(&op) {
+            set_jump_target(&mut op, new_index[target]);
+        }
+        optimized.code.push(op);
+        optimized.spans.push(span);
+    }
+    optimized
+}
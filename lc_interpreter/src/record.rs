@@ -0,0 +1,61 @@
+use std::fmt::Write;
+
+use crate::*;
+use lc_core::*;
+
+/// An instance of a `struct` declaration. Fields are kept in declaration order so that
+/// printing is stable.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub name: Symbol,
+    fields: Vec<(Symbol, Value)>,
+}
+impl Record {
+    pub fn new(name: Symbol, fields: Vec<(Symbol, Value)>) -> Self {
+        Self { name, fields }
+    }
+
+    pub fn get(&self, field: Symbol) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns `false` if the record has no field with the given name.
+    pub fn set(&mut self, field: Symbol, value: Value) -> bool {
+        match self.fields.iter_mut().find(|(name, _)| *name == field) {
+            Some((_, slot)) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn as_str(&self) -> String {
+        self.as_str_within(&mut Vec::new())
+    }
+
+    /// [`Record::as_str`] inside the collections and records in `enclosing`, as
+    /// [`Value::as_str_within`] describes.
+    pub(crate) fn as_str_within(&self, enclosing: &mut Vec<*const ()>) -> String {
+        if self.fields.is_empty() {
+            return format!("{} {{}}", self.name);
+        }
+        let mut str = format!("{} {{", self.name);
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(
+                str,
+                "{}{}: {}",
+                separator,
+                name,
+                value.repr_within(enclosing)
+            )
+            .unwrap();
+        }
+        str.push_str(" }");
+        str
+    }
+}
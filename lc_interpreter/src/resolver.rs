@@ -1,41 +1,124 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, mem};
 
 use lc_core::*;
 
 use crate::*;
 
-type Scope = HashMap<String, bool>;
+type Scope = HashMap<String, Binding>;
 type ResolverResult = Result<(), SpannedError>;
 
+/// A write-only name that may be declared any number of times in a scope.
+const DISCARD: &str = "_";
+
+/// What the resolver knows about a name declared in a scope.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Binding {
+    /// Whether its initializer has been resolved, so that it may be read
+    defined: bool,
+    /// Declared with `const`, so it can't be assigned to
+    constant: bool,
+    /// Whether anything refers to it, or it is exempt from the unused warning
+    used: bool,
+    /// Where it was declared
+    span: Span,
+}
+impl Binding {
+    /// A binding that is never warned about, like a parameter or `this`.
+    fn exempt(span: Span) -> Self {
+        Self {
+            defined: true,
+            constant: false,
+            used: true,
+            span,
+        }
+    }
+}
+
+/// A name referred to in a scope before any scope declared it.
+#[derive(Debug)]
+struct ForwardReference {
+    ex: Expr,
+    name: String,
+    /// How many scopes were open where it was referred to
+    scopes: usize,
+    /// Scopes from this index up have closed since, so can no longer be the ones it refers to
+    limit: usize,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum FunctionKind {
     None,
     Function,
+    Method,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
 }
 
 #[derive(Debug)]
 pub struct Resolver<'a, 'b> {
     interpreter: &'a mut Interpreter<'b>,
     scopes: Vec<Scope>,
+    /// Names referred to inside a scope before any scope declared them. A function declared
+    /// later in a scope enclosing the reference binds it, so that functions in a block can call
+    /// each other whichever comes first.
+    forward_references: Vec<ForwardReference>,
+    /// Names declared at the top level. Globals are looked up dynamically, so this is only
+    /// consulted for redeclarations and never for resolving depths.
+    globals: Scope,
+    allow_global_redeclaration: bool,
     current_function: FunctionKind,
+    current_class: ClassKind,
+    /// Whether `break` and `continue` have a loop to apply to
+    in_loop: bool,
     errors: Vec<SpannedError>,
+    warnings: Vec<SpannedError>,
 }
 impl<'a, 'b> Resolver<'a, 'b> {
     pub fn new(interpreter: &'a mut Interpreter<'b>) -> Self {
         Self {
             interpreter,
             scopes: Vec::new(),
+            forward_references: Vec::new(),
+            globals: Scope::new(),
+            allow_global_redeclaration: true,
             current_function: FunctionKind::None,
+            current_class: ClassKind::None,
+            in_loop: false,
             errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
-    pub fn resolve(&mut self, statements: &Vec<Stmt>) -> TranslationResult<()> {
+    /// Whether a top-level `let` (or other declaration) may reuse a name already declared at
+    /// the top level. Allowed by default; when disallowed it is an error, as in inner scopes.
+    pub fn allow_global_redeclaration(self, allow: bool) -> Self {
+        Self {
+            allow_global_redeclaration: allow,
+            ..self
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> TranslationResult<()> {
+        // Functions declared before a top-level `const` may still assign to it
+        for stmt in statements {
+            if let Stmt::Const(id, _) = stmt {
+                self.interpreter.declare_global(id.symbol, true);
+            }
+        }
         let _ = self.resolve_statements(statements);
-        ((), self.errors.clone().into())
+        let errors = TranslationErrors::from(self.errors.clone());
+        // Unused locals are only found as their scopes close, after what they contain
+        let mut warnings = self.warnings.clone();
+        warnings.sort_by_key(|warning| warning.span.start);
+        ((), errors.with_warnings(warnings))
     }
 
-    fn resolve_statements(&mut self, statements: &Vec<Stmt>) -> ResolverResult {
+    fn resolve_statements(&mut self, statements: &[Stmt]) -> ResolverResult {
         for stmt in statements {
             if let Err(e) = self.resolve_stmt(stmt) {
                 self.report_error(e);
@@ -47,7 +130,16 @@ impl<'a, 'b> Resolver<'a, 'b> {
     fn resolve_stmt(&mut self, stmt: &Stmt) -> ResolverResult {
         match stmt {
             Stmt::Block(statements) => self.visit_block_stmt(statements)?,
-            Stmt::Class(id, methods) => self.visit_class_stmt(id, methods)?,
+            Stmt::Break(span) => self.visit_loop_control_stmt(*span, "break")?,
+            Stmt::Continue(span) => self.visit_loop_control_stmt(*span, "continue")?,
+            Stmt::Class(id, superclass, methods) => {
+                self.visit_class_stmt(id, superclass, methods)?
+            }
+            Stmt::Enum(id, variants) => {
+                self.declare(id)?;
+                self.define(id);
+                Self::check_unique(variants, "variant", "enum", id)?;
+            }
             Stmt::Expression(ex) => self.resolve_expr(ex)?,
             Stmt::Function(id, params, body) => {
                 self.visit_function_stmt(id, params, body, FunctionKind::Function)?
@@ -55,25 +147,89 @@ impl<'a, 'b> Resolver<'a, 'b> {
             Stmt::If(condition, st_then, st_else) => {
                 self.visit_if_stmt(condition, st_then, st_else)?
             }
-            Stmt::Print(ex) => self.resolve_expr(ex)?,
+            Stmt::Print(expressions) => {
+                for ex in expressions {
+                    self.resolve_expr(ex)?;
+                }
+            }
             Stmt::Return(ex) => self.visit_return_stmt(ex)?,
             Stmt::Let(id, initializer) => self.visit_let_stmt(id, initializer)?,
-            Stmt::While(condition, body) => self.visit_while_stmt(condition, body)?,
+            Stmt::Const(id, initializer) => self.visit_const_stmt(id, initializer)?,
+            Stmt::LetTuple(ids, initializer) => {
+                for id in ids {
+                    self.declare(id)?;
+                }
+                self.resolve_expr(initializer)?;
+                for id in ids {
+                    self.define(id);
+                }
+            }
+            Stmt::Struct(id, fields) => {
+                self.declare(id)?;
+                self.define(id);
+                Self::check_unique(fields, "field", "struct", id)?;
+            }
+            Stmt::While(condition, body, increment) => {
+                self.visit_while_stmt(condition, body, increment)?
+            }
+            Stmt::DoWhile(body, condition) => self.visit_do_while_stmt(body, condition)?,
+            Stmt::ForEach(id, iterable, body) => self.visit_foreach_stmt(id, iterable, body)?,
         };
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> ResolverResult {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> ResolverResult {
+        if !Stmt::declares_bindings(statements) {
+            return self.resolve_statements(statements);
+        }
         self.begin_scope();
         self.resolve_statements(statements)?;
         self.end_scope();
         Ok(())
     }
 
-    fn visit_class_stmt(&mut self, id: &Ident, _: &Vec<Stmt>) -> ResolverResult {
+    fn visit_class_stmt(
+        &mut self,
+        id: &Ident,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+    ) -> ResolverResult {
         self.declare(id)?;
         self.define(id);
-        Ok(())
+        if let Some(superclass) = superclass {
+            if let ExprKind::Variable(name) = &superclass.kind {
+                if name.symbol == id.symbol {
+                    return Err((superclass.span, "A class can't inherit from itself.").into());
+                }
+            }
+            self.resolve_expr(superclass)?;
+        }
+        let enclosing = self.current_class;
+        self.current_class = match superclass {
+            Some(_) => ClassKind::Subclass,
+            None => ClassKind::Class,
+        };
+        // Mirrors the closures built at runtime: a scope binding `super` around the methods,
+        // and one binding `this` around each bound method
+        if superclass.is_some() {
+            self.begin_scope();
+            self.define_synthetic(SUPER);
+        }
+        let result = methods.iter().try_for_each(|method| match method {
+            Stmt::Function(_, params, body) => {
+                self.begin_scope();
+                self.define_synthetic(THIS);
+                let result = self.resolve_function(None, params, body, FunctionKind::Method);
+                self.end_scope();
+                result
+            }
+            _ => Ok(()),
+        });
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        self.current_class = enclosing;
+        result
     }
 
     fn visit_if_stmt(
@@ -82,7 +238,7 @@ impl<'a, 'b> Resolver<'a, 'b> {
         st_then: &Stmt,
         st_else: &Option<Box<Stmt>>,
     ) -> ResolverResult {
-        self.resolve_expr(condition)?;
+        self.resolve_condition(condition)?;
         self.resolve_stmt(st_then)?;
         if let Some(st_else) = st_else {
             self.resolve_stmt(st_else)?;
@@ -98,26 +254,89 @@ impl<'a, 'b> Resolver<'a, 'b> {
         }
     }
 
+    fn visit_loop_control_stmt(&mut self, span: Span, keyword: &str) -> ResolverResult {
+        if self.in_loop {
+            Ok(())
+        } else {
+            Err((span, format!("Can't use '{}' outside of a loop", keyword)).into())
+        }
+    }
+
     fn visit_function_stmt(
         &mut self,
         id: &Ident,
-        params: &Vec<Ident>,
-        body: &Vec<Stmt>,
+        params: &[Ident],
+        body: &[Stmt],
         kind: FunctionKind,
     ) -> ResolverResult {
         self.declare(id)?;
         self.define(id);
+        self.bind_forward_references(id);
+        self.resolve_function(None, params, body, kind)
+    }
+
+    /// Errors at the second of any two `names` that are the same, like a `what` declared twice
+    /// in the `kind` named `id`.
+    fn check_unique(names: &[Ident], what: &str, kind: &str, id: &Ident) -> ResolverResult {
+        for (i, name) in names.iter().enumerate() {
+            if names[..i]
+                .iter()
+                .any(|earlier| earlier.symbol == name.symbol)
+            {
+                let message = format!(
+                    "Duplicate {} '{}' in {} '{}'.",
+                    what, name.symbol, kind, id.symbol
+                );
+                return Err((name.span, message).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the references made before `id` was declared in the innermost scope to it.
+    fn bind_forward_references(&mut self, id: &Ident) {
+        let Some(index) = self.scopes.len().checked_sub(1) else {
+            return;
+        };
+        let name = id.symbol.to_string();
+        let (bound, pending): (Vec<_>, Vec<_>) = mem::take(&mut self.forward_references)
+            .into_iter()
+            .partition(|reference| reference.name == name && index < reference.limit);
+        self.forward_references = pending;
+        for reference in &bound {
+            self.interpreter
+                .resolve(&reference.ex, reference.scopes - 1 - index);
+        }
+        if let (false, Some(binding)) = (bound.is_empty(), self.scopes[index].get_mut(&name)) {
+            binding.used = true;
+        }
+    }
 
+    fn resolve_function(
+        &mut self,
+        name: Option<&Ident>,
+        params: &[Ident],
+        body: &[Stmt],
+        kind: FunctionKind,
+    ) -> ResolverResult {
         let enclosing = self.current_function;
+        let enclosing_loop = self.in_loop;
         self.current_function = kind;
+        self.in_loop = false;
         self.begin_scope();
+        // A named function expression can refer to itself, but only from within its own body
+        if let Some(name) = name {
+            self.declare(name)?;
+            self.define_exempt(name);
+        }
         for param in params {
             self.declare(param)?;
-            self.define(param);
+            self.define_exempt(param);
         }
         self.resolve_statements(body)?;
         self.end_scope();
         self.current_function = enclosing;
+        self.in_loop = enclosing_loop;
         Ok(())
     }
 
@@ -128,27 +347,131 @@ impl<'a, 'b> Resolver<'a, 'b> {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> ResolverResult {
-        self.resolve_expr(condition)?;
-        self.resolve_stmt(body)?;
+    fn visit_const_stmt(&mut self, id: &Ident, initializer: &Expr) -> ResolverResult {
+        self.declare_binding(id, true)?;
+        self.resolve_expr(initializer)?;
+        self.define(id);
         Ok(())
     }
 
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> ResolverResult {
+        self.resolve_condition(condition)?;
+        if let Some(increment) = increment {
+            self.resolve_expr(increment)?;
+        }
+        let enclosing_loop = self.in_loop;
+        self.in_loop = true;
+        let result = self.resolve_stmt(body);
+        self.in_loop = enclosing_loop;
+        result
+    }
+
+    fn visit_do_while_stmt(&mut self, body: &Stmt, condition: &Expr) -> ResolverResult {
+        let enclosing_loop = self.in_loop;
+        self.in_loop = true;
+        let result = self.resolve_stmt(body);
+        self.in_loop = enclosing_loop;
+        result?;
+        self.resolve_condition(condition)
+    }
+
+    /// The loop variable lives in a scope of its own around the body, matching the scope the
+    /// interpreter creates for each iteration.
+    fn visit_foreach_stmt(&mut self, id: &Ident, iterable: &Expr, body: &Stmt) -> ResolverResult {
+        self.resolve_expr(iterable)?;
+        self.begin_scope();
+        self.declare(id)?;
+        self.define(id);
+        let enclosing_loop = self.in_loop;
+        self.in_loop = true;
+        let result = self.resolve_stmt(body);
+        self.in_loop = enclosing_loop;
+        self.end_scope();
+        result
+    }
+
+    /// Warns when the whole condition is an assignment, which is usually a mistyped `==`.
+    /// Assignments nested inside a larger condition are left alone.
+    fn resolve_condition(&mut self, condition: &Expr) -> ResolverResult {
+        if matches!(condition.kind, ExprKind::Assign(..) | ExprKind::Set(..)) {
+            self.report_warning(
+                (
+                    condition.span,
+                    "Assignment used as a condition. Did you mean '=='?",
+                )
+                    .into(),
+            );
+        }
+        self.resolve_expr(condition)
+    }
+
     fn resolve_expr(&mut self, expr: &Expr) -> ResolverResult {
         match &expr.kind {
             ExprKind::Assign(id, initializer) => self.visit_assign_expr(expr, id, initializer),
             ExprKind::Binary(left, _, right) => self.visit_binary_expr(left, right),
+            ExprKind::Block(statements, value) => self.visit_block_expr(statements, value),
             ExprKind::Call(callee, _, args) => self.visit_call_expr(callee, args),
+            ExprKind::Get(object, _) => self.resolve_expr(object),
             ExprKind::Grouping(ex) => self.resolve_expr(ex),
+            ExprKind::If(condition, ex_then, ex_else)
+            | ExprKind::Ternary(condition, ex_then, ex_else) => {
+                self.resolve_condition(condition)?;
+                self.resolve_expr(ex_then)?;
+                self.resolve_expr(ex_else)
+            }
+            ExprKind::Lambda(name, params, body) => {
+                self.resolve_function(name.as_ref(), params, body, FunctionKind::Function)
+            }
             ExprKind::Literal(_) => Ok(()),
             ExprKind::Logical(left, _, right) => self.visit_binary_expr(left, right),
-            ExprKind::Unary(_, right) => self.resolve_expr(right),
+            ExprKind::Record(name, fields) => {
+                self.visit_var_expr(expr, name)?;
+                for (_, initializer) in fields {
+                    self.resolve_expr(initializer)?;
+                }
+                Ok(())
+            }
+            ExprKind::Set(object, _, value) => self.visit_binary_expr(value, object),
+            ExprKind::Index(object, index) => self.visit_binary_expr(object, index),
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.visit_binary_expr(key, value)?;
+                }
+                Ok(())
+            }
+            ExprKind::SetIndex(object, index, value) => {
+                self.resolve_expr(value)?;
+                self.visit_binary_expr(object, index)
+            }
+            ExprKind::Array(elements)
+            | ExprKind::Interpolation(elements)
+            | ExprKind::Tuple(elements) => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+                Ok(())
+            }
+            ExprKind::Is(right, _) | ExprKind::Unary(_, right) => self.resolve_expr(right),
+            ExprKind::Super(keyword, _) => self.visit_super_expr(expr, keyword),
+            ExprKind::This(keyword) => self.visit_this_expr(expr, keyword),
             ExprKind::Variable(id) => self.visit_var_expr(expr, id),
         }
     }
 
     fn visit_assign_expr(&mut self, ex: &Expr, id: &Ident, initializer: &Expr) -> ResolverResult {
         self.resolve_expr(initializer)?;
+        if self.is_constant(id) {
+            return Err((
+                ex.span,
+                format!("Can't assign to constant '{}'.", id.symbol),
+            )
+                .into());
+        }
         self.resolve_local(ex, id);
         Ok(())
     }
@@ -159,6 +482,25 @@ impl<'a, 'b> Resolver<'a, 'b> {
         Ok(())
     }
 
+    fn visit_block_expr(
+        &mut self,
+        statements: &[Stmt],
+        value: &Option<Box<Expr>>,
+    ) -> ResolverResult {
+        let scoped = Stmt::declares_bindings(statements);
+        if scoped {
+            self.begin_scope();
+        }
+        self.resolve_statements(statements)?;
+        if let Some(value) = value {
+            self.resolve_expr(value)?;
+        }
+        if scoped {
+            self.end_scope();
+        }
+        Ok(())
+    }
+
     fn visit_call_expr(&mut self, callee: &Expr, args: &Vec<Expr>) -> ResolverResult {
         self.resolve_expr(callee)?;
         for arg in args {
@@ -168,12 +510,15 @@ impl<'a, 'b> Resolver<'a, 'b> {
     }
 
     fn visit_var_expr(&mut self, ex: &Expr, id: &Ident) -> ResolverResult {
-        if let Some(initialized) = self
+        if id.symbol.to_string() == DISCARD {
+            return Err((ex.span, "Can't read from '_', it only discards values.").into());
+        }
+        if let Some(binding) = self
             .scopes
-            .last_mut()
+            .last()
             .and_then(|s| s.get(&id.symbol.to_string()))
         {
-            if !initialized {
+            if !binding.defined {
                 self.report_error(
                     (ex.span, "Can't read local variable in its own initializer.").into(),
                 );
@@ -184,45 +529,139 @@ impl<'a, 'b> Resolver<'a, 'b> {
         Ok(())
     }
 
+    fn visit_super_expr(&mut self, ex: &Expr, keyword: &Ident) -> ResolverResult {
+        match self.current_class {
+            ClassKind::None => Err((ex.span, "Can't use 'super' outside of a class").into()),
+            ClassKind::Class => {
+                Err((ex.span, "Can't use 'super' in a class with no superclass").into())
+            }
+            ClassKind::Subclass => {
+                self.resolve_local(ex, keyword);
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_this_expr(&mut self, ex: &Expr, keyword: &Ident) -> ResolverResult {
+        if self.current_class == ClassKind::None {
+            return Err((ex.span, "Can't use 'this' outside of a method").into());
+        }
+        self.resolve_local(ex, keyword);
+        Ok(())
+    }
+
     fn resolve_local(&mut self, ex: &Expr, id: &Ident) {
+        let name = id.symbol.to_string();
         for i in (0..self.scopes.len()).rev() {
-            if self
-                .scopes
-                .get(i)
-                .is_some_and(|s| s.contains_key(&id.symbol.to_string()))
-            {
+            if let Some(binding) = self.scopes[i].get_mut(&name) {
+                binding.used = true;
                 self.interpreter.resolve(ex, self.scopes.len() - 1 - i);
                 return;
             }
         }
+        if !self.scopes.is_empty() {
+            self.forward_references.push(ForwardReference {
+                ex: ex.to_owned(),
+                name,
+                scopes: self.scopes.len(),
+                limit: self.scopes.len(),
+            });
+        }
+    }
+
+    /// Whether `id` refers to a binding declared with `const`, in the innermost scope that has
+    /// one by that name or else among the globals.
+    fn is_constant(&self, id: &Ident) -> bool {
+        let name = id.symbol.to_string();
+        match self.scopes.iter().rev().find_map(|s| s.get(&name)) {
+            Some(binding) => binding.constant,
+            None => self.interpreter.is_global_constant(id.symbol),
+        }
     }
 
     fn declare(&mut self, id: &Ident) -> ResolverResult {
-        let Some(scope) = self.scopes.last_mut() else {
-            return Ok(());
+        self.declare_binding(id, false)
+    }
+
+    fn declare_binding(&mut self, id: &Ident, constant: bool) -> ResolverResult {
+        if self.scopes.is_empty() && id.symbol.to_string() != DISCARD {
+            self.interpreter.declare_global(id.symbol, constant);
+        }
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None if self.allow_global_redeclaration => return Ok(()),
+            None => &mut self.globals,
         };
-        if scope.contains_key(&id.symbol.to_string()) {
+        let name = id.symbol.to_string();
+        if name == DISCARD {
+            return Ok(());
+        }
+        if scope.contains_key(&name) {
             return Err((id.span, "Already a variable with this name in this scope.").into());
         }
-        scope.insert(id.symbol.to_string(), false);
+        scope.insert(
+            name,
+            Binding {
+                defined: false,
+                constant,
+                used: false,
+                span: id.span,
+            },
+        );
         Ok(())
     }
 
     fn define(&mut self, id: &Ident) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(id.symbol.to_string(), true);
+            scope
+                .entry(id.symbol.to_string())
+                .or_insert(Binding::exempt(id.span))
+                .defined = true;
+        };
+    }
+
+    /// Defines a name that needn't be used, like a parameter.
+    fn define_exempt(&mut self, id: &Ident) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.symbol.to_string(), Binding::exempt(id.span));
         };
     }
 
+    /// Binds one of the names the interpreter defines itself, like `this`, in the innermost
+    /// scope.
+    fn define_synthetic(&mut self, name: &str) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), Binding::exempt(Span::default()));
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.push(Scope::new());
     }
 
+    /// Warns about the locals of the closing scope that nothing referred to.
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        let open = self.scopes.len();
+        for reference in &mut self.forward_references {
+            reference.limit = reference.limit.min(open);
+        }
+        // With no scope left open, what's still unresolved is global
+        self.forward_references
+            .retain(|reference| reference.limit > 0);
+        for (name, binding) in scope.iter().filter(|(_, b)| !b.used) {
+            self.report_warning((binding.span, format!("Local '{}' is never used.", name)).into());
+        }
     }
 
     fn report_error(&mut self, e: SpannedError) {
-        self.errors.push(e)
+        self.errors.push(e.with_phase(Phase::Resolve))
+    }
+
+    fn report_warning(&mut self, warning: SpannedError) {
+        self.warnings.push(warning.with_phase(Phase::Resolve))
     }
 }
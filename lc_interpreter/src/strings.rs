@@ -0,0 +1,193 @@
+use crate::*;
+use lc_core::*;
+
+pub fn define_strings(environment: &mut Environment) {
+    environment.define_builtin::<LcUpper>("upper");
+    environment.define_builtin::<LcLower>("lower");
+    environment.define_builtin::<LcTrim>("trim");
+    environment.define_builtin::<LcSubstring>("substring");
+    environment.define_builtin::<LcFormat>("format");
+}
+
+/// Checks the arity and that the first argument is a string, naming the builtin in the error.
+fn string_argument(
+    name: &str,
+    span: Span,
+    arity: usize,
+    arguments: &[Value],
+) -> Result<String, Throw> {
+    check_arity(span, arity, arguments)?;
+    match &arguments[0] {
+        Value::Literal(Literal::String(string)) => Ok(string.resolve()),
+        _ => Err((span, format!("{} expects a string", name)).into()),
+    }
+}
+
+fn string_value(string: String) -> Throw {
+    Literal::String(Symbol::string(string)).into()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcUpper;
+impl<'a> Callable<'a> for LcUpper {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match string_argument("upper", span, self.arity(), arguments) {
+            Ok(string) => string_value(string.to_uppercase()),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn upper/{}>", self.arity())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LcLower;
+impl<'a> Callable<'a> for LcLower {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match string_argument("lower", span, self.arity(), arguments) {
+            Ok(string) => string_value(string.to_lowercase()),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn lower/{}>", self.arity())
+    }
+}
+
+/// Strips whitespace from both ends.
+#[derive(Clone, Debug, Default)]
+pub struct LcTrim;
+impl<'a> Callable<'a> for LcTrim {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        match string_argument("trim", span, self.arity(), arguments) {
+            Ok(string) => string_value(string.trim().to_string()),
+            Err(throw) => throw,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn trim/{}>", self.arity())
+    }
+}
+
+/// `substring(s, start, end)`, the characters from `start` up to but not including `end`.
+/// Indices count characters rather than bytes and must satisfy `0 <= start <= end <= len(s)`.
+#[derive(Clone, Debug, Default)]
+pub struct LcSubstring;
+impl<'a> Callable<'a> for LcSubstring {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let string = match string_argument("substring", span, self.arity(), arguments) {
+            Ok(string) => string,
+            Err(throw) => return throw,
+        };
+        let len = string.chars().count();
+        let (Some(start), Some(end)) = (
+            Interpreter::number_value(&arguments[1]),
+            Interpreter::number_value(&arguments[2]),
+        ) else {
+            return (span, "substring expects number indices").into();
+        };
+        if start.fract() != 0.0 || end.fract() != 0.0 {
+            return (span, "substring indices must be whole numbers").into();
+        }
+        if start < 0.0 || start > end || end > len as f64 {
+            return (
+                span,
+                format!(
+                    "substring range {}..{} is out of bounds for a string of length {}",
+                    start, end, len
+                ),
+            )
+                .into();
+        }
+        let (start, end) = (start as usize, end as usize);
+        string_value(string.chars().skip(start).take(end - start).collect())
+    }
+
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn substring/{}>", self.arity())
+    }
+}
+
+/// `format(template, values...)`, the template with each `{}` replaced by the next value as
+/// `print` would show it. `{{` and `}}` stand for literal braces, and any other brace is an
+/// error, as is a count of values that doesn't match the placeholders.
+#[derive(Clone, Debug, Default)]
+pub struct LcFormat;
+impl<'a> Callable<'a> for LcFormat {
+    fn call(&mut self, _: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        let Some((Value::Literal(Literal::String(template)), values)) = arguments.split_first()
+        else {
+            return (span, "format expects a format string").into();
+        };
+        match format_placeholders(&template.resolve(), values) {
+            Ok(string) => string_value(string),
+            Err(message) => (span, message).into(),
+        }
+    }
+
+    /// The values are variadic, so this is the fewest arguments it takes.
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn as_str(&self) -> String {
+        format!("<native fn format/{}>", self.arity())
+    }
+}
+
+/// Fills the `{}` placeholders of `template` with `values` for [`LcFormat`].
+fn format_placeholders(template: &str, values: &[Value]) -> Result<String, String> {
+    let mut string = String::new();
+    let mut placeholders = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                string.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if let Some(value) = values.get(placeholders) {
+                    string.push_str(&value.as_str());
+                }
+                placeholders += 1;
+            }
+            ('{' | '}', _) => {
+                return Err(format!(
+                    "Unmatched '{}' in format string, write '{}{}' for a literal brace",
+                    c, c, c
+                ))
+            }
+            _ => string.push(c),
+        }
+    }
+    if placeholders != values.len() {
+        return Err(format!(
+            "format string has {} placeholders but was given {} values",
+            placeholders,
+            values.len()
+        ));
+    }
+    Ok(string)
+}
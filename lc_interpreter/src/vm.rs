@@ -0,0 +1,177 @@
+use core::fmt;
+use std::{cmp::Ordering, collections::HashMap, io};
+
+use crate::*;
+use lc_core::*;
+
+type VmResult<T> = Result<T, RuntimeError>;
+
+/// A stack machine executing [`Chunk`]s produced by the [`Compiler`].
+pub struct Vm<'a> {
+    stack: Vec<Value>,
+    slots: Vec<Value>,
+    globals: HashMap<Symbol, Value>,
+    output: &'a mut dyn io::Write,
+}
+impl<'a> fmt::Debug for Vm<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vm")
+            .field("stack", &self.stack)
+            .field("slots", &self.slots)
+            .field("globals", &self.globals)
+            .finish()
+    }
+}
+impl<'a> Vm<'a> {
+    pub fn new(output: &'a mut dyn io::Write) -> Self {
+        Self {
+            stack: Vec::new(),
+            slots: Vec::new(),
+            globals: HashMap::new(),
+            output,
+        }
+    }
+
+    pub fn run(&mut self, chunk: &Chunk) -> VmResult<()> {
+        let mut ip = 0;
+        while ip < chunk.code.len() {
+            let span = chunk.spans[ip];
+            ip += 1;
+            match &chunk.code[ip - 1] {
+                OpCode::Constant(lit) => self.stack.push(lit.to_owned().into()),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::DefineGlobal(symbol) => {
+                    let value = self.pop();
+                    self.globals.insert(*symbol, value);
+                }
+                OpCode::GetGlobal(symbol) => {
+                    let value = self
+                        .globals
+                        .get(symbol)
+                        .ok_or_else(|| self.undefined(symbol, span))?;
+                    self.stack.push(value.to_owned());
+                }
+                OpCode::SetGlobal(symbol) => {
+                    let value = self.peek().to_owned();
+                    if !self.globals.contains_key(symbol) {
+                        return Err(self.undefined(symbol, span));
+                    }
+                    self.globals.insert(*symbol, value);
+                }
+                OpCode::DefineLocal => {
+                    let value = self.pop();
+                    self.slots.push(value);
+                }
+                OpCode::GetLocal(slot) => self.stack.push(self.slots[*slot].to_owned()),
+                OpCode::SetLocal(slot) => self.slots[*slot] = self.peek().to_owned(),
+                OpCode::PopLocals(count) => self.slots.truncate(self.slots.len() - count),
+                OpCode::Binary(op) => {
+                    let right = self.pop_literal(span)?;
+                    let left = self.pop_literal(span)?;
+                    let value = Self::binary(left, *op, right)
+                        .map_err(|message| RuntimeError::with_span(message.into(), span))?;
+                    self.stack.push(value.into());
+                }
+                OpCode::Unary(op) => {
+                    let right = self.pop_literal(span)?;
+                    let value = match op {
+                        UnaryOp::Negative => -right,
+                        UnaryOp::Not => Ok(!right),
+                        UnaryOp::BitNot => right.bit_not(),
+                    };
+                    let value = value.map_err(|mut e| {
+                        e.set_span(span);
+                        e
+                    })?;
+                    self.stack.push(value.into());
+                }
+                OpCode::Print(count) => {
+                    let values = self.stack.split_off(self.stack.len() - count);
+                    let values: Vec<String> = values.iter().map(Value::as_str).collect();
+                    writeln!(self.output, "{}", values.join(" ")).unwrap();
+                }
+                OpCode::Interpolate(count) => {
+                    let values = self.stack.split_off(self.stack.len() - count);
+                    let string: String = values.iter().map(Value::as_str).collect();
+                    self.stack
+                        .push(Literal::String(Symbol::string(string)).into());
+                }
+                OpCode::Is(type_name) => {
+                    let value = self.pop();
+                    self.stack
+                        .push(Literal::Bool(value.is_type(*type_name)).into());
+                }
+                OpCode::Jump(target) => ip = *target,
+                OpCode::JumpIfFalse(target) => {
+                    if !self.peek().is_truthy() {
+                        ip = *target;
+                    }
+                }
+                OpCode::JumpIfTrue(target) => {
+                    if self.peek().is_truthy() {
+                        ip = *target;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn binary(left: Literal, op: BinaryOp, right: Literal) -> Result<Literal, &'static str> {
+        if let BinaryOp::Equal | BinaryOp::NotEqual = op {
+            return Ok(Literal::Bool((left == right) == (op == BinaryOp::Equal)));
+        }
+        if let (Literal::String(left), BinaryOp::Plus) = (left, op) {
+            return Ok(Literal::String(left + Symbol::string(right.as_str())));
+        }
+        if let (Literal::String(left), Literal::String(right)) = (&left, &right) {
+            let ordering = left.resolve().cmp(&right.resolve());
+            return match op {
+                BinaryOp::Greater => Ok(Literal::Bool(ordering.is_gt())),
+                BinaryOp::GreaterEqual => Ok(Literal::Bool(ordering.is_ge())),
+                BinaryOp::Less => Ok(Literal::Bool(ordering.is_lt())),
+                BinaryOp::LessEqual => Ok(Literal::Bool(ordering.is_le())),
+                _ => Err("Operands must be two numbers or two strings."),
+            };
+        }
+        let Some(operands) = Operands::new(&left, &right) else {
+            return Err("Operands must be two numbers or two strings.");
+        };
+        // NaN compares as `None`, which makes every ordering false
+        let ordering = operands.compare();
+        match op {
+            BinaryOp::Greater => Ok(Literal::Bool(ordering.is_some_and(Ordering::is_gt))),
+            BinaryOp::GreaterEqual => Ok(Literal::Bool(ordering.is_some_and(Ordering::is_ge))),
+            BinaryOp::Less => Ok(Literal::Bool(ordering.is_some_and(Ordering::is_lt))),
+            BinaryOp::LessEqual => Ok(Literal::Bool(ordering.is_some_and(Ordering::is_le))),
+            BinaryOp::Equal | BinaryOp::NotEqual => unreachable!(),
+            _ => operands.apply(op),
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("value stack underflow")
+    }
+
+    fn pop_literal(&mut self, span: Span) -> VmResult<Literal> {
+        match self.pop() {
+            Value::Literal(lit) => Ok(lit),
+            _ => Err(RuntimeError::with_span(
+                "Operands must be two numbers or two strings.".into(),
+                span,
+            )),
+        }
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("value stack underflow")
+    }
+
+    fn undefined(&self, symbol: &Symbol, span: Span) -> RuntimeError {
+        let names: Vec<String> = self.globals.keys().map(Symbol::to_string).collect();
+        let message = undefined_variable(&symbol.to_string(), names.iter().map(String::as_str));
+        RuntimeError::with_span(message, span)
+    }
+}
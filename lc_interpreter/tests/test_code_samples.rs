@@ -1,7 +1,11 @@
 mod common;
 
+use std::{cell::RefCell, rc::Rc};
+
 use anyhow::Result;
-use common::execute_sample;
+use common::{evaluate_sample, execute_sample, run_sample};
+use lc_core::*;
+use lc_interpreter::*;
 
 #[test]
 fn closure_scope() -> Result<()> {
@@ -28,6 +32,102 @@ global
     Ok(())
 }
 
+#[test]
+fn closures_see_later_global_assignments() -> Result<()> {
+    let source = "\
+let greeting = \"hello\";
+fn greet() { return greeting; }
+let show = fn () { print greet(), limit; };
+let limit = 1;
+greeting = \"hi\";
+show();
+limit = 2;
+let greeting = \"hey\";
+show();
+fn isEven(n) { if (n == 0) return true; return isOdd(n - 1); }
+fn isOdd(n) { if (n == 0) return false; return isEven(n - 1); }
+{
+    fn ev(n) { if (n == 0) return true; return od(n - 1); }
+    fn od(n) { if (n == 0) return false; return ev(n - 1); }
+    print isEven(10), isOdd(7), ev(3);
+}
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+hi 1
+hey 2
+true true false
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn calls_resolve_like_reads() -> Result<()> {
+    let source = "\
+let f = fn () { return 1; };
+{
+    fn g() { return f(); }
+    fn h() { let k = f; return k(); }
+    let f = fn () { return 2; };
+    print g(), h(), f();
+}
+{
+    fn outer() { { return inner() + later; } }
+    fn inner() { return 10; }
+    let later = 5;
+    print outer();
+}
+";
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.eval("let later = 1;")?;
+    run_sample(source, &mut context)?;
+    drop(context);
+    // Only functions declared later in the block are found, other names are looked up as
+    // they were when the function was declared
+    assert_eq!(String::from_utf8_lossy(&output), "1 1 2\n11\n");
+    Ok(())
+}
+
+#[test]
+fn closure_counter() -> Result<()> {
+    let source = "\
+fn makeCounter() {
+    let count = 0;
+    fn increment() {
+        count = count + 1;
+        return count;
+    }
+    return increment;
+}
+let first = makeCounter();
+let second = makeCounter();
+print first(), first(), first();
+print second(), first();
+
+let shared = 0;
+fn bump() { shared = shared + 1; }
+bump();
+bump();
+print shared;
+
+class A { fn name() { return \"a\"; } }
+class B < A {
+    fn later() { return fn() { return super.name() + this.suffix; }; }
+}
+let b = B();
+let name = b.later();
+b.suffix = \"!\";
+print name();
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "1 2 3\n1 4\n2\na!\n");
+    Ok(())
+}
+
 #[test]
 fn block_scope() -> Result<()> {
     let source = "\
@@ -99,6 +199,135 @@ while (x < 5) {
     Ok(())
 }
 
+#[test]
+fn do_while_loop() -> Result<()> {
+    let source = "\
+let x = 10;
+do {
+    print x;
+    x++;
+} while (x < 5);
+do print x; while ((x = x - 4) > 0);
+let n = 0;
+do {
+    n++;
+    if (n == 2) continue;
+    if (n == 4) break;
+    print \"n\", n;
+} while (true);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+10
+11
+7
+3
+n 1
+n 3
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        ("do print 1;", "Expected 'while' after do loop body."),
+        (
+            "do {} while (true)",
+            "Expected ';' after do loop condition.",
+        ),
+        ("do {} while true;", "Expected '(' after 'while'."),
+        (
+            "break; do {} while (false);",
+            "Can't use 'break' outside of a loop",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+    Ok(())
+}
+
+#[test]
+fn foreach_loop() -> Result<()> {
+    let source = "\
+let items = [1, \"two\", [3]];
+for (let item in items) print item;
+let total = 0;
+for (n in [1, 2, 3, 4, 5]) {
+    if (n == 2) continue;
+    if (n == 5) break;
+    total += n;
+}
+print total;
+for (let c in \"hi\") print c;
+for (let c in word) print c;
+for (let x in []) print \"never\";
+let fns = [null, null];
+let k = 0;
+for (let i in [1, 2]) {
+    fns[k] = fn () { return i; };
+    k++;
+}
+print fns[0](), fns[1]();
+let grow = [1, 2];
+for (let x in grow) grow[0] = grow[0] + x;
+print grow;
+let item = \"outer\";
+for (let item in [1]) {}
+print item;
+";
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    // Characters, not bytes, of a string the lexer can't yet read in source
+    context.environment.define(
+        &Ident::new("word".into(), Span::default()),
+        Literal::String(Symbol::string_str("héllo")).into(),
+    );
+    run_sample(source, &mut context)?;
+    drop(context);
+    let expect = "\
+1
+two
+[3]
+8
+h
+i
+h
+é
+l
+l
+o
+1 2
+[4, 2]
+outer
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        (
+            "for (let x in 5) {}",
+            "Can only loop over arrays and strings, not '5'.",
+        ),
+        (
+            "for (let x in {:}) {}",
+            "Can only loop over arrays and strings, not '{}'.",
+        ),
+        ("for (let x in [1]) x;\nprint x;", "Undefined variable 'x'"),
+        ("for (let x in [1) {}", "Expected ']'"),
+        (
+            "for (let x in [1] {}",
+            "Expected ')' after for-in iterable.",
+        ),
+        (
+            "for (let x in [1]) { fn f() { break; } }",
+            "Can't use 'break' outside of a loop",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+    Ok(())
+}
+
 #[test]
 fn shadowing() -> Result<()> {
     let source = "\
@@ -183,6 +412,61 @@ after
     Ok(())
 }
 
+/// Resolves the sample without running it, returning any errors up to resolution
+fn resolve_sample(source: &str, allow_global_redeclaration: bool) -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let mut issues = TranslationErrors::new();
+    let (tokens, mut errs) = Scanner::new(source.to_string()).scan_tokens();
+    issues.merge(&mut errs);
+    let (statements, mut errs) = Parser::new(tokens).parse();
+    issues.merge(&mut errs);
+    let mut resolver =
+        Resolver::new(&mut context).allow_global_redeclaration(allow_global_redeclaration);
+    let (_, mut errs) = resolver.resolve(&statements);
+    issues.merge(&mut errs);
+    issues.check()?;
+    Ok(())
+}
+
+#[test]
+fn redefine_var_policy() {
+    let source = "\
+let x = \"before\";
+fn f() {}
+let x = \"after\";
+fn f() {}
+    ";
+    assert!(resolve_sample(source, true).is_ok());
+    let err = resolve_sample(source, false).unwrap_err().to_string();
+    assert_eq!(
+        err.matches("Already a variable with this name in this scope.")
+            .count(),
+        2,
+        "{}",
+        err
+    );
+    assert!(
+        err.contains("[line 3]") && err.contains("[line 4]"),
+        "{}",
+        err
+    );
+
+    // Distinct names, and shadowing in inner scopes, are unaffected
+    let source = "\
+let x = 1;
+let y = 2;
+{
+    let x = 3;
+}
+fn g(x) {
+    let y = x;
+}
+    ";
+    assert!(resolve_sample(source, false).is_ok());
+    assert!(resolve_sample("{ let z = 1; let z = 2; }", true).is_err());
+}
+
 #[test]
 fn evaluate_ver_expr() -> Result<()> {
     let source = "\
@@ -386,3 +670,2535 @@ if (x == 1) var y = 2;
     let mut output: Vec<u8> = Vec::new();
     execute_sample(source, &mut output).unwrap();
 }
+
+#[test]
+fn block_expression() -> Result<()> {
+    let source = "\
+let a = 10;
+let x = {
+    let a = 1;
+    a + 1
+};
+print x;
+print a;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+2
+10
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn block_expression_without_value() -> Result<()> {
+    let source = "\
+let x = {
+    let a = 1;
+    print a;
+};
+print x;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+1
+null
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn if_expression() -> Result<()> {
+    let source = "\
+fn sign(x) {
+    return if (x < 0) -1 else 1;
+}
+print sign(-5);
+print sign(5);
+let label = if (sign(0) == 1) { let s = \"non-negative\"; s } else \"negative\";
+print label;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+-1
+1
+non-negative
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn if_expression_missing_else() {
+    let source = "\
+let x = if (true) 1;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn if_expression_as_block_value() -> Result<()> {
+    let source = "\
+let v = { if (true) 1 else 2 };
+fn size(n) {
+    return {
+        if (n > 5) { print \"checked\"; } else { print \"skipped\"; }
+        if (n > 100) \"huge\" else if (n > 5) { let s = \"big\"; s } else \"small\"
+    };
+}
+print v, size(50), size(1);
+let w = {
+    if (v == 1) print \"one\";
+    if (v == 2) { print \"two\"; } else { print \"not two\"; }
+};
+print w;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+checked
+skipped
+1 big small
+one
+not two
+null
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    // An if with a branch that is neither a statement nor an expression reports the statement's
+    // error
+    let err = execute_sample("let x = { if (true) 1 };", &mut output).unwrap_err();
+    let err = err.downcast_ref::<TranslationErrors>().unwrap();
+    assert_eq!(err.issues().len(), 1, "{}", err);
+    assert_eq!(err.issues()[0].message, "Expected ';' after expression.");
+    Ok(())
+}
+
+#[test]
+fn chained_comparison() -> Result<()> {
+    let source = "\
+fn x() {
+    print \"x\";
+    return 3;
+}
+print 1 < x() < 5;
+print 1 < x() <= 2;
+print 5 > 4 >= 4 > 3;
+print 5 > 4 >= 4 > 4;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+x
+true
+x
+false
+true
+false
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn recursive_function_expression() -> Result<()> {
+    let source = "\
+let factorial = fn fac(n) {
+    if (n <= 1) return 1;
+    return n * fac(n - 1);
+};
+print factorial(5);
+print factorial(10);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+120
+3628800
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn function_expression_name_not_in_enclosing_scope() {
+    let source = "\
+let factorial = fn fac(n) {
+    return 1;
+};
+print fac(5);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn immediately_invoked_function() -> Result<()> {
+    let source = "\
+let secret = \"outer\";
+let answer = (fn() {
+    let secret = 40;
+    return secret + 2;
+})();
+print answer;
+print secret;
+print (fn(a, b) { return a * b; })(6, 7);
+print fn() { return \"direct\"; }();
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+42
+outer
+42
+direct
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn enum_variant_equality() -> Result<()> {
+    let source = "\
+enum Color { Red, Green, Blue, }
+enum Light { Red, Amber, Green }
+let current = Color.Green;
+print current == Color.Green;
+print current == Color.Red;
+print current != Color.Blue;
+print Color.Red == Light.Red;
+print Color.Red == \"Red\";
+let red = Color.Red;
+{
+  enum Color { Red }
+  print red == Color.Red, red == red;
+}
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+true
+false
+true
+false
+false
+false true
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+
+    // Redeclaring an enum makes new variants
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.eval("enum A { X } let a = A.X;")?;
+    context.eval("enum A { X }")?;
+    assert_eq!(
+        context.eval("a == A.X;")?,
+        Value::Literal(Literal::Bool(false))
+    );
+
+    let source = "enum C { R, G, R }";
+    let err = execute_sample(source, &mut output).unwrap_err();
+    let err = err.downcast_ref::<TranslationErrors>().unwrap();
+    let issue = &err.issues()[0];
+    assert_eq!(issue.message, "Duplicate variant 'R' in enum 'C'.");
+    assert_eq!(issue.span.start, source.rfind('R').unwrap());
+    Ok(())
+}
+
+#[test]
+fn enum_variant_printing() -> Result<()> {
+    let source = "\
+enum Color { Red, Green, Blue }
+print Color.Blue;
+print Color;
+print typeof(Color.Red);
+print typeof(Color);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Color.Blue
+<enum Color>
+Color
+Enum
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn enum_undefined_variant() {
+    let source = "\
+enum Color { Red, Green, Blue }
+print Color.Purple;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn struct_construction() -> Result<()> {
+    let source = "\
+struct Point { x, y }
+struct Unit {}
+let p = Point { y: 2, x: 1 };
+print p;
+print Unit {};
+print typeof(p);
+print p == p;
+print p == Point { x: 1, y: 2 };
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Point { x: 1, y: 2 }
+Unit {}
+Point
+true
+false
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn struct_field_read_write() -> Result<()> {
+    let source = "\
+struct Point { x, y }
+let p = Point { x: 1, y: 2 };
+let alias = p;
+print p.x + p.y;
+p.x = 10;
+print alias.x;
+print alias.y = p.x * 2;
+print p;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+3
+10
+20
+Point { x: 10, y: 20 }
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn struct_missing_field() {
+    let source = "\
+struct Point { x, y }
+let p = Point { x: 1 };
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn struct_duplicate_field() {
+    let source = "struct P { x, y, x }\nprint 1;";
+    let mut output: Vec<u8> = Vec::new();
+    let err = execute_sample(source, &mut output).unwrap_err();
+    let err = err.downcast_ref::<TranslationErrors>().unwrap();
+    let issue = &err.issues()[0];
+    assert_eq!(issue.message, "Duplicate field 'x' in struct 'P'.");
+    assert_eq!(issue.span.start, source.rfind('x').unwrap());
+    assert!(output.is_empty());
+}
+
+#[test]
+fn tuple_construction() -> Result<()> {
+    let source = "\
+let pair = (1, \"two\");
+print pair;
+print (3);
+print (3,);
+print (1, (2, 3)) == (1, (2, 3));
+print typeof(pair);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+(1, \"two\")
+3
+(3,)
+true
+Tuple
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn tuple_destructuring() -> Result<()> {
+    let source = "\
+fn divmod(a, b) {
+    return (a / b, a - b * 2);
+}
+let (q, r) = divmod(7, 2);
+print q;
+print r;
+{
+    let (x, y, z) = (\"x\", \"y\", \"z\");
+    print x + y + z;
+}
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+3
+3
+xyz
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn tuple_destructuring_arity_mismatch() {
+    let source = "\
+let (x, y) = (1, 2, 3);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+#[should_panic]
+fn tuple_destructuring_non_tuple() {
+    let source = "\
+let (x, y) = 5;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn print_multiple_values() -> Result<()> {
+    let source = "\
+print 1, \"two\", 3;
+print (1, 2), 3;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+1 two 3
+(1, 2) 3
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn logical_short_circuit() -> Result<()> {
+    let source = "\
+fn touch(label, value) {
+    print label;
+    return value;
+}
+print false and touch(\"and\", true);
+print true or touch(\"or\", false);
+print false && touch(\"&&\", true);
+print true || touch(\"||\", false);
+print true and touch(\"and\", 1);
+print false or touch(\"or\", 2);
+print true && touch(\"&&\", 3);
+print false || touch(\"||\", 4);
+print null or false;
+print 1 && 2 || touch(\"mixed\", 3);
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+false
+true
+false
+true
+and
+1
+or
+2
+&&
+3
+||
+4
+false
+2
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn binding_free_blocks_skip_scopes() -> Result<()> {
+    let source = "\
+let a = 1;
+{
+    {
+        { a = a + 1; print a; }
+        print { a * 10 };
+    }
+}
+{
+    let b = a;
+    { print b; }
+}
+print a;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    run_sample(source, &mut context)?;
+    // Only the block declaring `b` needs a scope of its own
+    assert_eq!(context.environment.scopes_created(), 1);
+    let expect = "\
+2
+20
+2
+2
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn resolution_depths() -> Result<()> {
+    let source = "\
+let x = \"global\";
+fn outer() {
+    let x = \"outer\";
+    fn middle() {
+        {
+            let y = x;
+            {
+                let x = \"inner\";
+                print y, x;
+            }
+            x = \"reassigned\";
+        }
+        print x;
+    }
+    middle();
+    print x;
+}
+outer();
+print x;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+outer inner
+reassigned
+reassigned
+global
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn recursive_function_with_large_body() -> Result<()> {
+    let padding: String = (0..200)
+        .map(|i| format!("    let t{} = n + {};\n", i, i))
+        .collect();
+    let source = format!(
+        "\
+fn fib(n) {{
+{}    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}}
+print fib(15);
+let f = fib;
+print f(10);
+    ",
+        padding
+    );
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(&source, &mut output)?;
+    let expect = "\
+610
+55
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn property_assignment_targets() -> Result<()> {
+    let source = "\
+struct Counter { count, label }
+let c = Counter { count: 1, label: \"hits\" };
+c.label = \"clicks\";
+c.count += 4;
+c.count *= 2;
+c.count++;
+c.count--;
+c.count--;
+print c;
+fn wrap(counter) { return counter; }
+wrap(c).count = 0;
+print c.count;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Counter { count: 9, label: \"clicks\" }
+0
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn invalid_assignment_target() {
+    let source = "\
+fn f() {}
+f() = 1;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output).unwrap();
+}
+
+#[test]
+fn defined_builtin() -> Result<()> {
+    let source = "\
+print defined(\"x\");
+let x = 1;
+print defined(\"x\");
+fn f(param) {
+    let local = 2;
+    return (defined(\"param\"), defined(\"local\"), defined(\"x\"));
+}
+print f(0);
+print defined(\"local\");
+{
+    let shadow = 3;
+    print defined(\"shadow\");
+}
+print defined(\"shadow\"), defined(\"typeof\");
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+false
+true
+(true, true, true)
+false
+true
+false true
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn interpret_value_of_final_expression() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let value = evaluate_sample("let x = 20; print x; x * 2 + 2;", &mut context)?;
+    assert_eq!(value, Value::Literal(Literal::Number(42.0)));
+
+    let value = evaluate_sample("fn f() { return (1, 2); } f();", &mut context)?;
+    assert_eq!(value.as_str(), "(1, 2)");
+    drop(context);
+    assert_eq!(output, b"20\n");
+    Ok(())
+}
+
+#[test]
+fn interpret_value_of_final_statement() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let value = evaluate_sample("1 + 1; print 3;", &mut context)?;
+    assert_eq!(value, Value::Literal(Literal::Null));
+
+    let value = evaluate_sample("", &mut context)?;
+    assert_eq!(value, Value::Literal(Literal::Null));
+    assert!(evaluate_sample("1; missing;", &mut context).is_err());
+    drop(context);
+    assert_eq!(output, b"3\n");
+    Ok(())
+}
+
+#[test]
+fn eval_keeps_definitions_between_calls() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.eval("let config = { \"port\": 80 }; fn port() { return config[\"port\"]; }")?;
+    assert_eq!(
+        context.eval("port() + 1;")?,
+        Value::Literal(Literal::Int(81))
+    );
+
+    let err = context.eval("let = 1;").unwrap_err();
+    assert!(err.downcast_ref::<TranslationErrors>().is_some());
+    let err = context.eval("port() + nil;").unwrap_err();
+    assert!(err.downcast_ref::<RuntimeError>().is_some());
+    Ok(())
+}
+
+#[test]
+fn callable_printing() -> Result<()> {
+    let source = "\
+fn add(a, b) {
+    return a + b;
+}
+fn none() {}
+let named = fn twice(x) { return x * 2; };
+print add, none;
+print named;
+print fn (x) { return x; };
+print clock, typeof, sleep, defined;
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+<fn add/2> <fn none/0>
+<fn twice/1>
+<lambda/1>
+<native fn clock/0> <native fn typeof/1> <native fn sleep/1> <native fn defined/1>
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn discard_identifier() -> Result<()> {
+    let source = "\
+fn second(_, b) {
+    let _ = b * 10;
+    let _ = \"ignored\";
+    return b;
+}
+{
+    let _ = 1;
+    let (_, middle, _) = (1, 2, 3);
+    let _ = 4;
+    print middle;
+}
+print second(1, 2);
+    ";
+    assert!(resolve_sample(source, false).is_ok());
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(output, b"2\n2\n");
+    Ok(())
+}
+
+#[test]
+fn discard_identifier_read() {
+    for source in ["let _ = 1;\nprint _;", "{ let _ = 1; let x = _ + 1; }"] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(
+            err.contains("ResolveError: Can't read from '_', it only discards values."),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn builtin_arity_errors() {
+    let cases = [
+        ("clock(1);", 0, 1),
+        ("typeof();", 1, 0),
+        ("typeof(1, 2);", 1, 2),
+        ("sleep();", 1, 0),
+        ("defined(\"a\", \"b\");", 1, 2),
+        ("fn f(a) {} f();", 1, 0),
+    ];
+    for (source, expected, given) in cases {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        let message = format!(
+            "RuntimeError: Function expected {} arguments but was given {}",
+            expected, given
+        );
+        assert!(err.contains(&message), "{}: {}", source, err);
+    }
+}
+
+/// A host builtin that calls back into the script: `apply(f, x)` is `f(x)`
+#[derive(Clone, Debug, Default)]
+struct Apply;
+impl<'a> Callable<'a> for Apply {
+    fn call(&mut self, interpreter: &'a mut Interpreter, span: Span, arguments: &[Value]) -> Throw {
+        if let Err(throw) = check_arity(span, self.arity(), arguments) {
+            return throw;
+        }
+        match interpreter.call_value(&arguments[0], &arguments[1..]) {
+            Ok(value) => value.into(),
+            Err(err) => err.into(),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn as_str(&self) -> String {
+        "<native fn apply/2>".to_string()
+    }
+}
+
+#[test]
+fn native_callback_into_script() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let apply = Ident::new("apply".into(), Span::default());
+    context
+        .environment
+        .define(&apply, Value::Function(Box::new(Apply)));
+    let source = "\
+fn double(x) { return x * 2; }
+let offset = 1;
+print apply(double, 20);
+print apply(fn (x) { return x + offset; }, 41);
+print apply(typeof, 1);
+    ";
+    run_sample(source, &mut context)?;
+
+    let err = run_sample("apply(5, 1);", &mut context).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Can only call functions, not '5'."));
+    let err = run_sample("apply(fn (a, b) {}, 1);", &mut context).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Function expected 2 arguments but was given 1"));
+
+    let value = context.call_value(
+        &context.environment.get(&apply)?,
+        &[
+            context
+                .environment
+                .get(&Ident::new("double".into(), Span::default()))?,
+            Value::Literal(Literal::Number(4.0)),
+        ],
+    )?;
+    assert_eq!(value, Value::Literal(Literal::Number(8.0)));
+    drop(context);
+    assert_eq!(output, b"40\n42\nInt\n");
+    Ok(())
+}
+
+#[test]
+fn string_interpolation() -> Result<()> {
+    let source = "\
+let name = \"Ada\";
+let count = 2;
+print \"Hello ${name}, you have ${count * 2 + 1} messages\";
+print \"${count}${count}\", \"[${ \"nested ${name}\" }]\";
+print \"${(1, null)} ${fn () {}} ${count > 1}\";
+print typeof(\"${count}\") + \"!\";
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Hello Ada, you have 5 messages
+22 [nested Ada]
+(1, null) <lambda/0> true
+String!
+"
+    .as_bytes()
+    .to_vec();
+    assert_eq!(output, expect);
+    Ok(())
+}
+
+#[test]
+fn string_interpolation_malformed() {
+    for (source, message) in [
+        (
+            "print \"a ${1 + }\";",
+            "ParseError: Expected '}' after interpolated expression.",
+        ),
+        (
+            "print \"a ${1 2}\";",
+            "ParseError: Expected '}' after interpolated expression.",
+        ),
+        ("print \"a ${1\";", "LexError: Unterminated '${' in string"),
+        ("print \"${missing}\";", "Undefined variable 'missing'"),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+}
+
+#[test]
+fn is_type_checks() -> Result<()> {
+    let source = "\
+enum Color { Red }
+struct Point { x, y }
+let values = (1, \"one\", true, null, clock, fn() {}, (1, 2), Color, Point);
+let (n, s, b, u, native, lambda, pair, e, st) = values;
+print n is Number, s is String, b is Bool, u is Null;
+print native is Function, lambda is Function, pair is Tuple, e is Enum, st is Struct;
+print n is String, u is Bool, s is Number, lambda is Tuple;
+print Color.Red is Enum, Point { x: 1, y: 2 } is Struct;
+print 1 < 2 is Bool, !(n is Number), n is Number == true;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+true true true true
+true true true true true
+false false false false
+false false
+true false true
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn is_unknown_type() {
+    for (source, message) in [
+        ("print 1 is Float;", "ParseError: Unknown type 'Float'."),
+        (
+            "print 1 is Numbr;",
+            "ParseError: Unknown type 'Numbr'. Did you mean 'Number'?",
+        ),
+        (
+            "print 1 is 2;",
+            "ParseError: Expected a type name after 'is'.",
+        ),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+}
+
+#[test]
+fn function_identity() -> Result<()> {
+    let source = "\
+fn f() {}
+let g = f;
+print f == g, f == fn() {}, f != g;
+fn make() { return fn() {}; }
+let a = make();
+let b = make();
+print a == a, a == b;
+fn r() { return r; }
+let named = fn self() { return self; };
+print r() == r, named() == named;
+print clock == clock, clock == typeof, (f, 1) == (g, 1);
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+true false false
+true false
+true true
+true false true
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+// Records hash by identity, so their interior mutability can't change a key's hash
+#[allow(clippy::mutable_key_type)]
+fn function_map_keys() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let source = "fn f() {} let g = f; let h = fn() {}; (f, g, h, clock, clock);";
+    let Value::Tuple(functions) = evaluate_sample(source, &mut context)? else {
+        panic!("expected a tuple");
+    };
+    let mut names = std::collections::HashMap::new();
+    for (function, name) in functions
+        .iter()
+        .zip(["f", "g", "h", "clock", "clock again"])
+    {
+        names.insert(function.clone(), name);
+    }
+    assert_eq!(names.len(), 3);
+    assert_eq!(names[&functions[0]], "g");
+    assert_eq!(names[&functions[2]], "h");
+    assert_eq!(names[&functions[3]], "clock again");
+    Ok(())
+}
+
+#[test]
+fn string_concatenation_coerces_right_operand() -> Result<()> {
+    let source = "\
+print \"count: \" + 5, \"done: \" + true, \"x is \" + null, \"pi \" + 3.5 + 1;
+print \"\" + 1 + 2, \"\" + (1 + 2), \"a\" + \"b\";
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "count: 5 done: true x is null pi 3.51\n12 3 ab\n"
+    );
+
+    for (source, message) in [
+        ("print 5 + \"x\";", "Right operand must be a number."),
+        (
+            "print true + \"x\";",
+            "Operands must be two numbers or two strings.",
+        ),
+        (
+            "print \"f\" + clock;",
+            "Did you forget to call the function?",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+    Ok(())
+}
+
+#[test]
+fn trace_statements() -> Result<()> {
+    let source = "\
+let x = 1;
+fn bump(n) { return n + 1; }
+while (x < 3) x = bump(x);
+if (x == 3) print x, \"done\";";
+    let spans = Rc::new(RefCell::new(Vec::new()));
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let recorded = Rc::clone(&spans);
+    context.set_trace(Box::new(move |span| recorded.borrow_mut().push(span)));
+    run_sample(source, &mut context)?;
+    drop(context);
+    assert_eq!(output, b"3 done\n");
+
+    let traced: Vec<(usize, &str)> = spans
+        .borrow()
+        .iter()
+        .map(|span| (span.line, &source[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        traced,
+        vec![
+            (1, "x = 1"),
+            (2, "bump"),
+            (3, "x < 3"),
+            (3, "x = bump(x)"),
+            (2, "n + 1"),
+            (3, "x = bump(x)"),
+            (2, "n + 1"),
+            (4, "x == 3"),
+            (4, "x, \"done\""),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_final_expression_statement() -> Result<()> {
+    let source = "\
+let x = 1;
+fn show() { print x; }
+show();
+x + 1;";
+    let spans = Rc::new(RefCell::new(Vec::new()));
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let recorded = Rc::clone(&spans);
+    context.set_trace(Box::new(move |span| recorded.borrow_mut().push(span)));
+    run_sample(source, &mut context)?;
+    drop(context);
+    assert_eq!(output, b"1\n");
+
+    let traced: Vec<(usize, &str)> = spans
+        .borrow()
+        .iter()
+        .map(|span| (span.line, &source[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        traced,
+        vec![(1, "x = 1"), (2, "show"), (3, "()"), (2, "x"), (4, "x + 1")]
+    );
+    Ok(())
+}
+
+#[test]
+fn modulo_operator() -> Result<()> {
+    let source = "\
+let x = 17;
+x %= 5;
+print x, 10 % 3 == 1;
+struct Counter { n }
+let c = Counter { n: 9 };
+c.n %= 4;
+print c.n;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "2 true\n1\n");
+
+    for (source, message) in [
+        ("print \"a\" % 2;", "Left operand must be a number."),
+        ("print 2 % true;", "Right operand must be a number."),
+        ("print % 2;", "Binary operator '%' missing operand(s)"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+    Ok(())
+}
+
+#[test]
+fn break_and_continue() -> Result<()> {
+    let source = "\
+let i = 0;
+while (true) {
+    i++;
+    if (i % 2 == 0) continue;
+    if (i > 7) break;
+    print i;
+}
+for (let j = 0; j < 3; j++) {
+    for (let k = 0; k < 3; k++) {
+        if (k == j) continue;
+        if (k > j) break;
+        print j, k;
+    }
+}
+for (let n = 0; n < 2; n = n + 1) {
+    let n = 10;
+    print n;
+}
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+1
+3
+5
+7
+1 0
+2 0
+2 1
+10
+10
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn break_and_continue_outside_loop() {
+    for (source, message) in [
+        ("break;", "Can't use 'break' outside of a loop"),
+        (
+            "if (true) { continue; }",
+            "Can't use 'continue' outside of a loop",
+        ),
+        (
+            "while (true) { fn f() { break; } f(); }",
+            "Can't use 'break' outside of a loop",
+        ),
+        (
+            "for (;;) { let g = fn() { continue; }; }",
+            "Can't use 'continue' outside of a loop",
+        ),
+        ("while (true) break", "Expected ';' after 'break'."),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+}
+
+#[test]
+fn ternary_evaluates_taken_branch_only() -> Result<()> {
+    let source = "\
+let calls = 0;
+fn count(value) { calls++; return value; }
+let x = true ? count(\"yes\") : count(\"no\");
+let y = null ? count(1) : false ? count(2) : count(3);
+let z;
+z = calls == 2 ? \"ok\" : \"wrong\";
+print x, y, calls, z;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "yes 3 2 ok\n");
+    Ok(())
+}
+
+#[test]
+fn arrays() -> Result<()> {
+    let source = "\
+let a = [1, \"two\", [3, 4],];
+let b = a;
+b[0] = 10;
+a[2][1] += 1;
+a[0]++;
+print a, b[0], a[2][1], [], [null][0];
+fn fill(array, value) { array[1] = value; }
+fill(a, true);
+print b, typeof(a), a is Array, a == b, a == [11, true, [3, 5]];
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+[11, \"two\", [3, 5]] 11 5 [] null
+[11, true, [3, 5]] Array true true false
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn compound_assignment_evaluates_target_once() -> Result<()> {
+    let source = "\
+let n = 0;
+fn i() { n = n + 1; return 0; }
+let a = [10];
+a[i()] += 1;
+a[i()]++;
+print a, n;
+class P {}
+let p = P();
+p.x = 1;
+let gets = 0;
+fn get(object) { gets++; return object; }
+get(p).x += 1;
+get(p).x--;
+print p.x, gets;
+let grid = [[1]];
+print grid[i()][0] *= 5, grid, n;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "[12] 2\n1 2\n5 [[5]] 3\n");
+    Ok(())
+}
+
+#[test]
+fn cyclic_collections_print() -> Result<()> {
+    let source = "\
+let a = [1];
+a[0] = a;
+let m = {\"n\": 1};
+m[\"self\"] = m;
+m[\"list\"] = [m, a];
+print a, m;
+let shared = [2];
+print [shared, shared], str(a);
+struct Node { next }
+let node = Node { next: null };
+node.next = [node];
+print node;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+[[...]] {\"n\": 1, \"self\": {...}, \"list\": [{...}, [[...]]]}
+[[2], [2]] [[...]]
+Node { next: [Node {...}] }
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn array_index_errors() {
+    for (source, message, index) in [
+        (
+            "let a = [1, 2];\nprint a[2];",
+            "Index 2 is out of bounds for an array of length 2.",
+            "2",
+        ),
+        (
+            "let a = [1, 2];\na[0 - 1] = 0;",
+            "Index -1 is out of bounds for an array of length 2.",
+            "0 - 1",
+        ),
+        (
+            "let a = [1];\nprint a[0.5];",
+            "Array index must be a whole number.",
+            "0.5",
+        ),
+        (
+            "let a = [1];\nprint a[\"0\"];",
+            "Array index must be a number.",
+            "\"0\"",
+        ),
+        (
+            "let a = 1;\nprint a[0];",
+            "Only arrays and maps can be indexed.",
+            "a",
+        ),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        let span = err.span().unwrap();
+        assert_eq!(span.line, 2, "{}", source);
+        assert_eq!(&source[span.start..span.end], index, "{}", source);
+    }
+}
+
+#[test]
+fn maps() -> Result<()> {
+    let source = "\
+let m = {\"one\": 1, 2: \"two\", true: [3], null: null,};
+let alias = m;
+alias[\"four\"] = 4;
+m[\"one\"] += 10;
+m[2] = m[2] + \"!\";
+print m[\"one\"], m[2], m[true][0], m[\"four\"], m[\"missing\"], m[-0] == m[0];
+let empty = {:};
+empty[1 + 1] = \"computed\";
+print empty, typeof(m), m is Map, m == alias, {:} == {:};
+let block = { 1 };
+print block;
+let k = \"a\";
+print {k: 1, \"b\": k}, {\"a\": 1, k: 2};
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+11 two! 3 4 null true
+{2: \"computed\"} Map true true false
+1
+{\"a\": 1, \"b\": \"a\"} {\"a\": 2}
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn map_key_errors() {
+    for (source, message, key) in [
+        (
+            "let m = {1: 2};\nprint m[clock];",
+            "Map keys must be strings, numbers, booleans or null, not '<native fn clock/0>'.",
+            "clock",
+        ),
+        (
+            "let m = {1: 2};\nm[[1]] = 2;",
+            "Map keys must be strings, numbers, booleans or null, not '[1]'.",
+            "[1]",
+        ),
+        (
+            "let m = {\"a\": 1,\n(1, 2): 2};",
+            "Map keys must be strings, numbers, booleans or null, not '(1, 2)'.",
+            "(1, 2)",
+        ),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        let span = err.span().unwrap();
+        assert_eq!(span.line, 2, "{}", source);
+        assert_eq!(&source[span.start..span.end], key, "{}", source);
+    }
+}
+
+#[test]
+fn maps_keep_insertion_order() -> Result<()> {
+    let source = "\
+let m = {\"b\": 2, \"c\": [3], \"a\": 1};
+print m;
+m[\"d\"] = 4;
+m[\"b\"] = 20;
+m[0] = null;
+print m;
+print keys(m), values(m);
+print keys({:}), values({:}), len(keys(m)) == len(m);
+let ks = keys(m);
+push(ks, \"e\");
+print len(m);
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+{\"b\": 2, \"c\": [3], \"a\": 1}
+{\"b\": 20, \"c\": [3], \"a\": 1, \"d\": 4, 0: null}
+[\"b\", \"c\", \"a\", \"d\", 0] [20, [3], 1, 4, null]
+[] [] true
+5
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        ("keys([1]);", "keys expects a map"),
+        ("values(\"ab\");", "values expects a map"),
+        ("keys();", "Function expected 1 arguments but was given 0"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn strings_in_collections_are_escaped() -> Result<()> {
+    let source = r#"
+let s = "a\nb";
+print s;
+print [s, "say \"hi\"", "a, b", "1", 1], (s,), {"a\nb": "\t"};
+"#;
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = r#"a
+b
+["a\nb", "say \"hi\"", "a, b", "1", 1] ("a\nb",) {"a\nb": "\t"}
+"#;
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn string_comparison() -> Result<()> {
+    let source = "\
+print \"apple\" < \"banana\", \"banana\" > \"apple\", \"apple\" > \"banana\";
+print \"same\" < \"same\", \"same\" <= \"same\", \"same\" >= \"same\", \"same\" > \"same\";
+print \"app\" < \"apple\", \"apple\" > \"app\", \"\" < \"a\";
+print \"Zebra\" < \"apple\", \"a\" < \"B\", \"abc\" < \"abd\" < \"abe\";
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+true true false
+false true true false
+true true true
+true false true
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for source in ["print \"a\" < 1;", "print 1 >= \"a\";"] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(
+            err.message(),
+            "Can only compare two numbers or two strings.",
+            "{}",
+            source
+        );
+        let span = err.span().unwrap();
+        assert_eq!(&source[span.start..span.end], &source[6..source.len() - 1]);
+    }
+    Ok(())
+}
+
+#[test]
+fn anonymous_functions_as_values() -> Result<()> {
+    let source = "\
+fn apply(f, x) { return f(x); }
+let f = fn(x) { return x + 1; };
+let offset = 10;
+let shift = fn(x) { return x + offset; };
+offset = 20;
+print apply(f, 1), apply(shift, 1), apply(fn(x) { return x * x; }, 5);
+print f;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "2 21 25\n<lambda/1>\n");
+    Ok(())
+}
+
+#[test]
+fn class_instances() -> Result<()> {
+    let source = "\
+class Point {}
+let p = Point();
+let alias = p;
+p.x = 1;
+alias.y = p.x + 1;
+p.x += 10;
+print p.x, p.y, p == alias, p == Point(), typeof(p);
+print Point, p, Point == Point;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+11 2 true false Point
+<class Point> <Point instance> true
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn typeof_names_each_type() -> Result<()> {
+    let source = "\
+class Shape {}
+class Circle < Shape { fn area() { return 3; } }
+struct Pair { a, b }
+enum Color { Red }
+fn f() {}
+print typeof(\"s\"), typeof(1), typeof(1.5), typeof(true), typeof(null);
+print typeof(f), typeof(fn() {}), typeof(Circle().area), typeof(Circle);
+print typeof(clock), typeof(typeof);
+print typeof(Circle()), typeof(Shape()), typeof(Pair { a: 1, b: 2 }), typeof(Pair);
+print typeof(Color), typeof(Color.Red), typeof((1, 2)), typeof([]), typeof({:});
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+String Int Number Bool Null
+Function Function Function Function
+NativeFunction NativeFunction
+Circle Shape Pair Struct
+Enum Color Tuple Array Map
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn class_instance_errors() {
+    for (source, message, text) in [
+        (
+            "class Point {}\nprint Point().x;",
+            "Undefined property 'x' on '<Point instance>'.",
+            "x",
+        ),
+        (
+            "class Point {}\nPoint(1);",
+            "Function expected 0 arguments but was given 1",
+            "",
+        ),
+        (
+            "let n = 1;\nn.x = 2;",
+            "Only records and instances have fields.",
+            "n",
+        ),
+        (
+            "let n = 1;\nprint n.x;",
+            "Only enums, records and instances have properties.",
+            "n",
+        ),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        if !text.is_empty() {
+            let span = err.span().unwrap();
+            assert_eq!(span.line, 2, "{}", source);
+            assert_eq!(&source[span.start..span.end], text, "{}", source);
+        }
+    }
+}
+
+#[test]
+fn class_methods() -> Result<()> {
+    let source = "\
+let greeting = \"Hello\";
+class Greeter {
+    fn greet(name) { return greeting + \", \" + name; }
+    fn twice(me, name) { return me.greet(name) + \" \" + me.greet(name); }
+}
+let g = Greeter();
+print g.greet(\"Ada\");
+print g.twice(g, \"Bob\");
+let bound = g.greet;
+print bound(\"Cy\"), bound == g.greet, g.greet == Greeter().greet;
+g.greet = fn(name) { return \"Hi \" + name; };
+print g.greet(\"Di\"), Greeter().greet(\"Di\");
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Hello, Ada
+Hello, Bob Hello, Bob
+Hello, Cy true true
+Hi Di Hello, Di
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    let source = "class Greeter { fn greet(name) {} }\nGreeter().greet();";
+    let err = execute_sample(source, &mut output).unwrap_err().to_string();
+    assert!(
+        err.contains("Function expected 1 arguments but was given 0"),
+        "{}",
+        err
+    );
+    Ok(())
+}
+
+#[test]
+fn class_this() -> Result<()> {
+    let source = "\
+class Counter {
+    fn add(n) {
+        this.count = this.count + n;
+        return this;
+    }
+    fn reset() { this.count = 0; return this; }
+    fn twice(n) { return this.add(n).add(n).count; }
+}
+let c = Counter().reset();
+print c.twice(2), c.add(1).count, c.add == c.add;
+let add = c.add;
+add(10);
+print c.count, Counter().reset().twice(5);
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "4 5 true\n15 10\n");
+    Ok(())
+}
+
+#[test]
+fn this_outside_method() {
+    for source in ["print this;", "fn f() { return this; }"] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(
+            err.contains("Can't use 'this' outside of a method"),
+            "{}: {}",
+            source,
+            err
+        );
+    }
+}
+
+#[test]
+fn class_inheritance() -> Result<()> {
+    let source = "\
+class Animal {
+    fn name() { return \"animal\"; }
+    fn speak() { return this.name() + \" makes a sound\"; }
+}
+class Dog < Animal {
+    fn name() { return \"dog\"; }
+    fn speak() { return super.speak() + \", woof\"; }
+}
+class Puppy < Dog {
+    fn speak() { return super.speak() + \", yip\"; }
+    fn base() { return super.name; }
+}
+print Animal().speak();
+print Dog().speak();
+print Puppy().speak(), Puppy().base()();
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+animal makes a sound
+dog makes a sound, woof
+dog makes a sound, woof, yip dog
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+    Ok(())
+}
+
+#[test]
+fn class_inheritance_errors() {
+    for (source, message) in [
+        ("class A < A {}", "A class can't inherit from itself."),
+        (
+            "let B = 1;\nclass A < B {}",
+            "Superclass must be a class, not Int.",
+        ),
+        (
+            "fn B() {}\nclass A < B {}",
+            "Superclass must be a class, not Function.",
+        ),
+        ("print super.x;", "Can't use 'super' outside of a class"),
+        (
+            "class A { fn m() { return super.m(); } }",
+            "Can't use 'super' in a class with no superclass",
+        ),
+        (
+            "class A {}\nclass B < A { fn m() { return super.m; } }\nB().m();",
+            "Undefined method 'm' on superclass 'A'.",
+        ),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", source, err);
+    }
+}
+
+#[test]
+fn len_builtin() -> Result<()> {
+    let source = "\
+print len(\"\"), len(\"hello\"), len(\"a\\tb\");
+let items = [1, [2, 3], \"four\"];
+print len(items), len([]), len(items[1]), len({\"a\": 1, \"b\": 2}), len({:});
+print len;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "0 5 3\n3 0 2 2 0\n<native fn len/1>\n"
+    );
+
+    // Counts characters, not bytes
+    let mut context = Interpreter::new(&mut output);
+    let len = context
+        .environment
+        .get(&Ident::new("len".into(), Span::default()))?;
+    for (text, expect) in [("héllo wörld", 11.0), ("😀", 1.0), ("日本", 2.0)] {
+        let text = Value::Literal(Literal::String(Symbol::string_str(text)));
+        let value = context.call_value(&len, &[text])?;
+        assert_eq!(value, Value::Literal(Literal::Number(expect)));
+    }
+    drop(context);
+
+    for source in ["len(1);", "len(null);", "len((1, 2));", "len();"] {
+        assert!(execute_sample(source, &mut output).is_err(), "{}", source);
+    }
+    let err = execute_sample("len(true);", &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(err.message(), "len expects a string, array or map");
+    Ok(())
+}
+
+#[test]
+fn math_builtins() -> Result<()> {
+    let source = "\
+print sqrt(16), sqrt(2) * sqrt(2) - 2 < 0.000001, sqrt(0), sqrt(-1) == sqrt(-1);
+print pow(2, 10), pow(9, 0.5), pow(2, -1), pow(0, 0);
+print abs(-3.5), abs(3.5), abs(0);
+print floor(2.7), floor(-2.2), ceil(2.2), ceil(-2.7);
+print round(2.4), round(2.5), round(-2.5), round(-0.4);
+print sqrt, pow;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+4 true 0 false
+1024 3 0.5 1
+3.5 3.5 0
+2 -3 3 -2
+2 3 -3 -0
+<native fn sqrt/1> <native fn pow/2>
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        ("sqrt(\"4\");", "sqrt expects a number"),
+        ("abs(null);", "abs expects a number"),
+        ("round(true);", "round expects a number"),
+        ("pow(2, \"x\");", "pow expects numbers"),
+        ("floor();", "Function expected 1 arguments but was given 0"),
+        ("pow(2);", "Function expected 2 arguments but was given 1"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn array_builtins() -> Result<()> {
+    let source = "\
+let numbers = [1, 2, 3, 4];
+let doubled = map(numbers, fn (n) { return n * 2; });
+print doubled, numbers;
+print reduce(numbers, fn (sum, n) { return sum + n; }, 0);
+print filter(numbers, fn (n) { return n % 2 == 0; }), filter([], fn (n) { return true; });
+print map([\"a\", \"b\"], upper), reduce([], fn (a, b) { return a; }, \"empty\");
+let offset = 10;
+print map(numbers, fn (n) { return n + offset; });
+print map(numbers, fn (n) { numbers[0] = 0; return n; }), numbers;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+[2, 4, 6, 8] [1, 2, 3, 4]
+10
+[2, 4] []
+[\"A\", \"B\"] empty
+[11, 12, 13, 14]
+[1, 2, 3, 4] [0, 2, 3, 4]
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message, snippet) in [
+        (
+            "map(1, fn (n) { return n; });",
+            "map expects an array and a function",
+            "map(1, fn (n) { return n; })",
+        ),
+        (
+            "filter([1], 2);",
+            "filter expects an array and a function",
+            "filter([1], 2)",
+        ),
+        (
+            "reduce([1], fn (a, b) { return a; });",
+            "Function expected 3 arguments but was given 2",
+            "reduce([1], fn (a, b) { return a; })",
+        ),
+        (
+            "map([1], fn (a, b) { return a; });",
+            "Function expected 2 arguments but was given 1",
+            "map([1], fn (a, b) { return a; })",
+        ),
+        (
+            "map([1, \"x\"], fn (n) { return n * 2; });",
+            "Left operand must be a number.",
+            "n",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], snippet, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn push_and_pop() -> Result<()> {
+    let source = "\
+let stack = [];
+let alias = stack;
+for (let i = 1; i <= 4; i++) push(stack, i * i);
+print stack, len(alias), typeof(push(stack, null));
+print pop(stack), len(stack);
+let popped = [];
+while (len(stack) > 0) push(popped, pop(stack));
+print popped, stack, pop(stack);
+push(popped, [1]);
+print popped;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+[1, 4, 9, 16] 4 Null
+null 4
+[16, 9, 4, 1] [] null
+[16, 9, 4, 1, [1]]
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        ("push(\"ab\", 1);", "push expects an array"),
+        ("push((1, 2), 3);", "push expects an array"),
+        ("pop({:});", "pop expects an array"),
+        ("push([]);", "Function expected 2 arguments but was given 1"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], &source[..source.len() - 1]);
+    }
+    Ok(())
+}
+
+#[test]
+fn format_builtin() -> Result<()> {
+    let source = "\
+let name = \"Ada\";
+print format(\"{} is {} years old\", name, 36);
+print format(\"{}{}\", [1, \"a\"], null), format(\"no placeholders\");
+print format(\"{{}} {{{}}} }}\", 1.5), len(format(\"{}\", \"\"));
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Ada is 36 years old
+[1, \"a\"]null no placeholders
+{} {1.5} } 0
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        (
+            "format(\"{} and {}\", 1);",
+            "format string has 2 placeholders but was given 1 values",
+        ),
+        (
+            "format(\"{}\", 1, 2);",
+            "format string has 1 placeholders but was given 2 values",
+        ),
+        (
+            "format(\"{x}\");",
+            "Unmatched '{' in format string, write '{{' for a literal brace",
+        ),
+        (
+            "format(\"a } b\");",
+            "Unmatched '}' in format string, write '}}' for a literal brace",
+        ),
+        ("format(1);", "format expects a format string"),
+        ("format();", "format expects a format string"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], &source[..source.len() - 1]);
+    }
+    Ok(())
+}
+
+#[test]
+fn random_builtin() -> Result<()> {
+    let source = "\
+let all_in_range = true;
+for (let i = 0; i < 1000; i++) {
+    let r = random();
+    if (r < 0 or r >= 1) all_in_range = false;
+}
+print all_in_range, random() != random(), random;
+let (a, b, c) = (random(), random(), random());
+(a, b, c);
+";
+    let mut output: Vec<u8> = Vec::new();
+    let mut first = Interpreter::with_seed(&mut output, 42);
+    let sequence = evaluate_sample(source, &mut first)?;
+    drop(first);
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "true true <native fn random/0>\n"
+    );
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut second = Interpreter::with_seed(&mut output, 42);
+    assert_eq!(evaluate_sample(source, &mut second)?, sequence);
+    let mut third = Interpreter::with_seed(&mut output, 43);
+    assert_ne!(evaluate_sample(source, &mut third)?, sequence);
+    Ok(())
+}
+
+#[test]
+fn recursion_depth_limit() -> Result<()> {
+    // Run the way hosts run the interpreter, which the default depth has to fit
+    let source = "fn f() { return 1 + f(); }\nf();";
+    let (message, span) = with_interpreter_stack(|| {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        (err.message().to_string(), err.span().unwrap())
+    });
+    assert_eq!(message, "stack overflow: maximum call depth 1000 exceeded");
+    // The innermost call is the one past the limit
+    assert_eq!(&source[span.start..span.end], "f()");
+    assert_eq!(span.line, 1);
+
+    // The depth unwinds after an error, so the same interpreter can recurse again
+    let source = "fn count(n) { if (n == 0) return 0; return 1 + count(n - 1); }";
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.set_max_depth(5);
+    run_sample(source, &mut context)?;
+    run_sample("print count(4);", &mut context)?;
+    let err = run_sample("count(5);", &mut context).unwrap_err();
+    assert!(
+        err.to_string().contains("maximum call depth 5 exceeded"),
+        "{}",
+        err
+    );
+    run_sample("print count(4);", &mut context)?;
+    context.set_max_depth(20);
+    run_sample("print count(10);", &mut context)?;
+    drop(context);
+    assert_eq!(String::from_utf8_lossy(&output), "4\n4\n10\n");
+    Ok(())
+}
+
+#[test]
+fn tail_calls_run_in_constant_stack() -> Result<()> {
+    let source = "\
+fn sum(n, total) {
+  if (n == 0) return total;
+  return sum(n - 1, total + n);
+}
+fn isEven(n) { if (n == 0) return true; return isOdd(n - 1); }
+fn isOdd(n) { if (n == 0) return false; return isEven(n - 1); }
+fn loop(n) {
+  { let next = n - 1; if (next < 0) return str(n); return loop(next); }
+}
+print sum(100000, 0), isEven(100001), isOdd(100001), loop(50000);
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "5000050000 false true 0\n"
+    );
+
+    // An error deep in a chain of tail calls still reaches the outermost caller
+    let source = "fn f(n) { if (n == 0) return missing(); return f(n - 1); }\nf(3000);";
+    let err = execute_sample(source, &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(err.message(), "Undefined variable 'missing'");
+    let span = err.span().unwrap();
+    assert_eq!(&source[span.start..span.end], "missing");
+    Ok(())
+}
+
+#[test]
+fn string_builtins() -> Result<()> {
+    let source = "\
+let s = \"  Hello, World \\t\";
+print \"[\" + trim(s) + \"]\", \"[\" + trim(\"\") + \"]\";
+print upper(trim(s)), lower(trim(s)), upper(\"abc123_\") == \"ABC123_\";
+let word = \"language\";
+print substring(word, 0, 4), substring(word, 4, 8), \"[\" + substring(word, 3, 3) + \"]\";
+print substring(word, 0, len(word)) == word;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+[Hello, World] []
+HELLO, WORLD hello, world true
+lang uage []
+true
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    // Indices count characters, not bytes
+    let mut context = Interpreter::new(&mut output);
+    let substring = context
+        .environment
+        .get(&Ident::new("substring".into(), Span::default()))?;
+    let upper = context
+        .environment
+        .get(&Ident::new("upper".into(), Span::default()))?;
+    let text = Value::Literal(Literal::String(Symbol::string_str("héllo wörld")));
+    let value = context.call_value(
+        &substring,
+        &[
+            text.to_owned(),
+            Value::Literal(Literal::Number(1.0)),
+            Value::Literal(Literal::Number(8.0)),
+        ],
+    )?;
+    assert_eq!(value.as_str(), "éllo wö");
+    assert_eq!(context.call_value(&upper, &[text])?.as_str(), "HÉLLO WÖRLD");
+    drop(context);
+
+    for (source, message) in [
+        ("upper(1);", "upper expects a string"),
+        ("trim(null);", "trim expects a string"),
+        ("substring([1], 0, 1);", "substring expects a string"),
+        (
+            "substring(\"abc\", \"0\", 1);",
+            "substring expects number indices",
+        ),
+        (
+            "substring(\"abc\", 0.5, 1);",
+            "substring indices must be whole numbers",
+        ),
+        (
+            "substring(\"abc\", 1, 4);",
+            "substring range 1..4 is out of bounds for a string of length 3",
+        ),
+        (
+            "substring(\"abc\", 2, 1);",
+            "substring range 2..1 is out of bounds for a string of length 3",
+        ),
+        (
+            "substring(\"abc\", -1, 1);",
+            "substring range -1..1 is out of bounds for a string of length 3",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn input_builtin() -> Result<()> {
+    let source = "\
+let name = input(\"Name? \");
+print \"Hello, \" + name + \"!\";
+print \"[\" + input() + \"]\", input(), input();
+";
+    let mut output: Vec<u8> = Vec::new();
+    let mut input = "Ada\n\r\nlast line".as_bytes();
+    let mut context = Interpreter::new(&mut output);
+    context.set_input(&mut input);
+    run_sample(source, &mut context)?;
+    drop(context);
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "Name? Hello, Ada!\n[] last line null\n"
+    );
+
+    for (source, message) in [
+        ("input(1);", "input expects the prompt as a string"),
+        (
+            "input(\"a\", \"b\");",
+            "Function expected at most 1 arguments but was given 2",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn write_builtin() -> Result<()> {
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample("write(\"a\"); write(\"b\");", &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "ab");
+
+    let source = "\
+for (i in [1, 2, 3]) write(i);
+write(\"\\n\");
+print typeof(write([\"x\", null])), 4;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "123\n[\"x\", null]Null 4\n"
+    );
+
+    let err = execute_sample("write();", &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(
+        err.message(),
+        "Function expected 1 arguments but was given 0"
+    );
+    Ok(())
+}
+
+#[test]
+fn conversion_builtins() -> Result<()> {
+    let source = "\
+print num(str(3.14)) == 3.14, num(str(-0.5)) + 1, str(num(\"42\")) == \"42\";
+print str(1) + str(2), str(true), str(null), str([1, \"a\"]), str(str) == \"<native fn str/1>\";
+print num(\" 12.5 \") * 2, num(\"1e3\"), num(7), num(\"-8\");
+print num(\"\"), num(\"abc\"), num(\"12abc\"), num(\"inf\"), num(\"NaN\");
+print typeof(str(1)), typeof(num(\"1\"));
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+true 0.5 true
+12 true null [1, \"a\"] true
+25 1000 7 -8
+null null null null null
+String Int
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    let mut input = "20\n".as_bytes();
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.set_input(&mut input);
+    run_sample("print num(input()) + 1;", &mut context)?;
+    drop(context);
+    assert_eq!(String::from_utf8_lossy(&output), "21\n");
+
+    let err = execute_sample("num(true);", &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(err.message(), "num expects a string");
+    Ok(())
+}
+
+#[test]
+fn assert_builtin() -> Result<()> {
+    let source = "\
+print assert(true), assert(1 + 1 == 2, \"math works\"), assert([]), assert(\"\");
+print assert;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "null null null null\n<native fn assert/1>\n"
+    );
+
+    for (source, message) in [
+        ("assert(false);", "assertion failed"),
+        ("assert(null);", "assertion failed"),
+        (
+            "assert(1 > 2, \"one is bigger\");",
+            "assertion failed: one is bigger",
+        ),
+        (
+            "assert(false, 1);",
+            "assert expects the message as a string",
+        ),
+        (
+            "assert();",
+            "Function expected 1 or 2 arguments but was given 0",
+        ),
+        (
+            "assert(true, \"a\", \"b\");",
+            "Function expected 1 or 2 arguments but was given 3",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn time_builtins() -> Result<()> {
+    let source = "\
+let start = clockMillis();
+sleep(5);
+let end = clockMillis();
+print typeof(start), end >= start + 5, end - clock() * 1000 < 1000;
+print formatTime(0, \"%Y-%m-%d %H:%M:%S\");
+print formatTime(1700000000.25, \"%d/%m/%y %H:%M:%S%.3f %A\");
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+Int true true
+1970-01-01 00:00:00
+14/11/23 22:13:20.250 Tuesday
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message) in [
+        (
+            "formatTime(\"0\", \"%Y\");",
+            "formatTime expects a timestamp and a format string",
+        ),
+        ("formatTime(0, \"%Q\");", "Invalid time format '%Q'"),
+        (
+            "formatTime(100000000000000000, \"%Y\");",
+            "Timestamp out of range",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn builtin_errors_at_call_site() {
+    for (source, message, call) in [
+        (
+            "let x = 1;\nprint typeof();",
+            "Function expected 1 arguments but was given 0",
+            "typeof()",
+        ),
+        ("\n\nsqrt(\"4\");", "sqrt expects a number", "sqrt(\"4\")"),
+        (
+            "fn f(a) {}\nlet y = 2;\nf(1, y);",
+            "Function expected 1 arguments but was given 2",
+            "f(1, y)",
+        ),
+        (
+            "class A {}\n\nprint A(1);",
+            "Function expected 0 arguments but was given 1",
+            "A(1)",
+        ),
+        ("assert(1 > 2);", "assertion failed", "assert(1 > 2)"),
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], call, "{}", source);
+        assert_eq!(span.line, source.lines().count(), "{}", source);
+    }
+}
+
+#[test]
+fn int_and_float_arithmetic() -> Result<()> {
+    let source = "\
+print 10 / 3, 10.0 / 3, 10 / 3.0, 7 % 3, -7 / 2;
+print 2 ** 10, 2 ** -1, 1 + 0.5, 3 * 1.5, 0.1 + 0.2 > 0.3;
+print typeof(1), typeof(1.0), typeof(1 + 1), typeof(1 + 1.0), typeof(len(\"ab\"));
+print 1 == 1.0, 2 < 2.5, 3 >= 3, 1 is Int, 1.5 is Int, 1 is Number;
+let i = 0;
+i++;
+print i, typeof(i), typeof(num(\"7\")), typeof(num(\"7.5\"));
+let m = {1: \"one\"};
+print m[1.0], [\"a\", \"b\"][1.0];
+print 9223372036854775807, typeof(9223372036854775808);
+print 0xff + 0o10 + 0b11, typeof(0x10), 0b1010 == 10;
+print 7 ~/ 2, -7 ~/ 2, 7 ~/ -2, -7 ~/ -2, 6 ~/ 3, 7.5 ~/ 2, -7.5 ~/ 2, typeof(7.0 ~/ 2);
+print 1 + 9 ~/ 2 * 2, 10 ~/ 3 // comments still start with two slashes
+;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+3 3.3333333333333335 3.3333333333333335 1 -3
+1024 0.5 1.5 4.5 true
+Int Number Int Number Int
+true true true true false true
+1 Int Int Number
+one b
+9223372036854775807 Number
+266 Int true
+3 -4 -4 3 2 3 -4 Number
+9 3
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message, operation) in [
+        (
+            "9223372036854775807 + 1;",
+            "Integer overflow.",
+            "9223372036854775807 + 1",
+        ),
+        (
+            "-9223372036854775807 - 2;",
+            "Integer overflow.",
+            "-9223372036854775807 - 2",
+        ),
+        ("2 ** 63;", "Integer overflow.", "2 ** 63"),
+        ("let x = 1;\nx / 0;", "Division by zero.", "x / 0"),
+        ("let x = 1;\nx ~/ 0;", "Division by zero.", "x ~/ 0"),
+        (
+            "let m = -9223372036854775807 - 1;\nm ~/ -1;",
+            "Integer overflow.",
+            "m ~/ -1",
+        ),
+        (
+            "let x = 1;\nlet z = 0;\nx % z;",
+            "Division by zero.",
+            "x % z",
+        ),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], operation, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn bitwise_operators() -> Result<()> {
+    let source = "\
+let flags = 12;
+print flags & 10, flags | 3, flags ^ 5, 1 << 4, -16 >> 2, 6.0 & 3;
+print 1 << 63, 1 << 64 - 1 == 1 << 63, 5 & 4 == 4, 1 | 2 ^ 3 & 1, typeof(6.0 & 3);
+print ~0, ~flags, ~~flags, ~1.9, ~-1.9, typeof(~0.5), flags & ~4;
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    let expect = "\
+8 15 9 16 -4 2
+-9223372036854775808 true true 3 Int
+-1 -13 12 -2 0 Int 8
+";
+    assert_eq!(String::from_utf8_lossy(&output), expect);
+
+    for (source, message, operation) in [
+        ("1.5 & 2;", "Operands must be two integers.", "1.5 & 2"),
+        (
+            "let x = 0.5;\n1 | x;",
+            "Operands must be two integers.",
+            "1 | x",
+        ),
+        ("\"a\" ^ 1;", "Left operand must be a number.", "\"a\""),
+        (
+            "1 << 64;",
+            "Shift amount must be between 0 and 63.",
+            "1 << 64",
+        ),
+        (
+            "1 >> -1;",
+            "Shift amount must be between 0 and 63.",
+            "1 >> -1",
+        ),
+        ("~\"a\";", "Unary operand must be numeric.", "~\"a\""),
+        ("~true;", "Unary operand must be numeric.", "~true"),
+    ] {
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        let span = err.span().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        assert_eq!(&source[span.start..span.end], operation, "{}", source);
+    }
+    Ok(())
+}
+
+#[test]
+fn const_declarations() -> Result<()> {
+    let source = "\
+const limit = 3;
+fn over(n) { return n > limit; }
+{
+  let limit = 10;
+  limit = limit + 1;
+  print limit;
+}
+print limit, over(4);
+";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample(source, &mut output)?;
+    assert_eq!(String::from_utf8_lossy(&output), "11\n3 true\n");
+
+    for source in [
+        "print 1;\nconst x = 1;\nx = 2;",
+        "const x = 1;\nfn f() { x += 1; }",
+        "{ const x = 1; { x++; } }",
+        "fn f() { x = 2; }\nconst x = 1;\nf();\nprint x;",
+    ] {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err().to_string();
+        assert!(
+            err.contains("ResolveError: Can't assign to constant 'x'."),
+            "{}",
+            err
+        );
+        assert!(output.is_empty(), "{}", source);
+    }
+
+    let err = execute_sample("const x;", &mut output).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Expected '=' after constant name."));
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    context.eval("const port = 80;")?;
+    let err = context.eval("port = 81;").unwrap_err().to_string();
+    assert!(err.contains("Can't assign to constant 'port'."), "{}", err);
+    context.eval("let port = 81; port = 82;")?;
+    assert_eq!(context.eval("port;")?, Value::Literal(Literal::Int(82)));
+    Ok(())
+}
+
+#[test]
+fn runtime_error_backtrace() -> Result<()> {
+    let source = "\
+fn inner(x) {
+  let y = -x;
+  return y;
+}
+fn outer(x) {
+  let r = inner(x);
+  return r;
+}
+fn tail(x) { return inner(x); }
+print outer(1);
+";
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    run_sample(source, &mut context)?;
+
+    let backtrace = |source: &str, context: &mut Interpreter| {
+        let err = run_sample(source, context).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap().clone();
+        let frames = err
+            .backtrace()
+            .iter()
+            .map(|frame| (frame.name.clone(), frame.span.line))
+            .collect::<Vec<_>>();
+        (err, frames)
+    };
+
+    // Innermost call first, each with the line it was called from
+    let (err, frames) = backtrace("\n\nouter(\"a\");", &mut context);
+    assert_eq!(err.span().unwrap().line, 2);
+    assert_eq!(
+        frames,
+        [("<fn inner/1>".into(), 6), ("<fn outer/1>".into(), 3)]
+    );
+    assert_eq!(
+        err.to_string(),
+        "[line 2] RuntimeError: Unary operand must be numeric.
+  in <fn inner/1>, called from line 6
+  in <fn outer/1>, called from line 3
+"
+    );
+
+    // A tail call takes over its caller's frame
+    let (_, frames) = backtrace("tail(\"a\");", &mut context);
+    assert_eq!(frames, [("<fn inner/1>".into(), 9)]);
+
+    // Callbacks show the builtin that called them
+    let (_, frames) = backtrace("map([\"a\"], fn(v) { return -v; });", &mut context);
+    assert_eq!(
+        frames,
+        [("<lambda/1>".into(), 1), ("<native fn map/2>".into(), 1)]
+    );
+
+    // Errors outside any function have none, and the stack unwinds after each error
+    let (err, frames) = backtrace("-\"a\";", &mut context);
+    assert!(frames.is_empty());
+    assert_eq!(
+        err.to_string(),
+        "[line 1] RuntimeError: Unary operand must be numeric.\n"
+    );
+
+    // Recursion is shown once with a count
+    context.set_max_depth(5);
+    run_sample("fn down(n) {\n  return 1 + down(n - 1);\n}", &mut context)?;
+    let (err, frames) = backtrace("down(3);", &mut context);
+    assert_eq!(frames.len(), 5);
+    assert_eq!(
+        err.to_string(),
+        "[line 2] RuntimeError: stack overflow: maximum call depth 5 exceeded
+  in <fn down/1>, called from line 2
+  ... repeated 3 more times
+  in <fn down/1>, called from line 1
+"
+    );
+    drop(context);
+    assert_eq!(String::from_utf8_lossy(&output), "-1\n");
+    Ok(())
+}
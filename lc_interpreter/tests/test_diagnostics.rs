@@ -0,0 +1,192 @@
+mod common;
+
+use common::execute_sample;
+use lc_core::*;
+use lc_interpreter::*;
+
+#[test]
+fn error_phase_labels() {
+    let source = "\
+let a = 1 @;
+let b = ;
+{
+    let c = c;
+}
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    let err = execute_sample(source, &mut output).unwrap_err().to_string();
+    assert!(err.contains("[line 1] LexError: Unexpected character @"));
+    assert!(err.contains("[line 2] ParseError: Expected expression."));
+    assert!(
+        err.contains("[line 4] ResolveError: Can't read local variable in its own initializer.")
+    );
+}
+
+#[test]
+fn operand_error_spans() {
+    let cases = [
+        (
+            "print 1 + \"x\";",
+            "Right operand must be a number.",
+            10..13,
+        ),
+        ("print null * 2;", "Left operand must be a number.", 6..10),
+        (
+            "print (1 + 2) / true;",
+            "Right operand must be a number.",
+            16..20,
+        ),
+    ];
+    for (source, message, range) in cases {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+        let span = err.span().unwrap();
+        assert_eq!(span.start..span.end, range, "{}", source);
+    }
+}
+
+fn resolve_warnings(source: &str) -> Vec<SpannedError> {
+    let mut output: Vec<u8> = Vec::new();
+    let mut context = Interpreter::new(&mut output);
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(!errors.has_errors(), "{}", errors);
+    let (_, errors) = Resolver::new(&mut context).resolve(&statements);
+    assert!(!errors.has_errors(), "{}", errors);
+    errors.warnings().to_vec()
+}
+
+#[test]
+fn assignment_condition_warning() {
+    let source = "\
+let x = 1;
+let p = null;
+if (x = 5) print x;
+while (x = false) {}
+print if (x += 1) { 1 } else { 2 };
+    ";
+    let warnings = resolve_warnings(source);
+    let lines: Vec<usize> = warnings.iter().map(|w| w.span.line).collect();
+    assert_eq!(lines, [3, 4, 5]);
+    for warning in &warnings {
+        assert_eq!(
+            warning.message,
+            "Assignment used as a condition. Did you mean '=='?"
+        );
+        assert_eq!(warning.phase, Phase::Resolve);
+    }
+    assert_eq!(
+        &source[warnings[0].span.start..warnings[0].span.end],
+        "x = 5"
+    );
+
+    // Warnings don't stop the program from running
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample("let x = 1; if (x = 5) print x;", &mut output).unwrap();
+    assert_eq!(output, b"5\n");
+}
+
+#[test]
+fn assignment_condition_no_warning() {
+    let source = "\
+fn input() { return null; }
+let line = 1;
+let x = 1;
+while ((line = input()) != null) {}
+if ((x = 2)) print x;
+if (x == 5) print x;
+if (x) x = 3;
+    ";
+    assert!(resolve_warnings(source).is_empty());
+}
+
+#[test]
+fn unused_local_warnings() {
+    let source = "\
+let unused_global = 1;
+fn f(unused_param) {
+  let a = 1;
+  let (b, c) = (2, 3);
+  const d = c;
+  fn helper() {}
+  for (e in [1]) print d;
+  for (_ in [1]) {}
+  return fn g() { let h = a; };
+}
+class P { fn init(x) { this.x = x; } fn get() { return this.x; } }
+{ let i = [0]; i[0]++; let j = 0; j += 1; }
+";
+    let warnings = resolve_warnings(source);
+    let messages: Vec<String> = warnings.iter().map(|w| w.message.clone()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Local 'b' is never used.",
+            "Local 'helper' is never used.",
+            "Local 'e' is never used.",
+            "Local 'h' is never used.",
+        ]
+    );
+    let lines: Vec<usize> = warnings.iter().map(|w| w.span.line).collect();
+    assert_eq!(lines, [4, 6, 7, 9]);
+    assert_eq!(&source[warnings[3].span.start..warnings[3].span.end], "h");
+    assert!(warnings.iter().all(|w| w.phase == Phase::Resolve));
+
+    // A call to a function declared later in the same scope is a use of it
+    let source = "{\n  fn ev(n) { return n == 0 or od(n - 1); }\n  fn od(n) { return n != 0 and ev(n - 1); }\n  ev(2);\n}";
+    assert!(resolve_warnings(source).is_empty());
+
+    // Unused locals don't stop the program from running
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample("{ let x = 1; print 2; }", &mut output).unwrap();
+    assert_eq!(output, b"2\n");
+}
+
+#[test]
+fn undefined_variable_suggestions() {
+    let cases = [
+        (
+            "let length = 3;\nprint lenght;",
+            "Undefined variable 'lenght'. Did you mean 'length'?",
+        ),
+        (
+            "fn f() { let counter = 0; counter = countr + 1; }\nf();",
+            "Undefined variable 'countr'. Did you mean 'counter'?",
+        ),
+        (
+            "let total = 1;\ntotl = 2;",
+            "Undefined variable 'totl'. Did you mean 'total'?",
+        ),
+        (
+            "print typof(1);",
+            "Undefined variable 'typof'. Did you mean 'typeof'?",
+        ),
+        (
+            "let length = 3;\nprint width;",
+            "Undefined variable 'width'",
+        ),
+    ];
+    for (source, message) in cases {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.message(), message, "{}", source);
+    }
+}
+
+#[test]
+fn for_increment_error_line() {
+    let cases = [
+        ("for (let i = 0; i < 3;\n  i = i + \"x\")\n{\n}", 2),
+        ("let s = \"a\";\nfor (let i = 0; i < 3;\n  s -= 1) {}", 3),
+        ("let s = true;\nfor (let i = 0;\n  i < 3;\n  s++) {}", 4),
+    ];
+    for (source, increment_line) in cases {
+        let mut output: Vec<u8> = Vec::new();
+        let err = execute_sample(source, &mut output).unwrap_err();
+        let err = err.downcast_ref::<RuntimeError>().unwrap();
+        assert_eq!(err.span().unwrap().line, increment_line, "{}", source);
+    }
+}
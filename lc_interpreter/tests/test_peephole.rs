@@ -0,0 +1,89 @@
+use lc_core::*;
+use lc_interpreter::*;
+use OpCode::*;
+
+fn assert_optimized(code: Vec<OpCode>, expect: Vec<OpCode>) {
+    let optimized = optimize(code.into());
+    assert_eq!(optimized.code, expect);
+    assert_eq!(optimized.spans.len(), optimized.code.len());
+}
+
+#[test]
+fn peephole_untouched() {
+    let code = vec![
+        Constant(Literal::Bool(true)),
+        JumpIfFalse(4),
+        Pop,
+        Print(1),
+        Pop,
+    ];
+    assert_optimized(code.clone(), code);
+}
+
+#[test]
+fn peephole_jump_chain() {
+    assert_optimized(
+        vec![
+            Constant(Literal::Bool(true)),
+            JumpIfFalse(4),
+            Pop,
+            Print(1),
+            Jump(5),
+            Jump(7),
+            Constant(Literal::Null),
+            Pop,
+        ],
+        vec![
+            Constant(Literal::Bool(true)),
+            JumpIfFalse(4),
+            Pop,
+            Print(1),
+            Pop,
+        ],
+    );
+}
+
+#[test]
+fn peephole_dead_code() {
+    assert_optimized(
+        vec![
+            Jump(2),
+            Constant(Literal::Number(1.0)),
+            Jump(4),
+            Constant(Literal::Number(2.0)),
+            Print(1),
+        ],
+        vec![Print(1)],
+    );
+}
+
+#[test]
+fn peephole_loop() {
+    // while (cond) { if (x) {} } leaves the inner else-jump pointing at the loop's back-edge
+    assert_optimized(
+        vec![
+            GetGlobal(Symbol::ident_str("cond")),
+            JumpIfFalse(9),
+            Pop,
+            GetGlobal(Symbol::ident_str("x")),
+            JumpIfFalse(7),
+            Pop,
+            Jump(8),
+            Pop,
+            Jump(0),
+            Pop,
+        ],
+        vec![
+            GetGlobal(Symbol::ident_str("cond")),
+            JumpIfFalse(9),
+            Pop,
+            GetGlobal(Symbol::ident_str("x")),
+            JumpIfFalse(7),
+            Pop,
+            Jump(0),
+            Pop,
+            Jump(0),
+            Pop,
+        ],
+    );
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use anyhow::Result;
+use common::execute_sample;
+
+/// Each case is evaluated as `print <expression>;` with `a = 1` and `b = true` in scope.
+const CASES: &[(&str, &str)] = &[
+    ("-2 ** 2", "-4"),
+    ("(-2) ** 2", "4"),
+    ("2 ** 3 ** 2", "512"),
+    ("(2 ** 3) ** 2", "64"),
+    ("2 ** -1", "0.5"),
+    ("-2 ** 2 * 3", "-12"),
+    ("2 * 3 ** 2", "18"),
+    ("-3 * 2", "-6"),
+    ("- -3", "3"),
+    ("1 + 2 * 3", "7"),
+    ("10 - 2 - 3", "5"),
+    ("12 / 3 / 2", "2"),
+    ("1 + 7 % 4", "4"),
+    ("7 % 4 * 2", "6"),
+    ("2 * 7 % 4", "2"),
+    ("-7 % 3", "-1"),
+    ("7.5 % 2", "1.5"),
+    ("5.0 % 0", "NaN"),
+    ("!a == b", "false"),
+    ("!(a == b)", "true"),
+    ("1 < 2 == true", "true"),
+    ("false and true or true", "true"),
+    ("b ? 1 : 2", "1"),
+    ("a > 1 ? \"big\" : a == 1 ? \"one\" : \"small\"", "one"),
+    ("false or b ? a + 1 : a - 1", "2"),
+    ("(b ? 1 : 2) + 10", "11"),
+    ("b ? 1 : 2 + 10", "1"),
+    ("1 + 1 << 2", "8"),
+    ("1 << 2 < 5", "true"),
+    ("6 & 3 == 2", "true"),
+    ("1 | 6 ^ 3 & 5", "7"),
+    ("(1 | 6) ^ 3", "4"),
+    ("a & 1 and b", "true"),
+    ("~0", "-1"),
+    ("~a + 1", "-1"),
+    ("-~a", "2"),
+    ("~2 ** 2", "-5"),
+    ("~a & 3", "2"),
+];
+
+#[test]
+fn precedence_table() -> Result<()> {
+    for (expression, expect) in CASES {
+        let source = format!("let a = 1;\nlet b = true;\nprint {};", expression);
+        let mut output: Vec<u8> = Vec::new();
+        execute_sample(&source, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            format!("{}\n", expect),
+            "{}",
+            expression
+        );
+    }
+    Ok(())
+}
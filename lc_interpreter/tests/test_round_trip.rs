@@ -0,0 +1,98 @@
+use lc_core::*;
+
+const SAMPLES: &str = include_str!("test_code_samples.rs");
+
+/// Pulls the `let source = "\ ... ";` programs out of the code samples test file.
+fn sample_sources() -> Vec<String> {
+    let mut sources = Vec::new();
+    let mut lines = SAMPLES.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() != "let source = \"\\" {
+            continue;
+        }
+        let mut escaped = String::new();
+        for line in lines.by_ref() {
+            match line.strip_suffix("\";") {
+                Some(last) if !last.ends_with('\\') => {
+                    escaped.push_str(last);
+                    break;
+                }
+                _ => {
+                    escaped.push_str(line);
+                    escaped.push('\n');
+                }
+            }
+        }
+        sources.push(unescape(&escaped));
+    }
+    sources
+}
+
+fn unescape(escaped: &str) -> String {
+    let mut source = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            source.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => source.push('\n'),
+            Some('t') => source.push('\t'),
+            Some(other) => source.push(other),
+            None => {}
+        }
+    }
+    source
+}
+
+fn parse(source: &str) -> Option<Vec<Stmt>> {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    if !errors.issues().is_empty() {
+        return None;
+    }
+    let (statements, errors) = Parser::new(tokens).parse();
+    errors.issues().is_empty().then_some(statements)
+}
+
+#[test]
+fn round_trip_code_samples() {
+    let sources = sample_sources();
+    assert!(sources.len() > 40);
+    for source in sources {
+        // Some samples exercise parse errors
+        let Some(statements) = parse(&source) else {
+            continue;
+        };
+        let printed: Vec<String> = statements.iter().map(Stmt::to_string).collect();
+        let printed = printed.join("\n");
+        let reparsed = parse(&printed)
+            .unwrap_or_else(|| panic!("printed source doesn't parse:\n{}\n", printed));
+        assert_eq!(
+            ast_to_string(&reparsed),
+            ast_to_string(&statements),
+            "\n{}\n",
+            printed
+        );
+    }
+}
+
+#[test]
+fn round_trip_formatted_code_samples() {
+    for source in sample_sources() {
+        let Some(statements) = parse(&source) else {
+            continue;
+        };
+        let printed = to_source(&statements);
+        let reparsed = parse(&printed)
+            .unwrap_or_else(|| panic!("formatted source doesn't parse:\n{}\n", printed));
+        assert_eq!(
+            ast_to_string(&reparsed),
+            ast_to_string(&statements),
+            "\n{}\n",
+            printed
+        );
+        // Formatting is idempotent
+        assert_eq!(to_source(&reparsed), printed);
+    }
+}
@@ -0,0 +1,274 @@
+mod common;
+
+use anyhow::Result;
+use common::execute_sample;
+use lc_core::*;
+use lc_interpreter::*;
+
+fn execute_sample_vm(source: &str, output: &mut Vec<u8>) -> Result<()> {
+    let mut context = Interpreter::new(output);
+    let mut issues = TranslationErrors::new();
+
+    // Lexing
+    let mut scanner = Scanner::new(source.to_string());
+    let (tokens, mut errs) = scanner.scan_tokens();
+    issues.merge(&mut errs);
+
+    // Parsing
+    let mut parser = Parser::new(tokens);
+    let (statements, mut errs) = parser.parse();
+    issues.merge(&mut errs);
+
+    // Resolving and binding
+    let mut resolver = Resolver::new(&mut context);
+    let (_, mut errs) = resolver.resolve(&statements);
+    issues.merge(&mut errs);
+
+    // Compilation and execution
+    issues.check()?;
+    let chunk = Compiler::new().compile(&statements)?;
+    Vm::new(output).run(&chunk)?;
+    Ok(())
+}
+
+fn assert_same_output(source: &str) -> Result<()> {
+    let mut expect: Vec<u8> = Vec::new();
+    execute_sample(source, &mut expect)?;
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample_vm(source, &mut output)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        String::from_utf8_lossy(&expect)
+    );
+    Ok(())
+}
+
+#[test]
+fn vm_block_scope() -> Result<()> {
+    assert_same_output(
+        "\
+let x = \"outside\";
+{
+    let x = \"first\";
+    print x;
+}
+{
+    let x = \"second\";
+    print x;
+}
+print x;
+    ",
+    )
+}
+
+#[test]
+fn vm_mixed_scope() -> Result<()> {
+    assert_same_output(
+        "\
+let x = \"outside\";
+{
+    let y = \"inside\";
+    print x + y;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_shadowing() -> Result<()> {
+    assert_same_output(
+        "\
+let a = \"global a\";
+let b = \"global b\";
+let c = \"global c\";
+{
+    let a = \"outer a\";
+    let b = \"outer b\";
+    {
+        let a = \"inner a\";
+        print a;
+        print b;
+        print c;
+    }
+    print a;
+    print b;
+    print c;
+}
+print a;
+print b;
+print c;
+    ",
+    )
+}
+
+#[test]
+fn vm_loops() -> Result<()> {
+    assert_same_output(
+        "\
+let x = 0;
+while (x < 5) {
+    print x;
+    x++;
+}
+for (let i = 0; i < 5; i += 2) {
+    print i * 10;
+}
+do {
+    print x;
+    x = x - 2;
+} while (x > 0);
+do print x; while (false);
+    ",
+    )
+}
+
+#[test]
+fn vm_expressions() -> Result<()> {
+    assert_same_output(
+        "\
+let x = 1;
+let y = 2;
+print x + y;
+print -(x - y) * 4 / 2;
+print !(x == y) and x != y;
+print 1 < x + 1 <= 2;
+let z = if (x > y) \"x\" else { let w = \"y\"; w };
+print z;
+if (z == \"y\") print \"then\"; else print \"else\";
+let x = \"after\";
+print x;
+    ",
+    )
+}
+
+#[test]
+fn vm_print_multiple() -> Result<()> {
+    assert_same_output(
+        "\
+let x = 1;
+{
+    let y = \"two\";
+    print x, y, x + 2;
+}
+print x;
+    ",
+    )
+}
+
+#[test]
+#[should_panic]
+fn vm_unsupported_function() {
+    let source = "\
+fn f() {}
+    ";
+    let mut output: Vec<u8> = Vec::new();
+    execute_sample_vm(source, &mut output).unwrap();
+}
+
+#[test]
+fn vm_undefined_variable_suggestion() {
+    let mut output: Vec<u8> = Vec::new();
+    let err = execute_sample_vm("let length = 3;\nprint lenght;", &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(
+        err.message(),
+        "Undefined variable 'lenght'. Did you mean 'length'?"
+    );
+    let err = execute_sample_vm("let length = 3;\nwidth = 1;", &mut output).unwrap_err();
+    let err = err.downcast_ref::<RuntimeError>().unwrap();
+    assert_eq!(err.message(), "Undefined variable 'width'");
+}
+
+#[test]
+fn vm_string_interpolation() -> Result<()> {
+    assert_same_output(
+        "\
+let name = \"Ada\";
+{
+    let n = 3;
+    print \"${name} has ${n * 2} ${\"items${\"!\"}\"}\";
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_is_type_checks() -> Result<()> {
+    assert_same_output(
+        "\
+let n = 1;
+{
+    let s = \"one\";
+    print n is Number, s is String, n is String, null is Null, 1 < 2 is Bool;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_string_concatenation() -> Result<()> {
+    assert_same_output(
+        "\
+let n = 5;
+{
+    let done = true;
+    print \"count: \" + n, \"done: \" + done, \"x is \" + null, \"\" + 1 + 2;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_for_increment_after_body_scope() -> Result<()> {
+    assert_same_output(
+        "\
+for (let i = 0; i < 3; i = i + 1) {
+    let i = \"shadow\";
+    print i;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_ternary() -> Result<()> {
+    assert_same_output(
+        "\
+let a = 2;
+{
+    let b = a > 1 ? \"big\" : a == 1 ? \"one\" : \"small\";
+    print b, a < 1 ? 1 : 0;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_string_comparison() -> Result<()> {
+    assert_same_output(
+        "\
+let a = \"apple\";
+{
+    let b = \"banana\";
+    print a < b, a >= b, \"app\" < a, a <= a, \"Zebra\" < a;
+}
+    ",
+    )
+}
+
+#[test]
+fn vm_int_arithmetic() -> Result<()> {
+    assert_same_output(
+        "\
+let a = 10;
+{
+    let b = 3;
+    print a / b, a / 3.0, a % b, -a / 4, 2 ** a, 2 ** -1, a + 0.5;
+    print a > b, b < 3.5, a == 10.0, a / b is Int, a * 1.0 is Int;
+    print a & b, a | b, a ^ b, a << b, -a >> 1, 1 | 6 ^ 3 & 5, (4.0 & b) is Int;
+    print ~0, ~a, ~~b, ~-2.7, ~b is Int;
+    print a ~/ b, -a ~/ b, a ~/ -4.0, a ~/ b is Int;
+}
+    ",
+    )
+}